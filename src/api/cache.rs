@@ -8,6 +8,8 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use crate::api::types::JlcPart;
+use crate::atomic;
+use crate::config;
 
 /// Cached part data with a time-to-live based on file modification time.
 pub struct PartCache {
@@ -24,13 +26,10 @@ impl Default for PartCache {
 impl PartCache {
     /// Create a new part cache.
     ///
-    /// Cache location: `~/.pcb/jlcpcb/parts/`, TTL: 24 hours.
+    /// Cache location: `~/.pcb/jlcpcb/parts/` (or `parts/` under the
+    /// configured cache dir), TTL: 24 hours.
     pub fn new() -> Self {
-        let cache_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".pcb")
-            .join("jlcpcb")
-            .join("parts");
+        let cache_dir = config::cache_dir().join("parts");
 
         Self {
             cache_dir,
@@ -50,6 +49,14 @@ impl PartCache {
             return None;
         }
 
+        self.load_any(lcsc)
+    }
+
+    /// Load a cached part regardless of its age.
+    ///
+    /// Used when working offline, where a stale record beats no record.
+    pub fn load_any(&self, lcsc: &str) -> Option<JlcPart> {
+        let path = self.cache_dir.join(format!("{}.json", lcsc));
         let content = fs::read_to_string(&path).ok()?;
         serde_json::from_str(&content).ok()
     }
@@ -87,7 +94,7 @@ impl PartCache {
 
         let path = self.cache_dir.join(format!("{}.json", lcsc));
         if let Ok(content) = serde_json::to_string_pretty(part) {
-            let _ = fs::write(&path, content);
+            let _ = atomic::write_file(&path, content);
         }
     }
 }
@@ -1,12 +1,13 @@
 //! JLCPCB/LCSC API client.
 
-use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use super::cache::PartCache;
+use super::lcsc::normalize_lcsc;
 use super::types::{JlcPart, PartAttributes, PriceBreak};
 
 /// JLCPCB API endpoint for component search.
@@ -17,6 +18,9 @@ const JLCPCB_SEARCH_URL: &str =
 const JLCPCB_DETAIL_URL: &str =
     "https://cart.jlcpcb.com/shoppingCart/smtGood/getComponentDetail";
 
+/// Page size used when an exact lookup's first, small page misses the part.
+const LOOKUP_FALLBACK_PAGE_SIZE: i32 = 10;
+
 /// Secret key required by JLCPCB API.
 const JLCPCB_SECRET_KEY: &str = "64656661756c744b65794964";
 
@@ -25,6 +29,8 @@ pub struct JlcpcbClient {
     client: Client,
     part_cache: PartCache,
     use_cache: bool,
+    write_cache: bool,
+    strict_lcsc: bool,
 }
 
 /// Library type filter for parts search.
@@ -150,6 +156,10 @@ where
 }
 
 /// JLCPCB component from API response.
+///
+/// Only the fields used here are listed. The search response carries no
+/// date for when a part was added to the library, so results can't be
+/// sorted by recency.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct JlcpcbComponent {
@@ -180,6 +190,11 @@ struct JlcpcbComponent {
     /// Price info
     #[serde(default)]
     component_prices: Vec<JlcpcbPrice>,
+    /// Minimum order quantity (`leastNumber`). Not every response carries
+    /// it, so a missing value just means no minimum is known. The response
+    /// has no reel/cut-tape packaging field.
+    #[serde(default)]
+    least_number: Option<i32>,
     /// Datasheet URL
     #[serde(default)]
     datasheet_url: Option<String>,
@@ -234,6 +249,7 @@ impl From<JlcpcbComponent> for JlcPart {
             description: c.describe,
             stock: c.stock_count,
             price_breaks,
+            min_order: c.least_number.filter(|&n| n > 1),
             datasheet: c.datasheet_url.filter(|s| !s.is_empty()),
             basic: is_basic,
             preferred: is_preferred,
@@ -242,20 +258,50 @@ impl From<JlcpcbComponent> for JlcPart {
     }
 }
 
+/// Metric chip size codes and the imperial names JLCPCB lists packages by.
+const METRIC_CHIP_SIZES: [(&str, &str); 8] = [
+    ("0603", "0201"),
+    ("1005", "0402"),
+    ("1608", "0603"),
+    ("2012", "0805"),
+    ("3216", "1206"),
+    ("3225", "1210"),
+    ("5025", "2010"),
+    ("6332", "2512"),
+];
+
 /// Extract package size from description (e.g., "0402", "0603", "0805", "SOT-23")
 fn extract_package_from_description(desc: &str) -> String {
     use regex::Regex;
 
+    // Chip sizes explicitly marked metric, e.g. "1608 Metric". Checked first
+    // since "0603 Metric" is an imperial 0201.
+    let metric = Regex::new(r"\b(\d{4})\s*Metric\b").unwrap();
+    if let Some(cap) = metric.captures(desc) {
+        if let Some((_, imperial)) = METRIC_CHIP_SIZES.iter().find(|(m, _)| *m == &cap[1]) {
+            return imperial.to_string();
+        }
+    }
+
     // Match common SMD package sizes
+    let chip = Regex::new(r"\b(0201|0402|0603|0805|1206|1210|2010|2512)\b").unwrap();
+    if let Some(cap) = chip.captures(desc) {
+        return cap[1].to_string();
+    }
+
+    // IC and discrete packages, most specific first
     let patterns = [
-        r"\b(0201|0402|0603|0805|1206|1210|2010|2512)\b",
+        r"\b(TO-\d+(?:-\d+)?)\b",
+        r"\b(D2PAK|DPAK)\b",
         r"\b(SOT-\d+[A-Z]?)\b",
+        r"\b(WSON-\d+)\b",
+        r"\b(DFN-\d+)\b",
+        r"\b(TSSOP-\d+)\b",
+        r"\b(LQFP-\d+)\b",
         r"\b(QFN-\d+)\b",
         r"\b(QFP-\d+)\b",
         r"\b(SOIC-\d+)\b",
         r"\b(SOP-\d+)\b",
-        r"\b(TSSOP-\d+)\b",
-        r"\b(LQFP-\d+)\b",
     ];
 
     for pattern in patterns {
@@ -278,8 +324,7 @@ impl Default for JlcpcbClient {
 impl JlcpcbClient {
     /// Create a new API client.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+        let client = crate::http::client_builder()
             .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
             .build()
             .expect("Failed to create HTTP client");
@@ -288,6 +333,8 @@ impl JlcpcbClient {
             client,
             part_cache: PartCache::new(),
             use_cache: true,
+            write_cache: true,
+            strict_lcsc: false,
         }
     }
 
@@ -297,6 +344,24 @@ impl JlcpcbClient {
         self
     }
 
+    /// Configure whether fetched parts are written back to the part cache.
+    ///
+    /// Independent of [`Self::with_cache`], so a read-only filesystem can
+    /// still serve cached parts.
+    pub fn with_cache_writes(mut self, write_cache: bool) -> Self {
+        self.write_cache = write_cache;
+        self
+    }
+
+    /// Require looked-up parts to carry exactly the requested LCSC code.
+    ///
+    /// By default a result whose code differs only in case or leading zeros
+    /// (`c0123` for `C123`) is accepted; strict lookups fail on it instead.
+    pub fn with_strict_lcsc(mut self, strict_lcsc: bool) -> Self {
+        self.strict_lcsc = strict_lcsc;
+        self
+    }
+
     /// Search for parts by keyword (all parts).
     pub fn search(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
         self.search_with_filter(keyword, page, page_size, LibraryType::All)
@@ -325,6 +390,7 @@ impl JlcpcbClient {
     ) -> Result<SearchPage> {
         let request_body = JlcpcbSearchRequest::new(keyword, page, page_size, library_type);
 
+        let started = Instant::now();
         let response = self
             .client
             .post(JLCPCB_SEARCH_URL)
@@ -341,8 +407,8 @@ impl JlcpcbClient {
             anyhow::bail!("Search request failed: {}", response.status());
         }
 
-        let search_response: JlcpcbSearchResponse =
-            response.json().context("Failed to parse search response")?;
+        let search_response: JlcpcbSearchResponse = crate::http::read_json(response, "search", started)
+            .context("Failed to parse search response")?;
 
         if search_response.code != 200 {
             anyhow::bail!(
@@ -368,8 +434,15 @@ impl JlcpcbClient {
 
     /// Get a single part by LCSC part number.
     ///
-    /// Results are cached on disk for 24 hours unless caching is disabled.
+    /// Results are cached on disk for 24 hours unless caching (or cache
+    /// writes) are disabled.
     pub fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        let lcsc = match normalize_lcsc(lcsc) {
+            Some(code) => code,
+            None => return Ok(None),
+        };
+        let lcsc = lcsc.as_str();
+
         // Check cache first
         if self.use_cache {
             if let Some(cached) = self.part_cache.load(lcsc) {
@@ -377,12 +450,22 @@ impl JlcpcbClient {
             }
         }
 
-        // Search by exact LCSC part number
-        let parts = self.search(lcsc, 1, 10)?;
-        let result = parts.into_iter().find(|p| p.lcsc == lcsc);
+        // Search by exact LCSC part number. The exact match almost always
+        // ranks first, so a small page keeps the payload down; only look
+        // further when it didn't and there are more results. The detail
+        // endpoint is keyed by code but has no stock or prices, so it can't
+        // replace the search.
+        let page_size = crate::http::parts_per_request();
+        let page = self.search_page(lcsc, 1, page_size, LibraryType::All)?;
+        let more = page.total > i64::from(page_size);
+        let mut result = find_lcsc(page.parts, lcsc, self.strict_lcsc)?;
+        if result.is_none() && more && page_size < LOOKUP_FALLBACK_PAGE_SIZE {
+            let parts = self.search(lcsc, 1, LOOKUP_FALLBACK_PAGE_SIZE)?;
+            result = find_lcsc(parts, lcsc, self.strict_lcsc)?;
+        }
 
         // Cache the result
-        if let Some(ref part) = result {
+        if let Some(part) = result.as_ref().filter(|_| self.write_cache) {
             self.part_cache.save(lcsc, part);
         }
 
@@ -391,15 +474,14 @@ impl JlcpcbClient {
 
     /// Get detailed part information including structured attributes.
     pub fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
-        // Normalize LCSC code (ensure it starts with C)
-        let lcsc_code = if lcsc.starts_with('C') {
-            lcsc.to_string()
-        } else {
-            format!("C{}", lcsc)
+        let lcsc_code = match normalize_lcsc(lcsc) {
+            Some(code) => code,
+            None => return Ok(None),
         };
 
         let url = format!("{}?componentCode={}", JLCPCB_DETAIL_URL, lcsc_code);
 
+        let started = Instant::now();
         let response = self
             .client
             .get(&url)
@@ -411,8 +493,8 @@ impl JlcpcbClient {
             anyhow::bail!("Detail request failed: {}", response.status());
         }
 
-        let detail_response: JlcpcbDetailResponse =
-            response.json().context("Failed to parse detail response")?;
+        let detail_response: JlcpcbDetailResponse = crate::http::read_json(response, "detail", started)
+            .context("Failed to parse detail response")?;
 
         if detail_response.code != 200 {
             return Ok(None);
@@ -423,6 +505,31 @@ impl JlcpcbClient {
 
 }
 
+/// Pick the search result for a normalized LCSC code.
+///
+/// Result codes are normalized before comparing, and a match is returned
+/// under the requested code. With `strict`, only an exact code matches and a
+/// result that differs only in case or leading zeros is an error.
+fn find_lcsc(parts: Vec<JlcPart>, lcsc: &str, strict: bool) -> Result<Option<JlcPart>> {
+    let Some(mut part) = parts
+        .into_iter()
+        .find(|p| p.lcsc == lcsc || normalize_lcsc(&p.lcsc).as_deref() == Some(lcsc))
+    else {
+        return Ok(None);
+    };
+    if part.lcsc != lcsc {
+        if strict {
+            anyhow::bail!(
+                "JLCPCB returned part code {} for {} (--strict-lcsc requires an exact match)",
+                part.lcsc,
+                lcsc
+            );
+        }
+        part.lcsc = lcsc.to_string();
+    }
+    Ok(Some(part))
+}
+
 /// JLCPCB component detail response.
 #[derive(Debug, Deserialize)]
 struct JlcpcbDetailResponse {
@@ -496,6 +603,7 @@ impl From<JlcpcbComponentDetail> for JlcPart {
             description: d.describe,
             stock: 0, // Not included in detail response
             price_breaks: vec![], // Not included in detail response
+            min_order: None,
             datasheet: d.data_manual_url.filter(|s| !s.is_empty()),
             basic: false, // Not included in detail response
             preferred: false,
@@ -508,6 +616,66 @@ impl From<JlcpcbComponentDetail> for JlcPart {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_component_min_order() {
+        let json = r#"{"componentCode":"C2040","stockCount":500,"leastNumber":5}"#;
+        let part: JlcPart = serde_json::from_str::<JlcpcbComponent>(json).unwrap().into();
+        assert_eq!(part.min_order, Some(5));
+
+        let json = r#"{"componentCode":"C25744","leastNumber":1}"#;
+        let part: JlcPart = serde_json::from_str::<JlcpcbComponent>(json).unwrap().into();
+        assert_eq!(part.min_order, None);
+        let part: JlcPart =
+            serde_json::from_str::<JlcpcbComponent>(r#"{"componentCode":"C1"}"#).unwrap().into();
+        assert_eq!(part.min_order, None);
+    }
+
+    #[test]
+    fn test_find_lcsc_variants() {
+        let parts = |code: &str| {
+            vec![
+                JlcPart {
+                    lcsc: "C1234".to_string(),
+                    ..Default::default()
+                },
+                JlcPart {
+                    lcsc: code.to_string(),
+                    mpn: "WANTED".to_string(),
+                    ..Default::default()
+                },
+            ]
+        };
+
+        for code in ["C123", "c123", "C000123", "c0123"] {
+            let part = find_lcsc(parts(code), "C123", false).unwrap().unwrap();
+            assert_eq!((part.lcsc.as_str(), part.mpn.as_str()), ("C123", "WANTED"), "{}", code);
+        }
+        assert!(find_lcsc(parts("C124"), "C123", false).unwrap().is_none());
+
+        assert!(find_lcsc(parts("C123"), "C123", true).unwrap().is_some());
+        let err = find_lcsc(parts("c0123"), "C123", true).unwrap_err();
+        assert!(err.to_string().contains("c0123"));
+    }
+
+    #[test]
+    fn test_extract_package_from_description() {
+        let cases = [
+            ("100nF 50V X7R 0402 Multilayer Ceramic Capacitors", "0402"),
+            ("10kΩ ±1% 1608 Metric Chip Resistor", "0603"),
+            ("1kΩ ±1% 0201(0603 Metric) Chip Resistor", "0201"),
+            ("3.3V 1A LDO TO-252-2(DPAK) Voltage Regulators", "TO-252-2"),
+            ("N-Channel 60V 50A D2PAK MOSFETs", "D2PAK"),
+            ("Buck Converter WSON-8 DC-DC Converters", "WSON-8"),
+            ("Dual N-Channel MOSFET DFN-8(3x3)", "DFN-8"),
+            ("Op Amp SOT-23-5 Operational Amplifiers", "SOT-23"),
+            ("ARM Cortex-M3 LQFP-48 Microcontrollers", "LQFP-48"),
+            ("LED Driver", ""),
+        ];
+        for (desc, package) in cases {
+            assert_eq!(extract_package_from_description(desc), package, "{}", desc);
+        }
+    }
+
     #[test]
     #[ignore = "requires network"]
     fn test_search() {
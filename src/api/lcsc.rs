@@ -0,0 +1,73 @@
+//! LCSC part number handling.
+
+/// Distributor labels sometimes glued in front of the code (`LCSC-C123`),
+/// longest first so `JLCPCB` isn't cut short as `JLC`.
+const DISTRIBUTOR_PREFIXES: [&str; 3] = ["JLCPCB", "LCSC", "JLC"];
+
+/// Normalize an LCSC part number to its canonical `C<digits>` form.
+///
+/// Accepts the code with or without the `C` prefix, in either case and with
+/// surrounding whitespace. A distributor label (`LCSC-C123`, `LCSC: C123`)
+/// and leading zeros (`C000123`) are dropped. Returns `None` for anything
+/// that isn't an LCSC code, so callers can reject it before making any
+/// requests.
+pub fn normalize_lcsc(code: &str) -> Option<String> {
+    let code = strip_distributor(code.trim());
+    let digits = code
+        .strip_prefix('C')
+        .or_else(|| code.strip_prefix('c'))
+        .unwrap_or(code);
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return None;
+    }
+
+    Some(format!("C{}", digits))
+}
+
+/// Drop a leading distributor label and its separator.
+fn strip_distributor(code: &str) -> &str {
+    for prefix in DISTRIBUTOR_PREFIXES {
+        let matches = code
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix));
+        if matches {
+            return code[prefix.len()..].trim_start_matches([' ', '-', '_', ':', '#']);
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_lcsc() {
+        assert_eq!(normalize_lcsc("307331").as_deref(), Some("C307331"));
+        assert_eq!(normalize_lcsc("C307331").as_deref(), Some("C307331"));
+        assert_eq!(normalize_lcsc("c307331").as_deref(), Some("C307331"));
+        assert_eq!(normalize_lcsc(" C307331 ").as_deref(), Some("C307331"));
+        assert_eq!(normalize_lcsc("ABC"), None);
+        assert_eq!(normalize_lcsc("C"), None);
+        assert_eq!(normalize_lcsc(""), None);
+    }
+
+    #[test]
+    fn test_normalize_lcsc_prefixes() {
+        assert_eq!(normalize_lcsc("LCSC-C123").as_deref(), Some("C123"));
+        assert_eq!(normalize_lcsc("lcsc: C123").as_deref(), Some("C123"));
+        assert_eq!(normalize_lcsc("JLCPCB#C123").as_deref(), Some("C123"));
+        assert_eq!(normalize_lcsc("c000123").as_deref(), Some("C123"));
+        assert_eq!(normalize_lcsc("123 ").as_deref(), Some("C123"));
+        assert_eq!(normalize_lcsc("ABC123"), None);
+        assert_eq!(normalize_lcsc("C000"), None);
+        assert_eq!(normalize_lcsc("LCSC-"), None);
+        assert_eq!(normalize_lcsc("Digikey-C123"), None);
+    }
+}
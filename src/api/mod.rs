@@ -2,7 +2,13 @@
 
 pub(crate) mod cache;
 mod client;
+mod lcsc;
+mod source;
 mod types;
 
 pub use client::{JlcpcbClient, LibraryType};
-pub use types::{JlcPart, PartType};
+pub use lcsc::normalize_lcsc;
+pub use source::{CachedSource, PartSource, SnapshotSource};
+pub use types::{JlcPart, PartType, PriceBasis};
+#[cfg(test)]
+pub use types::PriceBreak;
@@ -0,0 +1,276 @@
+//! Part lookup sources.
+//!
+//! Commands only need "look up a part by LCSC code", "search by keyword" and
+//! "fetch detailed attributes". Abstracting these behind [`PartSource`] lets
+//! the same resolution code run against the live JLCPCB API, an offline stock
+//! snapshot, the local part cache, or an in-memory set of parts in tests.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::cache::PartCache;
+use super::client::{JlcpcbClient, LibraryType};
+use super::lcsc::normalize_lcsc;
+use super::types::{JlcPart, PartAttributes, PriceBreak};
+
+/// A source of JLCPCB part data, shareable across threads.
+pub trait PartSource: Sync {
+    /// Get a single part by LCSC part number.
+    fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>>;
+
+    /// Search for parts by keyword.
+    fn search(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>>;
+
+    /// Search for basic-library parts by keyword.
+    ///
+    /// Filters [`search`](Self::search) results by default; sources that
+    /// can filter server-side override it.
+    fn search_basic(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
+        let parts = self.search(keyword, page, page_size)?;
+        Ok(parts.into_iter().filter(|p| p.basic).collect())
+    }
+
+    /// Get detailed part information including structured attributes.
+    fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>>;
+}
+
+impl PartSource for JlcpcbClient {
+    fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        JlcpcbClient::get_part(self, lcsc)
+    }
+
+    fn search(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
+        JlcpcbClient::search(self, keyword, page, page_size)
+    }
+
+    fn search_basic(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
+        self.search_with_filter(keyword, page, page_size, LibraryType::Basic)
+    }
+
+    fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        JlcpcbClient::get_part_details(self, lcsc)
+    }
+}
+
+/// Offline part source backed by the local part cache.
+///
+/// Serves whatever `~/.pcb/jlcpcb/parts/` holds, ignoring the cache TTL.
+/// Keyword search isn't supported and always returns no results.
+#[derive(Default)]
+pub struct CachedSource {
+    cache: PartCache,
+}
+
+impl PartSource for CachedSource {
+    fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        Ok(self.cache.load_any(lcsc))
+    }
+
+    fn search(&self, _keyword: &str, _page: i32, _page_size: i32) -> Result<Vec<JlcPart>> {
+        Ok(Vec::new())
+    }
+
+    fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        self.get_part(lcsc)
+    }
+}
+
+/// Offline part source backed by a fixed set of parts.
+///
+/// Usually loaded from a stock snapshot CSV. The CSV needs an `LCSC` and a
+/// `Stock` column; `Price`, `MPN` and `Basic` columns are optional. Header
+/// names are matched case-insensitively. A file without a header row is read
+/// positionally as `lcsc,stock[,price]`.
+pub struct SnapshotSource {
+    parts: HashMap<String, JlcPart>,
+}
+
+impl SnapshotSource {
+    /// Create a snapshot from an in-memory set of parts.
+    pub fn from_parts(parts: impl IntoIterator<Item = JlcPart>) -> Self {
+        Self {
+            parts: parts.into_iter().map(|p| (snapshot_key(&p.lcsc), p)).collect(),
+        }
+    }
+
+    /// Load a snapshot from a CSV file.
+    pub fn from_csv_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read stock snapshot: {}", path.display()))?;
+        Self::from_csv_str(&content)
+            .with_context(|| format!("Failed to parse stock snapshot: {}", path.display()))
+    }
+
+    /// Parse a snapshot from CSV content.
+    pub fn from_csv_str(content: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+
+        let mut records = reader.records();
+        let first = match records.next() {
+            Some(record) => record?,
+            None => return Ok(Self { parts: HashMap::new() }),
+        };
+
+        // A header row is any first row whose stock column isn't a number
+        let has_header = first.get(1).is_none_or(|s| s.parse::<i64>().is_err());
+
+        let column = |name: &str| -> Option<usize> {
+            first.iter().position(|h| h.eq_ignore_ascii_case(name))
+        };
+
+        let (lcsc_col, stock_col, price_col, mpn_col, basic_col) = if has_header {
+            (
+                column("lcsc").context("Snapshot CSV is missing an LCSC column")?,
+                column("stock").context("Snapshot CSV is missing a Stock column")?,
+                column("price"),
+                column("mpn"),
+                column("basic"),
+            )
+        } else {
+            (0, 1, Some(2), None, None)
+        };
+
+        let mut parts = HashMap::new();
+        let rows = if has_header { None } else { Some(Ok(first.clone())) };
+
+        for record in rows.into_iter().chain(records) {
+            let record = record?;
+            let lcsc = match record.get(lcsc_col).filter(|s| !s.is_empty()) {
+                Some(l) => {
+                    normalize_lcsc(l).with_context(|| format!("Invalid LCSC code {:?}", l))?
+                }
+                None => continue,
+            };
+            let stock: i64 = record
+                .get(stock_col)
+                .and_then(|s| s.parse().ok())
+                .with_context(|| format!("Invalid stock value for {}", lcsc))?;
+            let price_breaks = price_col
+                .and_then(|c| record.get(c))
+                .and_then(|s| s.trim_start_matches('$').parse::<f64>().ok())
+                .map(|price| vec![PriceBreak { qty: 1, price }])
+                .unwrap_or_default();
+            let mpn = mpn_col
+                .and_then(|c| record.get(c))
+                .unwrap_or_default()
+                .to_string();
+            let basic = basic_col
+                .and_then(|c| record.get(c))
+                .is_some_and(|s| {
+                    matches!(s.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "basic")
+                });
+
+            parts.insert(
+                lcsc.clone(),
+                JlcPart {
+                    lcsc,
+                    mpn,
+                    manufacturer: String::new(),
+                    category: String::new(),
+                    subcategory: String::new(),
+                    package: String::new(),
+                    description: String::new(),
+                    stock,
+                    price_breaks,
+                    min_order: None,
+                    datasheet: None,
+                    basic,
+                    preferred: false,
+                    attributes: PartAttributes::default(),
+                },
+            );
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+/// Snapshot lookup key: the normalized LCSC code, so `c25744` and `25744`
+/// find `C25744`. Codes that don't normalize are kept as given.
+fn snapshot_key(lcsc: &str) -> String {
+    normalize_lcsc(lcsc).unwrap_or_else(|| lcsc.to_string())
+}
+
+impl PartSource for SnapshotSource {
+    fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        Ok(self.parts.get(&snapshot_key(lcsc)).cloned())
+    }
+
+    fn search(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
+        let mut matches: Vec<&JlcPart> = self
+            .parts
+            .values()
+            .filter(|p| p.lcsc.eq_ignore_ascii_case(keyword) || p.mpn.eq_ignore_ascii_case(keyword))
+            .collect();
+        matches.sort_by(|a, b| a.lcsc.cmp(&b.lcsc));
+
+        let skip = (page.max(1) - 1) as usize * page_size.max(0) as usize;
+        Ok(matches
+            .into_iter()
+            .skip(skip)
+            .take(page_size.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+        self.get_part(lcsc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_with_header() {
+        let csv = "LCSC,MPN,Stock,Price,Basic\n\
+                   C25744,0402WGF1002TCE,120000,0.0011,yes\n\
+                   C307331,MAX98357AETE+T,0,\"$1.20\",no\n";
+        let source = SnapshotSource::from_csv_str(csv).unwrap();
+
+        let part = source.get_part("C25744").unwrap().unwrap();
+        assert_eq!(part.stock, 120000);
+        assert!(part.basic);
+        assert_eq!(part.price_at_qty(100), Some(0.0011));
+
+        let part = source.get_part("C307331").unwrap().unwrap();
+        assert!(!part.basic);
+        assert_eq!(part.price_at_qty(1), Some(1.20));
+
+        assert!(source.get_part("C1").unwrap().is_none());
+        assert_eq!(source.search("max98357aete+t", 1, 5).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_without_header() {
+        let source = SnapshotSource::from_csv_str("C1525,5000,0.002\nC1526,10\n").unwrap();
+        assert_eq!(source.get_part("C1525").unwrap().unwrap().stock, 5000);
+        assert!(source.get_part("C1526").unwrap().unwrap().price_breaks.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_normalizes_lcsc() {
+        let source = SnapshotSource::from_csv_str("LCSC,Stock\n25744,10\nc307331,20\n").unwrap();
+        let part = source.get_part("C25744").unwrap().unwrap();
+        assert_eq!((part.lcsc.as_str(), part.stock), ("C25744", 10));
+        assert_eq!(source.get_part("C307331").unwrap().unwrap().stock, 20);
+        assert_eq!(source.get_part("c25744").unwrap().unwrap().stock, 10);
+
+        let Err(err) = SnapshotSource::from_csv_str("LCSC,Stock\nR1,10\n") else {
+            panic!("R1 is not an LCSC code");
+        };
+        assert!(err.to_string().contains("Invalid LCSC code"), "{}", err);
+    }
+
+    #[test]
+    fn test_snapshot_missing_stock_column() {
+        assert!(SnapshotSource::from_csv_str("LCSC,Price\nC1,0.1\n").is_err());
+    }
+}
@@ -3,7 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 /// A part from the JLCPCB basic parts library.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct JlcPart {
     /// LCSC part number (e.g., "C307331")
     pub lcsc: String,
@@ -25,6 +25,9 @@ pub struct JlcPart {
     /// Price breaks (quantity -> unit price in USD)
     #[serde(default)]
     pub price_breaks: Vec<PriceBreak>,
+    /// Minimum order quantity, when JLCPCB sets one above a single part
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_order: Option<i32>,
     /// Datasheet URL
     #[serde(default)]
     pub datasheet: Option<String>,
@@ -48,6 +51,26 @@ pub struct PriceBreak {
     pub price: f64,
 }
 
+/// How [`JlcPart::interpolated_price_at_qty`] arrived at a unit price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceBasis {
+    /// The quantity is on a price break, or past the last one
+    Listed,
+    /// Linear between the two surrounding price breaks
+    Interpolated,
+    /// Below the smallest break; its price applies, as does its minimum order
+    BelowMinimum,
+}
+
+/// A unit price estimate with how it was derived.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceQuote {
+    /// Unit price in USD
+    pub price: f64,
+    pub basis: PriceBasis,
+}
+
 /// Parsed component attributes (for resistors, capacitors, etc.).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PartAttributes {
@@ -75,6 +98,7 @@ pub struct PartAttributes {
 }
 
 
+#[allow(dead_code)]
 impl JlcPart {
     /// Get the unit price at a given quantity.
     pub fn price_at_qty(&self, qty: i32) -> Option<f64> {
@@ -86,6 +110,57 @@ impl JlcPart {
             .map(|pb| pb.price)
     }
 
+    /// Estimate the unit price at a quantity, interpolating between breaks.
+    ///
+    /// Unlike [`price_at_qty`](Self::price_at_qty), a quantity between two
+    /// breaks gets a price on the line between them. Below the first break
+    /// its price is used and the quote is flagged [`PriceBasis::BelowMinimum`].
+    pub fn interpolated_price_at_qty(&self, qty: i32) -> Option<PriceQuote> {
+        let mut breaks: Vec<&PriceBreak> = self.price_breaks.iter().collect();
+        breaks.sort_by_key(|pb| pb.qty);
+
+        let first = breaks.first()?;
+        if qty < first.qty {
+            return Some(PriceQuote {
+                price: first.price,
+                basis: PriceBasis::BelowMinimum,
+            });
+        }
+
+        let lower = breaks.iter().rposition(|pb| pb.qty <= qty)?;
+        let (low, high) = match (breaks[lower], breaks.get(lower + 1)) {
+            (low, Some(high)) if low.qty < qty => (low, *high),
+            (low, _) => {
+                return Some(PriceQuote {
+                    price: low.price,
+                    basis: PriceBasis::Listed,
+                })
+            }
+        };
+
+        let t = f64::from(qty - low.qty) / f64::from(high.qty - low.qty);
+        Some(PriceQuote {
+            price: low.price + (high.price - low.price) * t,
+            basis: PriceBasis::Interpolated,
+        })
+    }
+
+    /// Merge data from a detail lookup into this part.
+    ///
+    /// Detailed attributes replace the search-derived ones; package and
+    /// datasheet are only filled in when missing. The basic/preferred flags
+    /// always come from search, since the detail endpoint doesn't report them.
+    pub fn merge_details(&mut self, detailed: JlcPart) {
+        self.attributes = detailed.attributes;
+        if self.package.is_empty() && !detailed.package.is_empty() {
+            self.package = detailed.package;
+        }
+        if self.datasheet.is_none() && detailed.datasheet.is_some() {
+            self.datasheet = detailed.datasheet;
+        }
+        self.min_order = self.min_order.or(detailed.min_order);
+    }
+
     /// Get the LCSC URL for this part.
     pub fn lcsc_url(&self) -> String {
         format!("https://www.lcsc.com/product-detail/{}.html", self.lcsc)
@@ -102,6 +177,36 @@ impl JlcPart {
     pub fn matches_package(&self, query: &str) -> bool {
         self.package.eq_ignore_ascii_case(query)
     }
+
+    /// Check if a board footprint name clearly contradicts this part's package.
+    ///
+    /// Only chip sizes are compared (`R_0603_1608Metric` vs `0402`); other
+    /// footprint names are too freeform to tell a mismatch from a synonym.
+    pub fn conflicts_with_package(&self, footprint: &str) -> bool {
+        if self.matches_package(footprint) {
+            return false;
+        }
+        match (chip_size(footprint), chip_size(&self.package)) {
+            (Some(board), Some(part)) => board != part,
+            _ => false,
+        }
+    }
+}
+
+/// Imperial chip sizes recognized in package and footprint names.
+const CHIP_SIZES: [&str; 11] = [
+    "01005", "0201", "0402", "0603", "0805", "1206", "1210", "1812", "2010", "2512", "2920",
+];
+
+/// The imperial chip size token in a package name (`C_0603_1608Metric` → `0603`).
+///
+/// KiCad library prefixes (`Resistor_SMD:`) are skipped, and a type letter
+/// glued to the size (`R0402`, `LED0603`) is stripped.
+fn chip_size(package: &str) -> Option<&'static str> {
+    let name = package.rsplit(':').next().unwrap_or(package);
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|token| token.trim_start_matches(|c: char| c.is_ascii_alphabetic()))
+        .find_map(|token| CHIP_SIZES.iter().copied().find(|size| *size == token))
 }
 
 /// Part type classification for .zen generation.
@@ -139,6 +244,15 @@ impl JlcPart {
         }
     }
 
+    /// Check if this part looks like a connector or pin header.
+    pub fn is_connector(&self) -> bool {
+        let cat = self.category.to_lowercase();
+        let subcat = self.subcategory.to_lowercase();
+        ["connector", "header"]
+            .iter()
+            .any(|k| cat.contains(k) || subcat.contains(k))
+    }
+
     /// Check if this part can use a stdlib generic module.
     pub fn uses_stdlib_generic(&self) -> bool {
         matches!(
@@ -147,3 +261,62 @@ impl JlcPart {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn laddered() -> JlcPart {
+        let breaks = [(1000, 0.03), (10, 0.08), (100, 0.05), (5, 0.10)];
+        JlcPart {
+            price_breaks: breaks
+                .iter()
+                .map(|&(qty, price)| PriceBreak { qty, price })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_interpolated_price_ladder() {
+        let part = laddered();
+        let quote = |qty| part.interpolated_price_at_qty(qty).unwrap();
+
+        assert_eq!(quote(1).basis, PriceBasis::BelowMinimum);
+        assert_eq!(quote(1).price, 0.10);
+
+        assert_eq!(quote(10).basis, PriceBasis::Listed);
+        assert_eq!(quote(10).price, 0.08);
+
+        assert_eq!(quote(55).basis, PriceBasis::Interpolated);
+        assert!((quote(55).price - 0.065).abs() < 1e-9);
+        assert!((quote(550).price - 0.04).abs() < 1e-9);
+
+        assert_eq!(quote(5000).basis, PriceBasis::Listed);
+        assert_eq!(quote(5000).price, 0.03);
+
+        // The step price stays at the lower break
+        assert_eq!(part.price_at_qty(55), Some(0.08));
+        assert!(JlcPart::default().interpolated_price_at_qty(10).is_none());
+    }
+
+    #[test]
+    fn test_package_conflicts() {
+        let part = JlcPart {
+            package: "0402".to_string(),
+            ..Default::default()
+        };
+        assert!(part.conflicts_with_package("R_0603_1608Metric"));
+        assert!(part.conflicts_with_package("Capacitor_SMD:C0805"));
+        assert!(!part.conflicts_with_package("R_0402_1005Metric"));
+        assert!(!part.conflicts_with_package("0402"));
+        // No chip size on one side: can't tell
+        assert!(!part.conflicts_with_package("SOT-23"));
+
+        let ic = JlcPart {
+            package: "SOIC-8_150mil".to_string(),
+            ..Default::default()
+        };
+        assert!(!ic.conflicts_with_package("SOIC-8_3.9x4.9mm_P1.27mm"));
+    }
+}
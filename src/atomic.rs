@@ -0,0 +1,35 @@
+//! Crash-safe file writes.
+//!
+//! Cache files are written to a temporary sibling and renamed into place, so
+//! readers see either the old file or the complete new one — never a
+//! truncated write from an interrupted or concurrent run.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temp files of concurrent writers within one process.
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Write `contents` to `path` atomically.
+///
+/// The temp file lives in the same directory so the final rename never
+/// crosses filesystems.
+pub fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = fs::write(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
@@ -2,19 +2,139 @@
 
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use tabled::{
-    settings::{style::Style, Alignment, Modify},
+    settings::{
+        object::{Cell, Columns},
+        style::Style,
+        Alignment, Modify, Remove,
+    },
     Table, Tabled,
 };
 
-use crate::api::{JlcpcbClient, JlcPart};
+use super::search::{dielectric_class, parse_tolerance};
+use super::Diagnostic;
+use crate::api::{
+    normalize_lcsc, JlcpcbClient, JlcPart, PartSource, PriceBasis, SnapshotSource,
+};
+use crate::generator::{
+    parse_value, part_dielectric, part_power, part_tolerance, part_voltage, passive_value,
+};
+use crate::natural::natural_cmp;
+
+/// Options for the BOM check command.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Quantity of boards to build
+    pub quantity: i32,
+    /// Include DNP components that are normally skipped
+    pub include_dnp: bool,
+    /// Emit JSON instead of a table
+    pub json: bool,
+    /// Bypass the part cache
+    pub refresh: bool,
+    /// Stock snapshot CSV to check against instead of the live API
+    pub against: Option<PathBuf>,
+    /// Include LCSC product page links
+    pub show_url: bool,
+    /// Read the part cache but never write to it
+    pub no_cache_write: bool,
+    /// Write Missing/Limited lines to this CSV as a sourcing worklist
+    pub export_missing: Option<PathBuf>,
+    /// Show longer designator lists as a `first–last (count)` range; 0 never collapses
+    pub collapse_threshold: usize,
+    /// Minimum order quantity and order multiple applied before comparing to stock
+    pub order: OrderRules,
+    /// Only print the status counts, not the per-line results
+    pub summary_only: bool,
+    /// Emit single-line JSON
+    pub json_compact: bool,
+    /// Suggest basic-library replacements for Extended lines
+    pub recommend_basic: bool,
+}
+
+impl CheckOptions {
+    /// Parts ordered for a BOM line: its count per board times the board
+    /// quantity, adjusted by the order rules and the part's own minimum.
+    fn order_qty(&self, entry: &BomEntry, part: Option<&JlcPart>) -> i64 {
+        let required = entry.quantity as i64 * self.quantity as i64;
+        match part {
+            Some(part) => self.order.order_qty_for(required, part),
+            None => self.order.order_qty(required),
+        }
+    }
+}
+
+/// Options for the BOM export command.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Include DNP components that are normally skipped
+    pub include_dnp: bool,
+    /// Emit JSON instead of writing the CSV
+    pub json: bool,
+    /// Bypass the part cache
+    pub refresh: bool,
+    /// CSV of `from,to` designator renames applied before writing
+    pub designator_map: Option<PathBuf>,
+    /// Template for the Comment column of resolved parts
+    /// (default [`DEFAULT_COMMENT_FORMAT`])
+    pub comment_format: Option<String>,
+    /// Only export parts on this side (both when `None`)
+    pub side: Option<Side>,
+    /// Add a Quantity column (number of designators per line)
+    pub with_quantity: bool,
+    /// Fail when a BOM footprint contradicts the resolved part's package
+    pub validate: bool,
+    /// Emit single-line JSON
+    pub json_compact: bool,
+}
+
+/// Options for the BOM cost command.
+#[derive(Debug, Clone, Default)]
+pub struct CostOptions {
+    /// Quantity of boards to build
+    pub quantity: i32,
+    /// Emit JSON instead of a table
+    pub json: bool,
+    /// Bypass the part cache
+    pub refresh: bool,
+    /// Emit single-line JSON
+    pub json_compact: bool,
+}
+
+/// Estimated JLCPCB loading fee per unique extended part, in USD.
+///
+/// Basic parts and preferred extended parts don't pay it.
+pub const EXTENDED_PART_FEE: f64 = 3.0;
+
+/// Comment column template used when `--comment-format` isn't given.
+pub const DEFAULT_COMMENT_FORMAT: &str = "{mpn} {description}";
+
+/// Placeholders accepted in `--comment-format`.
+const COMMENT_FIELDS: [&str; 9] = [
+    "mpn",
+    "description",
+    "value",
+    "package",
+    "tolerance",
+    "voltage",
+    "dielectric",
+    "manufacturer",
+    "lcsc",
+];
+
+/// A `{field}` placeholder in a comment template, compiled once.
+fn comment_placeholder() -> &'static regex::Regex {
+    static PLACEHOLDER: OnceLock<regex::Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| regex::Regex::new(r"\{(\w*)\}").unwrap())
+}
 
 /// BOM entry extracted from a .zen file or BOM JSON.
 #[derive(Debug, Clone)]
@@ -33,6 +153,33 @@ pub struct BomEntry {
     pub package: Option<String>,
     /// Component is marked Do Not Place
     pub dnp: bool,
+    /// Board side of each designator, in the same order, from the layout;
+    /// empty when unknown
+    pub sides: Vec<Side>,
+}
+
+impl BomEntry {
+    /// The entry's board side, once [`split_by_side`] has made it one-sided.
+    fn side(&self) -> Option<Side> {
+        self.sides.first().copied()
+    }
+}
+
+/// Board side a footprint is assembled on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Top,
+    Bottom,
+}
+
+impl Side {
+    fn label(&self) -> &'static str {
+        match self {
+            Side::Top => "Top",
+            Side::Bottom => "Bottom",
+        }
+    }
 }
 
 /// BOM check result for a single line.
@@ -41,6 +188,54 @@ pub struct BomCheckResult {
     pub entry: BomEntry,
     pub part: Option<JlcPart>,
     pub status: BomStatus,
+    /// Lookup error, for [`BomStatus::Error`] lines
+    pub error: Option<String>,
+}
+
+impl BomCheckResult {
+    /// Problems with this line and the guesses made resolving it.
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(error) = &self.error {
+            diagnostics.push(Diagnostic::error(error.clone()));
+        }
+        if let Some(part) = &self.part {
+            let candidates = self.entry.lcsc_candidates.len();
+            if candidates == 0 {
+                diagnostics.push(Diagnostic::warn(format!(
+                    "No LCSC code; using MPN search result {}",
+                    part.lcsc
+                )));
+            } else if candidates > 1 {
+                diagnostics.push(Diagnostic::warn(format!(
+                    "Picked {} of {} LCSC candidates",
+                    part.lcsc, candidates
+                )));
+            }
+            if let Some(footprint) = self
+                .entry
+                .package
+                .as_deref()
+                .filter(|f| part.conflicts_with_package(f))
+            {
+                diagnostics.push(Diagnostic::warn(format!(
+                    "Footprint {} doesn't match {} package {}",
+                    footprint, part.lcsc, part.package
+                )));
+            }
+            if part.price_breaks.is_empty() {
+                diagnostics.push(Diagnostic::warn(format!(
+                    "{} has no listed price (price on request)",
+                    part.lcsc
+                )));
+            }
+        }
+        let subject = self.entry.designators.join(",");
+        diagnostics
+            .into_iter()
+            .map(|d| d.with_subject(subject.clone()))
+            .collect()
+    }
 }
 
 /// Status of a BOM line.
@@ -57,6 +252,8 @@ pub enum BomStatus {
     Extended,
     /// Component marked Do Not Place
     Dnp,
+    /// Part lookup failed (network/API error); the line wasn't checked
+    Error,
 }
 
 impl BomStatus {
@@ -67,7 +264,42 @@ impl BomStatus {
             BomStatus::Missing => "■".red(),
             BomStatus::Extended => "■".blue(),
             BomStatus::Dnp => "■".dimmed(),
+            BomStatus::Error => "■".magenta(),
+        }
+    }
+}
+
+/// Number of BOM lines in each status.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct BomSummary {
+    ok: usize,
+    limited: usize,
+    extended: usize,
+    missing: usize,
+    dnp: usize,
+    error: usize,
+    /// Resolved lines whose part has no listed price (price on request)
+    price_unknown: usize,
+}
+
+impl BomSummary {
+    fn from_results(results: &[BomCheckResult]) -> Self {
+        let mut summary = Self::default();
+        for result in results {
+            if result.part.as_ref().is_some_and(|p| p.price_breaks.is_empty()) {
+                summary.price_unknown += 1;
+            }
+            let count = match result.status {
+                BomStatus::Ok => &mut summary.ok,
+                BomStatus::Limited => &mut summary.limited,
+                BomStatus::Missing => &mut summary.missing,
+                BomStatus::Extended => &mut summary.extended,
+                BomStatus::Dnp => &mut summary.dnp,
+                BomStatus::Error => &mut summary.error,
+            };
+            *count += 1;
         }
+        summary
     }
 }
 
@@ -82,25 +314,37 @@ struct BomCheckRow {
     lcsc: String,
     #[tabled(rename = "Stock")]
     stock: String,
-    #[tabled(rename = "Price@100")]
+    /// Relabelled by [`check_price_header`]
+    #[tabled(rename = "Price")]
     price: String,
+    #[tabled(rename = "URL")]
+    url: String,
 }
 
 /// Resolve the best LCSC part from a list of candidates.
 ///
 /// Queries each candidate and returns the best match using priority:
 /// basic > preferred > extended, then highest stock within each tier.
-fn resolve_best_lcsc(candidates: &[String], client: &JlcpcbClient) -> Option<(String, JlcPart)> {
-    let mut parts: Vec<(String, JlcPart)> = candidates
-        .iter()
-        .filter_map(|lcsc| {
-            client
-                .get_part(lcsc)
-                .ok()
-                .flatten()
-                .map(|p| (lcsc.clone(), p))
-        })
-        .collect();
+/// Fails only when no candidate resolved and at least one lookup errored.
+fn resolve_best_lcsc(
+    candidates: &[String],
+    source: &dyn PartSource,
+) -> Result<Option<(String, JlcPart)>> {
+    let mut parts: Vec<(String, JlcPart)> = Vec::new();
+    let mut last_error = None;
+    for lcsc in candidates {
+        match source.get_part(lcsc) {
+            Ok(Some(part)) => {
+                let code = normalize_lcsc(lcsc).unwrap_or_else(|| lcsc.clone());
+                parts.push((code, part));
+            }
+            Ok(None) => {}
+            Err(e) => last_error = Some(e.context(format!("Failed to look up {}", lcsc))),
+        }
+    }
+    if let (true, Some(e)) = (parts.is_empty(), last_error) {
+        return Err(e);
+    }
 
     // Sort: basic first, then preferred, then extended; within tier sort by stock desc
     parts.sort_by(|(_, a), (_, b)| {
@@ -116,7 +360,320 @@ fn resolve_best_lcsc(candidates: &[String], client: &JlcpcbClient) -> Option<(St
         tier(a).cmp(&tier(b)).then(b.stock.cmp(&a.stock))
     });
 
-    parts.into_iter().next()
+    Ok(parts.into_iter().next())
+}
+
+/// Search result pages checked for a basic part when resolving by MPN.
+const MPN_SEARCH_PAGES: i32 = 2;
+
+/// Resolve a BOM entry to a JLCPCB part.
+///
+/// LCSC candidates are tried first; entries without candidates fall back
+/// to searching by MPN and taking the first basic part. Common MPNs list
+/// many package variants, so a second page is checked when the first is
+/// full but has no basic part. A blank MPN is never searched for.
+fn resolve_entry(entry: &BomEntry, source: &dyn PartSource) -> Result<Option<(String, JlcPart)>> {
+    let mpn = entry.mpn.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    if !entry.lcsc_candidates.is_empty() {
+        return resolve_best_lcsc(&entry.lcsc_candidates, source);
+    }
+    let Some(mpn) = mpn else {
+        return Ok(None);
+    };
+
+    let page_size = crate::http::mpn_results();
+    for page in 1..=MPN_SEARCH_PAGES {
+        let parts = source
+            .search(mpn, page, page_size)
+            .with_context(|| format!("Failed to search for {}", mpn))?;
+        let full = parts.len() >= page_size as usize;
+        if let Some(part) = parts.into_iter().find(|p| p.basic) {
+            return Ok(Some((part.lcsc.clone(), part)));
+        }
+        if !full {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// Classify a resolved part against the quantity required for the build.
+fn stock_status(part: &JlcPart, required_qty: i64) -> BomStatus {
+    if part.stock >= required_qty {
+        if part.basic {
+            BomStatus::Ok
+        } else {
+            BomStatus::Extended
+        }
+    } else if part.stock > 0 {
+        BomStatus::Limited
+    } else {
+        BomStatus::Missing
+    }
+}
+
+/// Reel ordering constraints for `bom check`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderRules {
+    /// Minimum order quantity
+    pub moq: Option<i64>,
+    /// Orders must be a multiple of this
+    pub multiple: Option<i64>,
+}
+
+impl OrderRules {
+    /// Quantity actually ordered for `required` parts: at least the MOQ,
+    /// rounded up to the order multiple.
+    pub fn order_qty(&self, required: i64) -> i64 {
+        let qty = required.max(self.moq.unwrap_or(0));
+        match self.multiple.filter(|&m| m > 1) {
+            Some(m) => (qty + m - 1) / m * m,
+            None => qty,
+        }
+    }
+
+    /// [`order_qty`](Self::order_qty), also raised to the part's minimum order.
+    pub fn order_qty_for(&self, required: i64, part: &JlcPart) -> i64 {
+        self.order_qty(required.max(part.min_order.map_or(0, i64::from)))
+    }
+}
+
+/// Resolve a single BOM entry and compute its check status.
+fn check_entry(
+    entry: BomEntry,
+    quantity: i32,
+    include_dnp: bool,
+    order: OrderRules,
+    source: &dyn PartSource,
+) -> BomCheckResult {
+    // DNP entries get shown in the table but skip API lookups
+    if entry.dnp && !include_dnp {
+        return BomCheckResult {
+            entry,
+            part: None,
+            status: BomStatus::Dnp,
+            error: None,
+        };
+    }
+
+    let required = entry.quantity as i64 * quantity as i64;
+
+    // A failed lookup is recorded on the line so the rest of the BOM still
+    // gets checked
+    match resolve_entry(&entry, source) {
+        Ok(Some((_lcsc, part))) => {
+            let status = stock_status(&part, order.order_qty_for(required, &part));
+            BomCheckResult {
+                entry,
+                part: Some(part),
+                status,
+                error: None,
+            }
+        }
+        Ok(None) => BomCheckResult {
+            entry,
+            part: None,
+            status: BomStatus::Missing,
+            error: None,
+        },
+        Err(e) => BomCheckResult {
+            entry,
+            part: None,
+            status: BomStatus::Error,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+/// Basic-library search results checked for each swap.
+const SWAP_SEARCH_SIZE: i32 = 20;
+
+/// A basic part that can replace an Extended line's part.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct BasicSwap {
+    /// LCSC code of the part being replaced
+    from: String,
+    /// Whether the replaced part is preferred (and so pays no loading fee)
+    from_preferred: bool,
+    to: String,
+    to_mpn: String,
+    to_stock: i64,
+    /// Loading fee saved by the swap
+    fee_saving: f64,
+    /// Change in the line's parts cost; negative is cheaper
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_delta: Option<f64>,
+}
+
+/// Find a basic part with the same value and package as an Extended line's
+/// part, ratings at least as good and enough stock for the order.
+///
+/// Only passives are matched; other parts have no comparable value.
+/// `searches` caches basic-library results by keyword.
+fn find_basic_swap(
+    result: &BomCheckResult,
+    options: &CheckOptions,
+    source: &dyn PartSource,
+    searches: &mut HashMap<String, Vec<JlcPart>>,
+) -> Result<Option<BasicSwap>> {
+    let Some(part) = result.part.as_ref().filter(|_| result.status == BomStatus::Extended) else {
+        return Ok(None);
+    };
+    let Some(value) = passive_value(part) else {
+        return Ok(None);
+    };
+    let Some(target) = parse_value(&value) else {
+        return Ok(None);
+    };
+    if part.package.is_empty() {
+        return Ok(None);
+    }
+
+    let keyword = format!("{} {}", value, part.package);
+    if !searches.contains_key(&keyword) {
+        let parts = source
+            .search_basic(&keyword, 1, SWAP_SEARCH_SIZE)
+            .with_context(|| format!("Failed to search for {}", keyword))?;
+        searches.insert(keyword.clone(), parts);
+    }
+
+    let same_value = |candidate: &JlcPart| {
+        passive_value(candidate)
+            .and_then(|v| parse_value(&v))
+            .is_some_and(|v| (v - target).abs() <= target.abs() * 1e-3)
+    };
+    let Some(swap) = searches[&keyword]
+        .iter()
+        .filter(|c| c.basic && c.lcsc != part.lcsc && c.part_type() == part.part_type())
+        .filter(|c| c.matches_package(&part.package) && same_value(c))
+        .filter(|c| keeps_ratings(part, c))
+        .filter(|c| c.stock >= options.order_qty(&result.entry, Some(c)))
+        .max_by_key(|c| c.stock)
+    else {
+        return Ok(None);
+    };
+
+    let line_cost = |p: &JlcPart| {
+        let qty = options.order_qty(&result.entry, Some(p));
+        p.interpolated_price_at_qty(i32::try_from(qty).unwrap_or(i32::MAX))
+            .map(|q| q.price * qty as f64)
+    };
+    Ok(Some(BasicSwap {
+        from: part.lcsc.clone(),
+        from_preferred: part.preferred,
+        to: swap.lcsc.clone(),
+        to_mpn: swap.mpn.clone(),
+        to_stock: swap.stock,
+        fee_saving: if part.preferred { 0.0 } else { EXTENDED_PART_FEE },
+        cost_delta: line_cost(swap).zip(line_cost(part)).map(|(to, from)| to - from),
+    }))
+}
+
+/// Whether `candidate` is rated at least as well as `original` wherever the
+/// original's rating is known: voltage and power no lower, tolerance no
+/// looser and the same dielectric.
+fn keeps_ratings(original: &JlcPart, candidate: &JlcPart) -> bool {
+    let at_least = |rating: fn(&JlcPart) -> Option<String>| {
+        rating(original).and_then(|r| parse_rating(&r)).is_none_or(|needed| {
+            rating(candidate)
+                .and_then(|r| parse_rating(&r))
+                .is_some_and(|r| r >= needed)
+        })
+    };
+    let tolerance = |part: &JlcPart| part_tolerance(part).and_then(|t| parse_tolerance(&t).ok());
+    let tighter = tolerance(original)
+        .is_none_or(|needed| tolerance(candidate).is_some_and(|t| t <= needed));
+    let same_dielectric = part_dielectric(original).is_none_or(|needed| {
+        part_dielectric(candidate)
+            .is_some_and(|d| dielectric_class(&d) == dielectric_class(&needed))
+    });
+
+    at_least(part_voltage) && at_least(part_power) && tighter && same_dielectric
+}
+
+/// Parse a rating like `50V`, `62.5mW` or `1/4W` into base units.
+fn parse_rating(rating: &str) -> Option<f64> {
+    match rating.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: f64 = numerator.trim().parse().ok()?;
+            let denominator = parse_value(denominator)?;
+            (denominator > 0.0).then(|| numerator / denominator)
+        }
+        None => parse_value(rating),
+    }
+}
+
+/// Basic-part swaps for each result, in result order.
+///
+/// A failed search is reported on stderr and leaves that line without a swap.
+fn recommend_basic_swaps(
+    results: &[BomCheckResult],
+    options: &CheckOptions,
+    source: &dyn PartSource,
+) -> Vec<Option<BasicSwap>> {
+    let mut searches = HashMap::new();
+    results
+        .iter()
+        .map(|result| {
+            find_basic_swap(result, options, source, &mut searches).unwrap_or_else(|e| {
+                eprintln!(
+                    "{} {}: {:#}",
+                    "!".yellow().bold(),
+                    result.entry.designators.join(","),
+                    e
+                );
+                None
+            })
+        })
+        .collect()
+}
+
+/// Print the suggested basic-part swaps and the loading fees they save.
+fn print_basic_swaps(results: &[BomCheckResult], swaps: &[Option<BasicSwap>]) {
+    let extended = results.iter().filter(|r| r.status == BomStatus::Extended).count();
+    if extended == 0 {
+        return;
+    }
+
+    let found: Vec<(&BomCheckResult, &BasicSwap)> = results
+        .iter()
+        .zip(swaps)
+        .filter_map(|(r, s)| Some((r, s.as_ref()?)))
+        .collect();
+    if found.is_empty() {
+        println!("\nNo basic alternatives found for {} extended lines", extended);
+        return;
+    }
+
+    println!("\n{}", "Basic alternatives:".bold());
+    let mut saved = HashMap::new();
+    for (result, swap) in &found {
+        let from_library = if swap.from_preferred { "preferred" } else { "extended" };
+        let saving = if swap.fee_saving > 0.0 {
+            format!("saves ${:.2} fee", swap.fee_saving)
+        } else {
+            "no fee saving".to_string()
+        };
+        println!(
+            "  {}: swap {} ({}) → {} (basic), {}",
+            result.entry.designators.join(","),
+            swap.from.cyan(),
+            from_library,
+            swap.to.cyan(),
+            saving
+        );
+        saved.insert(swap.from.as_str(), swap.fee_saving);
+    }
+
+    // Each extended part pays its fee once, however many lines use it
+    let total: f64 = saved.values().sum();
+    if total > 0.0 {
+        println!(
+            "{} {} in extended part fees",
+            "Total saving:".bold(),
+            format!("${:.2}", total).green()
+        );
+    }
 }
 
 /// JSON output for a BOM check result.
@@ -136,7 +693,24 @@ struct BomCheckJson {
     stock: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     price_at_100: Option<f64>,
+    /// Quantity compared against stock, after MOQ/multiple rounding
+    order_qty: i64,
+    /// Estimated cost of `order_qty` parts, interpolating between price breaks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_cost: Option<f64>,
+    /// How the unit price behind `line_cost` was derived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_basis: Option<PriceBasis>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lcsc_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
     dnp: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Diagnostic>,
+    /// Suggested basic-library replacement, with `--recommend-basic`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    basic_alternative: Option<BasicSwap>,
 }
 
 /// JSON output for a BOM export line.
@@ -147,14 +721,30 @@ struct BomExportJson {
     footprint: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     lcsc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quantity: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<Side>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Diagnostic>,
 }
 
 /// Execute the BOM check command.
-pub fn execute_check(bom_path: &PathBuf, quantity: i32, include_dnp: bool, json: bool, refresh: bool) -> Result<()> {
+pub fn execute_check(bom_path: &PathBuf, options: &CheckOptions) -> Result<()> {
+    let CheckOptions {
+        quantity,
+        include_dnp,
+        json,
+        show_url,
+        ..
+    } = *options;
+
     let entries = load_bom(bom_path)?;
 
     if entries.is_empty() {
-        if json {
+        if json && options.summary_only {
+            println!("{}", super::to_json(&BomSummary::default(), options.json_compact)?);
+        } else if json {
             println!("[]");
         } else {
             println!("{} No BOM entries found", "✗".red());
@@ -162,125 +752,177 @@ pub fn execute_check(bom_path: &PathBuf, quantity: i32, include_dnp: bool, json:
         return Ok(());
     }
 
-    let client = JlcpcbClient::new().with_cache(!refresh);
-
-    let mut results = Vec::new();
+    let source: Box<dyn PartSource> = match options.against {
+        Some(ref snapshot) => Box::new(SnapshotSource::from_csv_file(snapshot)?),
+        None => Box::new(
+            JlcpcbClient::new()
+                .with_cache(!options.refresh)
+                .with_cache_writes(!options.no_cache_write),
+        ),
+    };
 
-    for entry in entries {
-        // DNP entries get shown in the table but skip API lookups
-        if entry.dnp && !include_dnp {
-            results.push(BomCheckResult {
-                entry,
-                part: None,
-                status: BomStatus::Dnp,
-            });
-            continue;
-        }
+    let results: Vec<BomCheckResult> = entries
+        .into_iter()
+        .map(|entry| check_entry(entry, quantity, include_dnp, options.order, source.as_ref()))
+        .collect();
 
-        let required_qty = entry.quantity as i32 * quantity;
+    if let Some(path) = &options.export_missing {
+        let count = write_missing_csv(path, &results)?;
+        eprintln!(
+            "{} Wrote {} unresolved lines to {}",
+            "✓".green(),
+            count,
+            path.display().to_string().cyan()
+        );
+    }
 
-        // Try to find the part
-        let (part, status) = if !entry.lcsc_candidates.is_empty() {
-            // Try resolving from LCSC candidates
-            if let Some((_lcsc, p)) = resolve_best_lcsc(&entry.lcsc_candidates, &client) {
-                let status = if p.stock >= required_qty as i64 {
-                    if p.basic {
-                        BomStatus::Ok
-                    } else {
-                        BomStatus::Extended
-                    }
-                } else if p.stock > 0 {
-                    BomStatus::Limited
-                } else {
-                    BomStatus::Missing
-                };
-                (Some(p), status)
-            } else {
-                (None, BomStatus::Missing)
-            }
-        } else if let Some(ref mpn) = entry.mpn {
-            // Search by MPN
-            let parts = client.search(mpn, 1, 5).unwrap_or_default();
-            if let Some(p) = parts.into_iter().find(|p| p.basic) {
-                let status = if p.stock >= required_qty as i64 {
-                    BomStatus::Ok
-                } else if p.stock > 0 {
-                    BomStatus::Limited
-                } else {
-                    BomStatus::Missing
-                };
-                (Some(p), status)
-            } else {
-                (None, BomStatus::Missing)
-            }
-        } else {
-            (None, BomStatus::Missing)
-        };
+    let summary = BomSummary::from_results(&results);
 
-        results.push(BomCheckResult {
-            entry,
-            part,
-            status,
-        });
+    if json && options.summary_only {
+        println!("{}", super::to_json(&summary, options.json_compact)?);
+        return Ok(());
     }
 
+    let swaps = if options.recommend_basic && !options.summary_only {
+        recommend_basic_swaps(&results, options, source.as_ref())
+    } else {
+        Vec::new()
+    };
+
     if json {
+        let mut swaps = swaps.into_iter();
         let json_results: Vec<BomCheckJson> = results
             .into_iter()
-            .map(|r| BomCheckJson {
-                designators: r.entry.designators,
-                status: r.status,
-                lcsc: r.part.as_ref().map(|p| p.lcsc.clone()),
-                mpn: r.entry.mpn,
-                value: r.entry.value,
-                package: r.entry.package,
-                stock: r.part.as_ref().map(|p| p.stock),
-                price_at_100: r.part.as_ref().and_then(|p| p.price_at_qty(100)),
-                dnp: r.entry.dnp,
+            .map(|r| {
+                let order_qty = options.order_qty(&r.entry, r.part.as_ref());
+                let quote = r.part.as_ref().and_then(|p| {
+                    p.interpolated_price_at_qty(i32::try_from(order_qty).unwrap_or(i32::MAX))
+                });
+                let warnings = r.diagnostics();
+                BomCheckJson {
+                    designators: r.entry.designators,
+                    status: r.status,
+                    lcsc: r.part.as_ref().map(|p| p.lcsc.clone()),
+                    mpn: r.entry.mpn,
+                    value: r.entry.value,
+                    package: r.entry.package,
+                    stock: r.part.as_ref().map(|p| p.stock),
+                    price_at_100: r.part.as_ref().and_then(|p| p.price_at_qty(100)),
+                    order_qty,
+                    line_cost: quote.map(|q| q.price * order_qty as f64),
+                    price_basis: quote.map(|q| q.basis),
+                    lcsc_url: r.part.as_ref().filter(|_| show_url).map(|p| p.lcsc_url()),
+                    error: r.error,
+                    dnp: r.entry.dnp,
+                    warnings,
+                    basic_alternative: swaps.next().flatten(),
+                }
             })
             .collect();
-        println!("{}", serde_json::to_string_pretty(&json_results)?);
+        println!("{}", super::to_json(&json_results, options.json_compact)?);
         return Ok(());
     }
 
-    // Build table rows and tally status counts
-    let mut ok_count = 0;
-    let mut limited_count = 0;
-    let mut missing_count = 0;
-    let mut extended_count = 0;
-    let mut dnp_count = 0;
+    if !options.summary_only {
+        print_check_table(&results, options);
+    }
+    println!(
+        "{} Ok  {} Limited  {} Extended  {} Missing  {} DNP  {} Error",
+        "■".green(),
+        "■".yellow(),
+        "■".blue(),
+        "■".red(),
+        "■".dimmed(),
+        "■".magenta()
+    );
+
+    // Print summary
+    println!();
+    println!(
+        "{} OK: {}, Limited: {}, Extended: {}, Missing: {}, DNP: {}, Error: {}",
+        "Summary:".bold(),
+        summary.ok.to_string().green(),
+        summary.limited.to_string().yellow(),
+        summary.extended.to_string().blue(),
+        summary.missing.to_string().red(),
+        summary.dnp.to_string().dimmed(),
+        summary.error.to_string().magenta()
+    );
+
+    if summary.missing > 0 {
+        println!(
+            "\n{} {} parts missing - search for alternatives with `pcb jlcpcb search`",
+            "!".yellow().bold(),
+            summary.missing
+        );
+    }
+
+    if summary.price_unknown > 0 {
+        println!(
+            "\n{} {} parts have no listed price (price on request) - check them on LCSC",
+            "!".yellow().bold(),
+            summary.price_unknown
+        );
+    }
+
+    if options.recommend_basic && !options.summary_only {
+        print_basic_swaps(&results, &swaps);
+    }
+
+    if summary.error > 0 {
+        println!(
+            "\n{} {} lookups failed - re-run to retry",
+            "✗".red().bold(),
+            summary.error
+        );
+    }
+
+    if !options.summary_only {
+        let diagnostics: Vec<Diagnostic> =
+            results.iter().flat_map(BomCheckResult::diagnostics).collect();
+        if !diagnostics.is_empty() {
+            eprintln!();
+            super::print_diagnostics(&diagnostics);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the per-line BOM check table.
+fn print_check_table(results: &[BomCheckResult], options: &CheckOptions) {
+    let priced_qtys: Vec<i64> = results
+        .iter()
+        .filter_map(|r| Some(options.order_qty(&r.entry, Some(r.part.as_ref()?))))
+        .collect();
+    let header = check_price_header(&priced_qtys);
+    // Lines ordered in different amounts each note their own quantity
+    let per_line_qty = header.is_none();
 
     let rows: Vec<BomCheckRow> = results
         .iter()
         .map(|result| {
-            match result.status {
-                BomStatus::Ok => ok_count += 1,
-                BomStatus::Limited => limited_count += 1,
-                BomStatus::Missing => missing_count += 1,
-                BomStatus::Extended => extended_count += 1,
-                BomStatus::Dnp => dnp_count += 1,
-            }
-
-            let designators = if result.entry.designators.len() > 3 {
-                format!(
-                    "{}-{}",
-                    result.entry.designators.first().unwrap(),
-                    result.entry.designators.last().unwrap()
-                )
-            } else {
-                result.entry.designators.join(",")
-            };
-
-            let (lcsc, stock, price) = if let Some(ref p) = result.part {
+            let designators =
+                format_designators(&result.entry.designators, options.collapse_threshold);
+
+            let (lcsc, stock, price, url) = if let Some(ref p) = result.part {
+                let order_qty = options.order_qty(&result.entry, Some(p));
+                let price = p
+                    .price_at_qty(i32::try_from(order_qty).unwrap_or(i32::MAX))
+                    .map(|v| format!("${:.4}", v))
+                    .unwrap_or_else(|| "—".to_string());
                 (
                     p.lcsc.clone(),
                     format_stock(p.stock),
-                    p.price_at_qty(100)
-                        .map(|v| format!("${:.4}", v))
-                        .unwrap_or_else(|| "—".to_string()),
+                    if per_line_qty {
+                        format!("{} @{}", price, order_qty)
+                    } else {
+                        price
+                    },
+                    p.lcsc_url(),
                 )
             } else {
-                ("—".to_string(), "—".to_string(), "—".to_string())
+                ("—".to_string(), "—".to_string(), "—".to_string(), "—".to_string())
             };
 
             BomCheckRow {
@@ -289,51 +931,323 @@ pub fn execute_check(bom_path: &PathBuf, quantity: i32, include_dnp: bool, json:
                 lcsc,
                 stock,
                 price,
+                url,
             }
         })
         .collect();
 
-    let table = Table::new(rows)
+    let mut table = Table::new(rows);
+    table
         .with(Style::rounded())
-        .with(Modify::new(tabled::settings::object::Columns::new(3..=4)).with(Alignment::right()))
-        .to_string();
+        .with(Modify::new(Columns::new(3..=4)).with(Alignment::right()))
+        .with(Modify::new(Cell::new(0, 4)).with(header.unwrap_or_else(|| "Price".to_string())));
+    if !options.show_url {
+        table.with(Remove::column(Columns::single(5)));
+    }
 
     println!("\n{}", table);
-    println!(
-        "{} Ok  {} Limited  {} Extended  {} Missing  {} DNP",
-        "■".green(),
-        "■".yellow(),
-        "■".blue(),
-        "■".red(),
-        "■".dimmed()
-    );
+}
+
+/// Price column header for lines ordered in `qtys` parts: `Price@<qty>` when
+/// every line orders the same amount, `None` when they differ.
+fn check_price_header(qtys: &[i64]) -> Option<String> {
+    match qtys {
+        [] => Some("Price".to_string()),
+        [first, rest @ ..] if rest.iter().all(|q| q == first) => Some(format!("Price@{}", first)),
+        _ => None,
+    }
+}
+
+/// Component cost of one part category.
+#[derive(Debug, Serialize)]
+struct CategoryCost {
+    category: String,
+    /// BOM lines in this category
+    lines: usize,
+    cost: f64,
+}
+
+/// Cost totals for building a BOM.
+#[derive(Debug, Serialize)]
+struct BomCost {
+    quantity: i32,
+    /// Parts cost for all boards, interpolating between price breaks at each
+    /// part's order quantity for all its lines combined
+    component_cost: f64,
+    /// Unique parts that pay the extended part loading fee
+    extended_parts: usize,
+    /// `extended_parts` times [`EXTENDED_PART_FEE`]
+    extended_fee: f64,
+    /// Component cost plus extended fee
+    total: f64,
+    /// `total` divided by the board quantity
+    per_unit: f64,
+    /// Most expensive category first
+    categories: Vec<CategoryCost>,
+    /// Lines whose part has no listed price, as `designators (LCSC)`; their
+    /// cost is missing from the totals
+    price_unknown: Vec<String>,
+    /// Designators of placed lines that couldn't be resolved
+    unresolved: Vec<String>,
+}
+
+impl BomCost {
+    fn from_results(results: &[BomCheckResult], quantity: i32) -> Self {
+        let mut component_cost = 0.0;
+        let mut categories: HashMap<String, CategoryCost> = HashMap::new();
+        let mut extended = HashSet::new();
+        let mut price_unknown = Vec::new();
+        let mut unresolved = Vec::new();
+
+        let mut lines = Vec::new();
+        // Required quantity and line count per LCSC code
+        let mut part_required: HashMap<&str, (i64, usize)> = HashMap::new();
+        for result in results.iter().filter(|r| r.status != BomStatus::Dnp) {
+            let designators = result.entry.designators.join(",");
+            let Some(part) = &result.part else {
+                unresolved.push(designators);
+                continue;
+            };
+            // Unpriced parts still pay the loading fee
+            if !part.basic && !part.preferred {
+                extended.insert(part.lcsc.clone());
+            }
+
+            let required = result.entry.quantity as i64 * quantity as i64;
+            let total = part_required.entry(&part.lcsc).or_default();
+            total.0 += required;
+            total.1 += 1;
+            lines.push((designators, part, required));
+        }
+
+        // A part on several lines is ordered once: its minimum order and
+        // price break apply to the combined quantity, and the cost is split
+        // over the lines by their share of it
+        for (designators, part, required) in lines {
+            let (total_required, line_count) = part_required[part.lcsc.as_str()];
+            let qty = OrderRules::default().order_qty_for(total_required, part);
+            let Some(quote) = part.interpolated_price_at_qty(i32::try_from(qty).unwrap_or(i32::MAX))
+            else {
+                price_unknown.push(format!("{} ({})", designators, part.lcsc));
+                continue;
+            };
+            let share = if total_required > 0 {
+                required as f64 / total_required as f64
+            } else {
+                1.0 / line_count as f64
+            };
+            let cost = quote.price * qty as f64 * share;
+
+            component_cost += cost;
+            let category = if part.category.is_empty() {
+                "Uncategorized".to_string()
+            } else {
+                part.category.clone()
+            };
+            let entry = categories.entry(category.clone()).or_insert(CategoryCost {
+                category,
+                lines: 0,
+                cost: 0.0,
+            });
+            entry.lines += 1;
+            entry.cost += cost;
+        }
+
+        let mut categories: Vec<CategoryCost> = categories.into_values().collect();
+        categories.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(a.category.cmp(&b.category)));
+
+        let extended_fee = extended.len() as f64 * EXTENDED_PART_FEE;
+        let total = component_cost + extended_fee;
+        Self {
+            quantity,
+            component_cost,
+            extended_parts: extended.len(),
+            extended_fee,
+            total,
+            per_unit: total / f64::from(quantity.max(1)),
+            categories,
+            price_unknown,
+            unresolved,
+        }
+    }
+}
+
+/// Table row for the BOM cost breakdown.
+#[derive(Tabled)]
+struct CategoryCostRow {
+    #[tabled(rename = "Category")]
+    category: String,
+    #[tabled(rename = "Lines")]
+    lines: usize,
+    #[tabled(rename = "Cost")]
+    cost: String,
+}
+
+/// Execute the BOM cost command.
+pub fn execute_cost(bom_path: &PathBuf, options: &CostOptions) -> Result<()> {
+    let source = JlcpcbClient::new().with_cache(!options.refresh);
+    let results: Vec<BomCheckResult> = load_bom(bom_path)?
+        .into_iter()
+        .map(|entry| check_entry(entry, options.quantity, false, OrderRules::default(), &source))
+        .collect();
+    let cost = BomCost::from_results(&results, options.quantity);
+
+    if options.json {
+        println!("{}", super::to_json(&cost, options.json_compact)?);
+        return Ok(());
+    }
+
+    if !cost.categories.is_empty() || !cost.price_unknown.is_empty() {
+        let mut rows: Vec<CategoryCostRow> = cost
+            .categories
+            .iter()
+            .map(|c| CategoryCostRow {
+                category: c.category.clone(),
+                lines: c.lines,
+                cost: format!("${:.2}", c.cost),
+            })
+            .collect();
+        if !cost.price_unknown.is_empty() {
+            rows.push(CategoryCostRow {
+                category: "Price unknown".to_string(),
+                lines: cost.price_unknown.len(),
+                cost: "?".to_string(),
+            });
+        }
+        let mut table = Table::new(rows);
+        table
+            .with(Style::rounded())
+            .with(Modify::new(Columns::new(1..=2)).with(Alignment::right()));
+        println!("\n{}", table);
+    }
 
-    // Print summary
     println!();
     println!(
-        "{} OK: {}, Limited: {}, Extended: {}, Missing: {}, DNP: {}",
-        "Summary:".bold(),
-        ok_count.to_string().green(),
-        limited_count.to_string().yellow(),
-        extended_count.to_string().blue(),
-        missing_count.to_string().red(),
-        dnp_count.to_string().dimmed()
+        "{} ${:.2} for {} boards",
+        "Components:".bold(),
+        cost.component_cost,
+        cost.quantity
+    );
+    println!(
+        "{} ${:.2} ({} extended parts × ${:.2})",
+        "Extended fee:".bold(),
+        cost.extended_fee,
+        cost.extended_parts,
+        EXTENDED_PART_FEE
+    );
+    println!(
+        "{} {} (${:.4} per board)",
+        "Total:".bold(),
+        format!("${:.2}", cost.total).green(),
+        cost.per_unit
     );
 
-    if missing_count > 0 {
+    if !cost.price_unknown.is_empty() {
         println!(
-            "\n{} {} parts missing - search for alternatives with `pcb jlcpcb search`",
+            "\n{} {} lines have no listed price (price on request), so the total is \
+             understated: {}",
             "!".yellow().bold(),
-            missing_count
+            cost.price_unknown.len(),
+            cost.price_unknown.join("; ")
+        );
+    }
+    if !cost.unresolved.is_empty() {
+        println!(
+            "\n{} {} lines couldn't be resolved and aren't included: {}",
+            "!".yellow().bold(),
+            cost.unresolved.len(),
+            cost.unresolved.join("; ")
         );
     }
 
     Ok(())
 }
 
+/// Resolve every placed BOM line to a part, in BOM order and without repeats.
+///
+/// Lines that can't be resolved are reported on stderr and skipped.
+pub fn resolve_bom_parts(bom_path: &PathBuf, source: &dyn PartSource) -> Result<Vec<JlcPart>> {
+    let mut seen = HashSet::new();
+    let mut parts = Vec::new();
+
+    for entry in load_bom(bom_path)?.into_iter().filter(|e| !e.dnp) {
+        match resolve_entry(&entry, source) {
+            Ok(Some((lcsc, part))) => {
+                if seen.insert(lcsc) {
+                    parts.push(part);
+                }
+            }
+            Ok(None) => eprintln!(
+                "{} No LCSC part for {}",
+                "!".yellow(),
+                entry.designators.join(",")
+            ),
+            Err(e) => eprintln!(
+                "{} {}: {:#}",
+                "✗".red(),
+                entry.designators.join(","),
+                e
+            ),
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Write Missing and Limited check results to a CSV worklist.
+///
+/// Returns the number of lines written.
+fn write_missing_csv(path: &Path, results: &[BomCheckResult]) -> Result<usize> {
+    let mut writer = csv::Writer::from_path(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    writer.write_record(["Status", "Designators", "Value", "Package", "MPN", "LCSC", "Stock"])?;
+
+    let mut count = 0;
+    for result in results
+        .iter()
+        .filter(|r| matches!(r.status, BomStatus::Missing | BomStatus::Limited))
+    {
+        let status = match result.status {
+            BomStatus::Missing => "missing",
+            _ => "limited",
+        };
+        let entry = &result.entry;
+        writer.write_record([
+            status,
+            &entry.designators.join(","),
+            entry.value.as_deref().unwrap_or_default(),
+            entry.package.as_deref().unwrap_or_default(),
+            entry.mpn.as_deref().unwrap_or_default(),
+            result.part.as_ref().map_or("", |p| p.lcsc.as_str()),
+            &result.part.as_ref().map(|p| p.stock.to_string()).unwrap_or_default(),
+        ])?;
+        count += 1;
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
 /// Execute the BOM export command (JLCPCB CSV format).
-pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, json: bool, refresh: bool) -> Result<()> {
-    let all_entries = load_bom(bom_path)?;
+pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, options: &ExportOptions) -> Result<()> {
+    let ExportOptions {
+        include_dnp,
+        json,
+        refresh,
+        ..
+    } = *options;
+
+    let comment_format = options
+        .comment_format
+        .as_deref()
+        .unwrap_or(DEFAULT_COMMENT_FORMAT);
+    validate_comment_format(comment_format)?;
+
+    let mut all_entries = load_bom(bom_path)?;
+    if let Some(map_path) = &options.designator_map {
+        let map = load_designator_map(map_path)?;
+        apply_designator_map(&mut all_entries, &map);
+    }
 
     if all_entries.is_empty() {
         if json {
@@ -344,6 +1258,18 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
         return Ok(());
     }
 
+    let has_sides = all_entries.iter().any(|e| !e.sides.is_empty());
+    let mut all_entries = split_by_side(all_entries);
+    if let Some(side) = options.side {
+        if !has_sides {
+            anyhow::bail!(
+                "--side needs board sides from the layout; \
+                 use a .zen BOM whose layout_path points at a .kicad_pcb"
+            );
+        }
+        all_entries.retain(|e| e.side() == Some(side));
+    }
+
     let (entries, dnp_entries): (Vec<_>, Vec<_>) = all_entries
         .into_iter()
         .partition(|e| include_dnp || !e.dnp);
@@ -380,32 +1306,59 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
     };
 
     if let Some(ref mut f) = output_file {
-        writeln!(f, "Comment,Designator,Footprint,LCSC Part #")?;
+        let mut header = String::from("Comment,Designator,Footprint,LCSC Part #");
+        if options.with_quantity {
+            header.push_str(",Quantity");
+        }
+        if has_sides {
+            header.push_str(",Side");
+        }
+        writeln!(f, "{}", header)?;
     }
 
     let mut exported_count = 0;
     let mut missing_count = 0;
+    let mut mismatches = 0;
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
 
     for entry in entries {
+        let side = entry.side();
         let designators_str = entry.designators.join(",");
         let footprint = entry.package.clone().unwrap_or_default();
+        // Optional trailing columns, in header order
+        let mut extra_cols = String::new();
+        if options.with_quantity {
+            extra_cols.push_str(&format!(",{}", entry.quantity));
+        }
+        if has_sides {
+            extra_cols.push_str(&format!(",{}", side.unwrap_or(Side::Top).label()));
+        }
+        let quantity = options.with_quantity.then_some(entry.quantity);
+        let mut warnings = Vec::new();
 
         // Try to get LCSC number
-        let resolved = if !entry.lcsc_candidates.is_empty() {
-            resolve_best_lcsc(&entry.lcsc_candidates, &client)
-        } else if let Some(ref mpn) = entry.mpn {
-            // Search for LCSC number by MPN
-            let parts = client.search(mpn, 1, 5).unwrap_or_default();
-            parts
-                .into_iter()
-                .find(|p| p.basic)
-                .map(|p| (p.lcsc.clone(), p))
-        } else {
-            None
+        let (resolved, lookup_failed) = match resolve_entry(&entry, &client) {
+            Ok(resolved) => (resolved, false),
+            Err(e) => {
+                warnings.push(
+                    Diagnostic::error(format!("{:#}", e)).with_subject(&designators_str),
+                );
+                (None, true)
+            }
         };
 
         if let Some((lcsc, part)) = resolved {
-            let comment = format!("{} {}", part.mpn, part.description);
+            let comment = format_comment(comment_format, &part, &entry);
+            if options.validate && part.conflicts_with_package(&footprint) {
+                warnings.push(
+                    Diagnostic::error(format!(
+                        "Footprint {} doesn't match {} package {}",
+                        footprint, lcsc, part.package
+                    ))
+                    .with_subject(&designators_str),
+                );
+                mismatches += 1;
+            }
 
             if json {
                 json_rows.push(BomExportJson {
@@ -413,15 +1366,19 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
                     designators: entry.designators,
                     footprint,
                     lcsc: Some(lcsc),
+                    quantity,
+                    side,
+                    warnings: warnings.clone(),
                 });
             } else {
                 writeln!(
                     output_file.as_mut().unwrap(),
-                    "\"{}\",\"{}\",\"{}\",\"{}\"",
+                    "\"{}\",\"{}\",\"{}\",\"{}\"{}",
                     comment.replace('"', "\"\""),
                     designators_str,
                     footprint,
-                    lcsc
+                    lcsc,
+                    extra_cols
                 )?;
             }
             exported_count += 1;
@@ -430,6 +1387,13 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
                 .mpn
                 .clone()
                 .unwrap_or_else(|| entry.value.clone().unwrap_or_default());
+            // A failed lookup already has its error
+            if !lookup_failed {
+                warnings.push(
+                    Diagnostic::warn("No LCSC part found; exported without one")
+                        .with_subject(&designators_str),
+                );
+            }
 
             if json {
                 json_rows.push(BomExportJson {
@@ -437,23 +1401,28 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
                     designators: entry.designators,
                     footprint,
                     lcsc: None,
+                    quantity,
+                    side,
+                    warnings: warnings.clone(),
                 });
             } else {
                 writeln!(
                     output_file.as_mut().unwrap(),
-                    "\"{}\",\"{}\",\"{}\",\"\"",
+                    "\"{}\",\"{}\",\"{}\",\"\"{}",
                     comment.replace('"', "\"\""),
                     designators_str,
-                    footprint
+                    footprint,
+                    extra_cols
                 )?;
             }
             missing_count += 1;
         }
+        diagnostics.extend(warnings);
     }
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&json_rows)?);
-        return Ok(());
+        println!("{}", super::to_json(&json_rows, options.json_compact)?);
+        return package_mismatch_result(mismatches);
     }
 
     println!(
@@ -484,20 +1453,158 @@ pub fn execute_export(bom_path: &PathBuf, output: &PathBuf, include_dnp: bool, j
         );
     }
 
-    Ok(())
+    if !diagnostics.is_empty() {
+        eprintln!();
+        super::print_diagnostics(&diagnostics);
+    }
+
+    package_mismatch_result(mismatches)
+}
+
+/// Turn the `--validate` mismatch count into the export's result.
+fn package_mismatch_result(mismatches: usize) -> Result<()> {
+    if mismatches > 0 {
+        anyhow::bail!(
+            "{} BOM footprint(s) don't match the JLCPCB package; see the warnings",
+            mismatches
+        );
+    }
+    Ok(())
+}
+
+/// Check that a comment template only uses known placeholders.
+fn validate_comment_format(template: &str) -> Result<()> {
+    for caps in comment_placeholder().captures_iter(template) {
+        if !COMMENT_FIELDS.contains(&&caps[1]) {
+            anyhow::bail!(
+                "Unknown placeholder {{{}}} in comment format (expected one of: {})",
+                &caps[1],
+                COMMENT_FIELDS.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Build the Comment column from a template like `{value} {voltage} {package}`.
+///
+/// Missing fields expand to nothing and the resulting whitespace is collapsed.
+fn format_comment(template: &str, part: &JlcPart, entry: &BomEntry) -> String {
+    let attrs = &part.attributes;
+    let value = attrs
+        .resistance
+        .as_ref()
+        .or(attrs.capacitance.as_ref())
+        .or(attrs.inductance.as_ref())
+        .or(entry.value.as_ref());
+    let package = Some(&part.package)
+        .filter(|p| !p.is_empty())
+        .or(entry.package.as_ref());
+
+    let expanded = comment_placeholder().replace_all(template, |caps: &regex::Captures| {
+        let field = match &caps[1] {
+            "mpn" => Some(&part.mpn),
+            "description" => Some(&part.description),
+            "value" => value,
+            "package" => package,
+            "tolerance" => attrs.tolerance.as_ref(),
+            "voltage" => attrs.voltage.as_ref(),
+            "dielectric" => attrs.dielectric.as_ref(),
+            "manufacturer" => Some(&part.manufacturer),
+            "lcsc" => Some(&part.lcsc),
+            _ => None,
+        };
+        field.cloned().unwrap_or_default()
+    });
+
+    expanded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Load a `from,to` designator rename map from CSV.
+///
+/// A leading `from,to` header row is optional.
+fn load_designator_map(path: &Path) -> Result<HashMap<String, String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read designator map: {}", path.display()))?;
+    parse_designator_map(&content)
+        .with_context(|| format!("Failed to parse designator map: {}", path.display()))
+}
+
+fn parse_designator_map(content: &str) -> Result<HashMap<String, String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut map = HashMap::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let (Some(from), Some(to)) = (record.get(0), record.get(1)) else {
+            anyhow::bail!("Line {}: expected `from,to`", i + 1);
+        };
+        if i == 0 && from.eq_ignore_ascii_case("from") && to.eq_ignore_ascii_case("to") {
+            continue;
+        }
+        if from.is_empty() || to.is_empty() {
+            anyhow::bail!("Line {}: empty designator", i + 1);
+        }
+        map.insert(from.to_string(), to.to_string());
+    }
+
+    Ok(map)
+}
+
+/// Designators for the check table.
+///
+/// Lists longer than `threshold` collapse to their natural-order first and
+/// last designator plus a count (`C1–C20 (20)`).
+fn format_designators(designators: &[String], threshold: usize) -> String {
+    if threshold == 0 || designators.len() <= threshold {
+        return designators.join(",");
+    }
+    let first = designators.iter().min_by(|a, b| natural_cmp(a, b));
+    let last = designators.iter().max_by(|a, b| natural_cmp(a, b));
+    match (first, last) {
+        (Some(first), Some(last)) => format!("{}–{} ({})", first, last, designators.len()),
+        _ => String::new(),
+    }
+}
+
+/// Rename designators using the map; unmapped designators pass through.
+fn apply_designator_map(entries: &mut [BomEntry], map: &HashMap<String, String>) {
+    for designator in entries.iter_mut().flat_map(|e| e.designators.iter_mut()) {
+        if let Some(to) = map.get(designator.as_str()) {
+            *designator = to.clone();
+        }
+    }
 }
 
-/// Load BOM entries from a file (JSON or .zen).
+/// BOM path that reads a JSON BOM from stdin.
+const STDIN_BOM: &str = "-";
+
+/// Load BOM entries from a file (JSON, CSV or .zen), or JSON from stdin for `-`.
 fn load_bom(path: &PathBuf) -> Result<Vec<BomEntry>> {
-    if path.extension().is_some_and(|e| e == "json") {
+    if path.as_os_str() == STDIN_BOM {
+        read_bom_json(std::io::stdin().lock()).context("Failed to read BOM from stdin")
+    } else if path.extension().is_some_and(|e| e == "json") {
         let content = fs::read_to_string(path).context("Failed to read BOM file")?;
         load_bom_json(&content)
+    } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+        let content = fs::read_to_string(path).context("Failed to read BOM file")?;
+        load_bom_csv(&content)
     } else {
         // Assume it's a .zen file - shell out to `pcb bom` to get JSON
         load_bom_from_zen(path)
     }
 }
 
+/// Load a JSON BOM from a reader.
+fn read_bom_json(mut reader: impl Read) -> Result<Vec<BomEntry>> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    load_bom_json(&content)
+}
+
 // ── JSON deserialization structs ──────────────────────────────────────────────
 
 /// Existing grouped BOM format (plural `designators`).
@@ -552,42 +1659,155 @@ struct PcbBomOffer {
     stock: Option<i64>,
 }
 
+/// Header-name predicate for a BOM CSV column.
+type HeaderMatch = fn(&str) -> bool;
+
+/// Lowercase a header and drop everything but letters and digits, so
+/// "LCSC Part #" and "lcsc_part" compare equal.
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether a CSV header names the LCSC/JLCPCB part number column.
+///
+/// Accepts the variants KiCad BOM exports and fab plugins use: "LCSC",
+/// "LCSC Part #", "LCSC Part Number", "JLCPCB Part", "JLCPCB Part #".
+fn is_lcsc_header(header: &str) -> bool {
+    let h = normalize_header(header);
+    h == "lcsc" || h == "jlcpcb" || h.starts_with("lcscpart") || h.starts_with("jlcpcbpart")
+}
+
+/// Load BOM from a CSV export (e.g. KiCad's built-in BOM).
+///
+/// Needs a designator column ("Reference", "Designator", ...); designators
+/// within a cell may be separated by commas or spaces. LCSC, value,
+/// footprint/package, MPN and DNP columns are picked up when present.
+fn load_bom_csv(content: &str) -> Result<Vec<BomEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = reader.headers().context("Failed to read BOM CSV header")?.clone();
+
+    let column = |matches: HeaderMatch| headers.iter().position(matches);
+    let designator_col = column(|h| {
+        matches!(
+            normalize_header(h).as_str(),
+            "reference" | "references" | "ref" | "refs" | "designator" | "designators"
+        )
+    })
+    .context("BOM CSV is missing a Reference/Designator column")?;
+    let lcsc_col = column(is_lcsc_header);
+    let value_col = column(|h| normalize_header(h) == "value");
+    let package_col = column(|h| matches!(normalize_header(h).as_str(), "footprint" | "package"));
+    let mpn_col = column(|h| {
+        matches!(
+            normalize_header(h).as_str(),
+            "mpn" | "manufacturerpartnumber" | "mfrpartnumber" | "partnumber"
+        )
+    });
+    let dnp_col = column(|h| normalize_header(h) == "dnp");
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+
+        let designators: Vec<String> = record
+            .get(designator_col)
+            .unwrap_or_default()
+            .split([',', ' ', ';'])
+            .filter(|d| !d.is_empty())
+            .map(str::to_string)
+            .collect();
+        if designators.is_empty() {
+            continue;
+        }
+
+        entries.push(BomEntry {
+            quantity: designators.len(),
+            designators,
+            lcsc_candidates: field(lcsc_col)
+                .and_then(|code| normalize_lcsc(&code))
+                .into_iter()
+                .collect(),
+            mpn: field(mpn_col),
+            value: field(value_col),
+            package: field(package_col),
+            dnp: field(dnp_col).is_some_and(|s| {
+                matches!(s.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "dnp" | "x")
+            }),
+            sides: Vec::new(),
+        });
+    }
+
+    Ok(entries)
+}
+
 /// Load BOM from JSON format.
 ///
 /// Tries the flat `pcb bom -f json` format first (singular `designator`),
-/// falls back to the grouped format (plural `designators`).
+/// falls back to the grouped format (plural `designators`). Hand-written
+/// files that aren't strict JSON are retried as JSON5, which allows
+/// comments and trailing commas.
 fn load_bom_json(content: &str) -> Result<Vec<BomEntry>> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(strict_err) => json5::from_str(content)
+            .map_err(|_| strict_err)
+            .context("Failed to parse BOM JSON")?,
+    };
+
     // Try flat pcb-bom format first
-    if let Ok(flat_entries) = serde_json::from_str::<Vec<PcbBomEntry>>(content) {
+    if let Ok(flat_entries) = Vec::<PcbBomEntry>::deserialize(&value) {
         return Ok(group_pcb_bom_entries(flat_entries));
     }
 
     // Fall back to grouped format
-    let entries: Vec<JsonBomEntry> =
-        serde_json::from_str(content).context("Failed to parse BOM JSON")?;
+    let entries = Vec::<JsonBomEntry>::deserialize(&value).context("Failed to parse BOM JSON")?;
 
     Ok(entries
         .into_iter()
         .map(|e| BomEntry {
             quantity: e.designators.len(),
             designators: e.designators,
-            lcsc_candidates: e.lcsc.into_iter().collect(),
+            lcsc_candidates: e.lcsc.iter().filter_map(|c| normalize_lcsc(c)).collect(),
             mpn: e.mpn,
             value: e.value,
             package: e.package,
             dnp: e.dnp.unwrap_or(false),
+            sides: Vec::new(),
         })
         .collect())
 }
 
+/// Accumulator for a group of flat BOM entries:
+/// (designators, mpn, value, package, description, offers, dnp).
+type BomGroup = (
+    Vec<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Vec<PcbBomOffer>,
+    bool,
+);
+
 /// Group flat per-designator entries into merged BomEntry values.
 ///
 /// Entries are grouped by MPN (same MPN = same physical part).
 /// For entries without MPN, fall back to (value, package) as key.
 fn group_pcb_bom_entries(entries: Vec<PcbBomEntry>) -> Vec<BomEntry> {
     // Key: either MPN or (value, package) tuple serialized
-    let mut groups: HashMap<String, (Vec<String>, Option<String>, Option<String>, Option<String>, Option<String>, Vec<PcbBomOffer>, bool)> =
-        HashMap::new();
+    let mut groups: HashMap<String, BomGroup> = HashMap::new();
     // Preserve insertion order
     let mut key_order: Vec<String> = Vec::new();
 
@@ -634,6 +1854,7 @@ fn group_pcb_bom_entries(entries: Vec<PcbBomEntry>) -> Vec<BomEntry> {
                 value,
                 package,
                 dnp,
+                sides: Vec::new(),
             })
         })
         .collect()
@@ -660,20 +1881,12 @@ fn extract_lcsc_candidates(offers: &[PcbBomOffer]) -> Vec<String> {
             if stock <= 0 {
                 return None;
             }
-            // Normalize: ensure it starts with 'C'
-            let normalized = if part_id.starts_with('C') || part_id.starts_with('c') {
-                format!("C{}", &part_id[1..])
-            } else if part_id.chars().all(|c| c.is_ascii_digit()) {
-                format!("C{}", part_id)
-            } else {
-                part_id.to_string()
-            };
-            Some((normalized, stock))
+            Some((normalize_lcsc(part_id)?, stock))
         })
         .collect();
 
-    // Sort by stock descending
-    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+    // Sort by stock descending; LCSC order breaks ties so exports are reproducible
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
     // Deduplicate preserving order
     let mut seen = Vec::new();
@@ -685,18 +1898,59 @@ fn extract_lcsc_candidates(offers: &[PcbBomOffer]) -> Vec<String> {
     seen
 }
 
+/// Environment variable overriding the `pcb` binary used for .zen BOMs.
+const PCB_BIN_ENV: &str = "PCB_BIN";
+
+/// Environment variable with extra whitespace-separated `pcb bom` arguments.
+const PCB_BOM_ARGS_ENV: &str = "PCB_BOM_ARGS";
+
+/// Program and arguments for `pcb bom -f json`, before the .zen path.
+///
+/// `bin` and `extra_args` come from [`PCB_BIN_ENV`] and [`PCB_BOM_ARGS_ENV`].
+fn pcb_bom_command(bin: Option<String>, extra_args: Option<String>) -> (String, Vec<String>) {
+    let bin = bin
+        .filter(|b| !b.trim().is_empty())
+        .unwrap_or_else(|| "pcb".to_string());
+    let mut args: Vec<String> = ["bom", "-f", "json"].map(String::from).to_vec();
+    args.extend(
+        extra_args
+            .iter()
+            .flat_map(|a| a.split_whitespace())
+            .map(String::from),
+    );
+    (bin, args)
+}
+
+/// The `pcb` binary run for .zen BOMs ([`PCB_BIN_ENV`], else `pcb`).
+pub(crate) fn pcb_bin() -> String {
+    pcb_bom_command(std::env::var(PCB_BIN_ENV).ok(), None).0
+}
+
 /// Load BOM from a .zen file by shelling out to `pcb bom -f json`.
 fn load_bom_from_zen(path: &PathBuf) -> Result<Vec<BomEntry>> {
-    let output = Command::new("pcb")
-        .args(["bom", "-f", "json"])
+    let (bin, args) = pcb_bom_command(
+        std::env::var(PCB_BIN_ENV).ok(),
+        std::env::var(PCB_BOM_ARGS_ENV).ok(),
+    );
+    let command_line = format!("{} {} {}", bin, args.join(" "), path.display());
+
+    let output = Command::new(&bin)
+        .args(&args)
         .arg(path)
         .output()
-        .context("Failed to run `pcb bom`. Is the `pcb` CLI installed?")?;
+        .with_context(|| {
+            format!(
+                "Failed to run `{}`. Is the `pcb` CLI installed? \
+                 Set {} to its path if it isn't on PATH.",
+                command_line, PCB_BIN_ENV
+            )
+        })?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!(
-            "`pcb bom -f json` failed (exit {}):\n{}",
+            "`{}` failed (exit {}):\n{}",
+            command_line,
             output.status.code().unwrap_or(-1),
             stderr.trim()
         );
@@ -710,22 +1964,18 @@ fn load_bom_from_zen(path: &PathBuf) -> Result<Vec<BomEntry>> {
         let layout_to_zen = build_layout_to_zen_map(&flat_entries);
         let mut entries = group_pcb_bom_entries(flat_entries);
 
-        let dnp_refs = read_layout_dnp(path, &layout_to_zen);
-        if !dnp_refs.is_empty() {
-            apply_layout_dnp(&mut entries, &dnp_refs);
-        }
-
+        let footprints = read_layout_footprints(path, &layout_to_zen);
+        apply_layout_dnp(&mut entries, &footprints);
+        tag_layout_sides(&mut entries, &footprints);
         return Ok(entries);
     }
 
     // Fallback: grouped format (no per-entry mapping available)
     let mut entries = load_bom_json(&stdout)?;
 
-    let dnp_refs = read_layout_dnp(path, &HashMap::new());
-    if !dnp_refs.is_empty() {
-        apply_layout_dnp(&mut entries, &dnp_refs);
-    }
-
+    let footprints = read_layout_footprints(path, &HashMap::new());
+    apply_layout_dnp(&mut entries, &footprints);
+    tag_layout_sides(&mut entries, &footprints);
     Ok(entries)
 }
 
@@ -748,53 +1998,75 @@ fn build_layout_to_zen_map(entries: &[PcbBomEntry]) -> HashMap<String, String> {
     map
 }
 
-/// Read DNP reference designators from the `.kicad_pcb` layout file associated
-/// with a `.zen` project, translated to zen names via the provided mapping.
+/// A footprint placed in a `.kicad_pcb` layout.
+#[derive(Debug, Clone, PartialEq)]
+struct LayoutFootprint {
+    /// Reference designator
+    reference: String,
+    /// Has the `dnp` attribute
+    dnp: bool,
+    /// Side from the footprint's `(layer ...)`
+    side: Side,
+}
+
+/// Read footprints from the `.kicad_pcb` layout file associated with a
+/// `.zen` project, with references translated to zen names via the provided
+/// mapping.
 ///
 /// The `.zen` file contains a `layout_path` field pointing to a directory that
-/// holds `layout.kicad_pcb`. Footprints in that file with `(attr ... dnp)` are
-/// considered DNP. Layout refs are translated to zen names using `layout_to_zen`;
-/// unmapped refs are included as-is (handles cases where zen name equals layout
-/// ref). Returns an empty set on any failure (missing file, parse error, etc.).
-fn read_layout_dnp(zen_path: &Path, layout_to_zen: &HashMap<String, String>) -> HashSet<String> {
+/// holds `layout.kicad_pcb`. Layout refs are translated to zen names using
+/// `layout_to_zen`; unmapped refs are included as-is (handles cases where zen
+/// name equals layout ref). Returns nothing on any failure (missing file,
+/// parse error, etc.).
+fn read_layout_footprints(
+    zen_path: &Path,
+    layout_to_zen: &HashMap<String, String>,
+) -> Vec<LayoutFootprint> {
     let zen_content = match fs::read_to_string(zen_path) {
         Ok(c) => c,
-        Err(_) => return HashSet::new(),
+        Err(_) => return Vec::new(),
     };
 
     // Extract layout_path from the .zen file
     let layout_path_re = regex::Regex::new(r#"layout_path\s*=\s*"([^"]+)""#).unwrap();
     let layout_rel = match layout_path_re.captures(&zen_content) {
         Some(caps) => caps[1].to_string(),
-        None => return HashSet::new(),
+        None => return Vec::new(),
     };
 
     // Resolve to absolute: zen_dir / layout_path / "layout.kicad_pcb"
     let zen_dir = match zen_path.parent() {
         Some(d) => d,
-        None => return HashSet::new(),
+        None => return Vec::new(),
     };
     let kicad_path = zen_dir.join(&layout_rel).join("layout.kicad_pcb");
 
     let content = match fs::read_to_string(&kicad_path) {
         Ok(c) => c,
-        Err(_) => return HashSet::new(),
+        Err(_) => return Vec::new(),
     };
 
-    let layout_refs = parse_kicad_dnp(&content);
-
     // Translate layout refs to zen names
-    layout_refs
+    parse_kicad_footprints(&content)
         .into_iter()
-        .map(|r| layout_to_zen.get(&r).cloned().unwrap_or(r))
+        .map(|mut fp| {
+            if let Some(zen_name) = layout_to_zen.get(&fp.reference) {
+                fp.reference = zen_name.clone();
+            }
+            fp
+        })
         .collect()
 }
 
-/// Parse a `.kicad_pcb` file and return the set of reference designators that
-/// have the `dnp` attribute (i.e. `(attr ... dnp)` inside a `(footprint ...)` block).
-fn parse_kicad_dnp(content: &str) -> HashSet<String> {
-    let mut result = HashSet::new();
+/// Parse the top-level `(footprint ...)` blocks of a `.kicad_pcb` file.
+///
+/// DNP comes from `(attr ... dnp)`; the side from the footprint's own
+/// `(layer ...)`, which precedes any pad or text layers in the block.
+/// Footprints without a `Reference` property are skipped.
+fn parse_kicad_footprints(content: &str) -> Vec<LayoutFootprint> {
+    let mut result = Vec::new();
     let ref_re = regex::Regex::new(r#"\(property\s+"Reference"\s+"([^"]+)""#).unwrap();
+    let layer_re = regex::Regex::new(r#"\(layer\s+"([^"]+)""#).unwrap();
 
     // We scan for top-level `(footprint ` blocks (depth 1) by tracking parens.
     let bytes = content.as_bytes();
@@ -804,6 +2076,10 @@ fn parse_kicad_dnp(content: &str) -> HashSet<String> {
 
     while i < len {
         match bytes[i] {
+            b'"' => {
+                // Parens inside quoted strings must not affect depth
+                i = skip_quoted_string(bytes, i);
+            }
             b'(' => {
                 depth += 1;
                 // Check if this opens a footprint block at depth 1
@@ -819,6 +2095,10 @@ fn parse_kicad_dnp(content: &str) -> HashSet<String> {
                         let mut j = i + 1;
                         while j < len && fp_depth > 0 {
                             match bytes[j] {
+                                b'"' => {
+                                    j = skip_quoted_string(bytes, j);
+                                    continue;
+                                }
                                 b'(' => fp_depth += 1,
                                 b')' => fp_depth -= 1,
                                 _ => {}
@@ -828,14 +2108,20 @@ fn parse_kicad_dnp(content: &str) -> HashSet<String> {
                         let block = &content[block_start..j];
 
                         // Check for DNP attribute: (attr ... dnp)
-                        let has_dnp = block.contains("(attr dnp)")
+                        let dnp = block.contains("(attr dnp)")
                             || block.contains("(attr smd dnp)")
                             || block.contains("(attr through_hole dnp)");
-
-                        if has_dnp {
-                            if let Some(caps) = ref_re.captures(block) {
-                                result.insert(caps[1].to_string());
-                            }
+                        let side = match layer_re.captures(block) {
+                            Some(caps) if caps[1].starts_with("B.") => Side::Bottom,
+                            _ => Side::Top,
+                        };
+
+                        if let Some(caps) = ref_re.captures(block) {
+                            result.push(LayoutFootprint {
+                                reference: caps[1].to_string(),
+                                dnp,
+                                side,
+                            });
                         }
 
                         // Skip past this block
@@ -859,16 +2145,92 @@ fn parse_kicad_dnp(content: &str) -> HashSet<String> {
     result
 }
 
+/// Given the index of an opening `"`, return the index just past the closing
+/// quote. Backslash escapes (`\"`) are honoured. Unterminated strings run to
+/// the end of input.
+fn skip_quoted_string(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
 /// Apply layout DNP status to BOM entries. If any designator in an entry
-/// appears in `dnp_refs`, the entry is marked as DNP.
-fn apply_layout_dnp(entries: &mut [BomEntry], dnp_refs: &HashSet<String>) {
+/// is DNP in the layout, the entry is marked as DNP.
+fn apply_layout_dnp(entries: &mut [BomEntry], footprints: &[LayoutFootprint]) {
+    let dnp_refs: HashSet<&str> = footprints
+        .iter()
+        .filter(|fp| fp.dnp)
+        .map(|fp| fp.reference.as_str())
+        .collect();
     for entry in entries.iter_mut() {
-        if entry.designators.iter().any(|d| dnp_refs.contains(d)) {
+        if entry.designators.iter().any(|d| dnp_refs.contains(d.as_str())) {
             entry.dnp = true;
         }
     }
 }
 
+/// Record each designator's layout side on the BOM entries.
+///
+/// Designators missing from the layout count as top. Entries stay whole so
+/// stock and price checks see the part's full quantity; only the export
+/// splits them with [`split_by_side`].
+fn tag_layout_sides(entries: &mut [BomEntry], footprints: &[LayoutFootprint]) {
+    if footprints.is_empty() {
+        return;
+    }
+    let sides: HashMap<&str, Side> = footprints
+        .iter()
+        .map(|fp| (fp.reference.as_str(), fp.side))
+        .collect();
+
+    for entry in entries.iter_mut() {
+        entry.sides = entry
+            .designators
+            .iter()
+            .map(|d| sides.get(d.as_str()).copied().unwrap_or(Side::Top))
+            .collect();
+    }
+}
+
+/// Split entries with parts on both sides into a top and a bottom line.
+///
+/// JLCPCB assembles each side in its own pass, so the export lists them
+/// separately. Entries without side information are kept as they are.
+fn split_by_side(entries: Vec<BomEntry>) -> Vec<BomEntry> {
+    let mut result = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry.sides.is_empty() {
+            result.push(entry);
+            continue;
+        }
+        for side in [Side::Top, Side::Bottom] {
+            let designators: Vec<String> = entry
+                .designators
+                .iter()
+                .zip(&entry.sides)
+                .filter(|(_, s)| **s == side)
+                .map(|(d, _)| d.clone())
+                .collect();
+            if designators.is_empty() {
+                continue;
+            }
+            result.push(BomEntry {
+                quantity: designators.len(),
+                sides: vec![side; designators.len()],
+                designators,
+                ..entry.clone()
+            });
+        }
+    }
+    result
+}
+
 /// Format stock number for display.
 fn format_stock(stock: i64) -> String {
     if stock >= 1_000_000 {
@@ -879,3 +2241,777 @@ fn format_stock(stock: i64) -> String {
         stock.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(lcsc: &str, mpn: &str, stock: i64, basic: bool) -> JlcPart {
+        JlcPart {
+            lcsc: lcsc.to_string(),
+            mpn: mpn.to_string(),
+            stock,
+            basic,
+            ..Default::default()
+        }
+    }
+
+    fn parse_kicad_dnp(content: &str) -> HashSet<String> {
+        parse_kicad_footprints(content)
+            .into_iter()
+            .filter(|fp| fp.dnp)
+            .map(|fp| fp.reference)
+            .collect()
+    }
+
+    fn entry(designators: &[&str], lcsc: &[&str], mpn: Option<&str>) -> BomEntry {
+        BomEntry {
+            designators: designators.iter().map(|s| s.to_string()).collect(),
+            lcsc_candidates: lcsc.iter().map(|s| s.to_string()).collect(),
+            mpn: mpn.map(String::from),
+            quantity: designators.len(),
+            value: None,
+            package: None,
+            dnp: false,
+            sides: Vec::new(),
+        }
+    }
+
+    fn source() -> SnapshotSource {
+        SnapshotSource::from_parts([
+            part("C1", "BASIC-PART", 10_000, true),
+            part("C2", "EXT-PART", 10_000, false),
+            part("C3", "LOW-STOCK", 50, true),
+            part("C4", "NO-STOCK", 0, true),
+        ])
+    }
+
+    #[test]
+    fn test_check_entry_statuses() {
+        let source = source();
+        let status =
+            |e: BomEntry| check_entry(e, 100, false, OrderRules::default(), &source).status;
+
+        assert_eq!(status(entry(&["R1"], &["C1"], None)), BomStatus::Ok);
+        assert_eq!(status(entry(&["R1"], &["C2"], None)), BomStatus::Extended);
+        assert_eq!(status(entry(&["R1"], &["C3"], None)), BomStatus::Limited);
+        assert_eq!(status(entry(&["R1"], &["C4"], None)), BomStatus::Missing);
+        assert_eq!(status(entry(&["R1"], &["C999"], None)), BomStatus::Missing);
+        assert_eq!(status(entry(&["R1"], &[], Some("BASIC-PART"))), BomStatus::Ok);
+        // MPN search only accepts basic parts
+        assert_eq!(status(entry(&["R1"], &[], Some("EXT-PART"))), BomStatus::Missing);
+        assert_eq!(status(entry(&["R1"], &[], None)), BomStatus::Missing);
+    }
+
+    #[test]
+    fn test_bom_summary_counts() {
+        let source = source();
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1"], &["C1"], None),
+            entry(&["R2"], &["C1"], None),
+            entry(&["U1"], &["C2"], None),
+            entry(&["U2"], &["C4"], None),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 1, false, OrderRules::default(), &source))
+        .collect();
+
+        let summary = BomSummary::from_results(&results);
+        assert_eq!(
+            summary,
+            BomSummary {
+                ok: 2,
+                extended: 1,
+                missing: 1,
+                // None of the test parts list a price
+                price_unknown: 4,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_bom_cost_totals() {
+        let priced = |lcsc: &str, category: &str, basic: bool, price: f64| JlcPart {
+            category: category.to_string(),
+            price_breaks: vec![crate::api::PriceBreak { qty: 1, price }],
+            ..part(lcsc, lcsc, 10_000, basic)
+        };
+        let source = SnapshotSource::from_parts([
+            priced("C1", "Resistors", true, 0.01),
+            priced("C2", "Resistors", true, 0.02),
+            priced("C3", "Connectors", false, 0.50),
+            part("C4", "NO-PRICE", 10_000, true),
+        ]);
+        let mut dnp = entry(&["C9"], &["C3"], None);
+        dnp.dnp = true;
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1", "R2"], &["C1"], None),
+            entry(&["R3"], &["C2"], None),
+            entry(&["J1"], &["C3"], None),
+            entry(&["J2"], &["C3"], None),
+            entry(&["U1"], &["C4"], None),
+            entry(&["U2"], &["C999"], None),
+            dnp,
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 10, false, OrderRules::default(), &source))
+        .collect();
+
+        let cost = BomCost::from_results(&results, 10);
+        assert!((cost.component_cost - 10.4).abs() < 1e-9);
+        // C3 is used on two lines but pays the fee once
+        assert_eq!(cost.extended_parts, 1);
+        assert!((cost.total - 13.4).abs() < 1e-9);
+        assert!((cost.per_unit - 1.34).abs() < 1e-9);
+        let categories: Vec<(&str, usize)> = cost
+            .categories
+            .iter()
+            .map(|c| (c.category.as_str(), c.lines))
+            .collect();
+        assert_eq!(categories, vec![("Connectors", 2), ("Resistors", 2)]);
+        assert_eq!(cost.price_unknown, vec!["U1 (C4)"]);
+        assert_eq!(cost.unresolved, vec!["U2"]);
+    }
+
+    #[test]
+    fn test_bom_cost_prices_part_at_combined_quantity() {
+        let source = SnapshotSource::from_parts([JlcPart {
+            price_breaks: vec![
+                crate::api::PriceBreak { qty: 1, price: 0.10 },
+                crate::api::PriceBreak { qty: 100, price: 0.05 },
+            ],
+            ..part("C1", "RES", 10_000, true)
+        }]);
+        // 60 + 60 per line, so only the combined 120 reaches the 100 break
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1", "R2", "R3"], &["C1"], None),
+            entry(&["R4", "R5", "R6"], &["C1"], None),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 20, false, OrderRules::default(), &source))
+        .collect();
+
+        let cost = BomCost::from_results(&results, 20);
+        assert!((cost.component_cost - 6.0).abs() < 1e-9, "{}", cost.component_cost);
+        assert_eq!(cost.categories[0].lines, 2);
+    }
+
+    #[test]
+    fn test_bom_cost_applies_min_order_once_per_part() {
+        let source = SnapshotSource::from_parts([JlcPart {
+            min_order: Some(100),
+            category: "Resistors".to_string(),
+            price_breaks: vec![crate::api::PriceBreak { qty: 1, price: 0.10 }],
+            ..part("C1", "RES", 10_000, true)
+        }]);
+        // Two lines of 10: one order of 100, not 100 per line
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1"], &["C1"], None),
+            entry(&["R2"], &["C1"], None),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 10, false, OrderRules::default(), &source))
+        .collect();
+
+        let cost = BomCost::from_results(&results, 10);
+        assert!((cost.component_cost - 10.0).abs() < 1e-9, "{}", cost.component_cost);
+        assert!((cost.categories[0].cost - 10.0).abs() < 1e-9);
+        assert_eq!(cost.categories[0].lines, 2);
+    }
+
+    #[test]
+    fn test_check_entry_quantity_scales_with_designators() {
+        let source = source();
+        // 50 in stock: enough for 1 board with 50 placements, not 2 boards
+        let e = entry(&["R1"], &["C3"], None);
+        assert_eq!(
+            check_entry(e.clone(), 50, false, OrderRules::default(), &source).status,
+            BomStatus::Ok
+        );
+        let e = entry(&["R1", "R2"], &["C3"], None);
+        assert_eq!(
+            check_entry(e, 50, false, OrderRules::default(), &source).status,
+            BomStatus::Limited
+        );
+    }
+
+    #[test]
+    fn test_check_entry_dnp() {
+        let source = source();
+        let mut e = entry(&["R1"], &["C1"], None);
+        e.dnp = true;
+        let result = check_entry(e.clone(), 100, false, OrderRules::default(), &source);
+        assert_eq!(result.status, BomStatus::Dnp);
+        assert!(result.part.is_none());
+        assert_eq!(check_entry(e, 100, true, OrderRules::default(), &source).status, BomStatus::Ok);
+    }
+
+    #[test]
+    fn test_resolve_best_lcsc_prefers_basic() {
+        let source = source();
+        let candidates = vec!["C2".to_string(), "C1".to_string()];
+        let (lcsc, _) = resolve_best_lcsc(&candidates, &source).unwrap().unwrap();
+        assert_eq!(lcsc, "C1");
+
+        // The exported code is the normalized one, not the BOM's spelling
+        let candidates = vec!["c001".to_string()];
+        let (lcsc, _) = resolve_best_lcsc(&candidates, &source).unwrap().unwrap();
+        assert_eq!(lcsc, "C1");
+    }
+
+    #[test]
+    fn test_designator_map() {
+        let map = parse_designator_map("from,to\nJ1, CN1\nU3,IC3\n").unwrap();
+        assert_eq!(map.len(), 2);
+
+        let mut entries = vec![entry(&["J1", "J2"], &[], None), entry(&["U3"], &[], None)];
+        apply_designator_map(&mut entries, &map);
+        assert_eq!(entries[0].designators, vec!["CN1", "J2"]);
+        assert_eq!(entries[1].designators, vec!["IC3"]);
+
+        assert!(parse_designator_map("J1\n").is_err());
+    }
+
+    #[test]
+    fn test_load_bom_json5() {
+        let content = r#"[
+            // Pull-ups
+            { designators: ["R1", "R2"], lcsc: "C25744", },
+            /* audio amp */
+            { "designators": ["U1"], "mpn": "MAX98357AETE+T" },
+        ]"#;
+        let entries = load_bom_json(content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].designators, vec!["R1", "R2"]);
+        assert_eq!(entries[0].lcsc_candidates, vec!["C25744"]);
+        assert_eq!(entries[1].mpn.as_deref(), Some("MAX98357AETE+T"));
+
+        assert!(load_bom_json("[{ designators: ").is_err());
+    }
+
+    #[test]
+    fn test_read_bom_json_from_reader() {
+        let flat = r#"[
+            {"designator": "C1", "mpn": "CL05B104KO5NNNC", "value": "100nF"},
+            {"designator": "C2", "mpn": "CL05B104KO5NNNC", "value": "100nF"}
+        ]"#;
+        let entries = read_bom_json(flat.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].designators, vec!["C1", "C2"]);
+
+        let grouped = r#"[{"designators": ["R1"], "lcsc": "C25744"}]"#;
+        let entries = read_bom_json(grouped.as_bytes()).unwrap();
+        assert_eq!(entries[0].lcsc_candidates, vec!["C25744"]);
+
+        // Grouped codes are normalized like the CSV loader's
+        let grouped = r#"[{"designators": ["U1"], "lcsc": "307331"},
+                          {"designators": ["U2"], "lcsc": "c0307331"}]"#;
+        let entries = read_bom_json(grouped.as_bytes()).unwrap();
+        assert_eq!(entries[0].lcsc_candidates, vec!["C307331"]);
+        assert_eq!(entries[1].lcsc_candidates, vec!["C307331"]);
+    }
+
+    #[test]
+    fn test_check_price_header() {
+        assert_eq!(check_price_header(&[20, 20]).as_deref(), Some("Price@20"));
+        assert_eq!(check_price_header(&[]).as_deref(), Some("Price"));
+        assert_eq!(check_price_header(&[20, 10]), None);
+
+        let options = CheckOptions {
+            quantity: 10,
+            order: OrderRules {
+                moq: Some(50),
+                multiple: None,
+            },
+            ..Default::default()
+        };
+        assert_eq!(options.order_qty(&entry(&["R1", "R2", "R3", "R4"], &[], None), None), 50);
+        assert_eq!(options.order_qty(&entry(&["R1"; 8], &[], None), None), 80);
+    }
+
+    #[test]
+    fn test_pcb_bom_command() {
+        let (bin, args) = pcb_bom_command(None, None);
+        assert_eq!(bin, "pcb");
+        assert_eq!(args, ["bom", "-f", "json"]);
+
+        let (bin, args) = pcb_bom_command(
+            Some("/opt/pcb/bin/pcb".to_string()),
+            Some("  --flatten --profile ci ".to_string()),
+        );
+        assert_eq!(bin, "/opt/pcb/bin/pcb");
+        assert_eq!(args, ["bom", "-f", "json", "--flatten", "--profile", "ci"]);
+
+        assert_eq!(pcb_bom_command(Some(String::new()), None).0, "pcb");
+    }
+
+    #[test]
+    fn test_format_comment() {
+        let mut cap = part("C15850", "CL21A106KAYNNNE", 1000, true);
+        cap.description = "25V 10uF X5R ±10% 0805 Multilayer Ceramic Capacitors".to_string();
+        cap.package = "0805".to_string();
+        cap.attributes.capacitance = Some("10uF".to_string());
+        cap.attributes.voltage = Some("25V".to_string());
+        cap.attributes.dielectric = Some("X5R".to_string());
+        let bom_line = entry(&["C1"], &["C15850"], None);
+
+        assert_eq!(
+            format_comment(DEFAULT_COMMENT_FORMAT, &cap, &bom_line),
+            format!("CL21A106KAYNNNE {}", cap.description)
+        );
+        assert_eq!(
+            format_comment("{value} {voltage} {dielectric} {package}", &cap, &bom_line),
+            "10uF 25V X5R 0805"
+        );
+        // Missing tolerance leaves no double space
+        assert_eq!(
+            format_comment("{mpn} {tolerance} {value}", &cap, &bom_line),
+            "CL21A106KAYNNNE 10uF"
+        );
+
+        assert!(validate_comment_format("{value} {package}").is_ok());
+        assert!(validate_comment_format("{value} {colour}").is_err());
+    }
+
+    #[test]
+    fn test_write_missing_csv() {
+        let source = source();
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1"], &["C1"], None),
+            entry(&["R2"], &["C3"], None),
+            entry(&["U1", "U2"], &["C9999999"], Some("NOPE")),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 100, false, OrderRules::default(), &source))
+        .collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.csv");
+        assert_eq!(write_missing_csv(&path, &results).unwrap(), 2);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "Status,Designators,Value,Package,MPN,LCSC,Stock");
+        assert_eq!(lines[1], "limited,R2,,,,C3,50");
+        assert_eq!(lines[2], "missing,\"U1,U2\",,,NOPE,,");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_lcsc_header_variants() {
+        for header in ["LCSC", "lcsc", "LCSC Part #", "LCSC Part Number", "JLCPCB Part", "JLCPCB Part #"] {
+            assert!(is_lcsc_header(header), "{header}");
+        }
+        assert!(!is_lcsc_header("Value"));
+        assert!(!is_lcsc_header("LCSC Price"));
+    }
+
+    #[test]
+    fn test_load_bom_csv_kicad_export() {
+        let csv = "\"Reference\",\"Value\",\"Footprint\",\"Qty\",\"JLCPCB Part #\"\n\
+                   \"R1,R2\",\"10k\",\"R_0402\",\"2\",\"c25744\"\n\
+                   \"U1\",\"MAX98357\",\"QFN-16\",\"1\",\"\"\n";
+        let entries = load_bom_csv(csv).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].designators, vec!["R1", "R2"]);
+        assert_eq!(entries[0].lcsc_candidates, vec!["C25744"]);
+        assert_eq!(entries[0].package.as_deref(), Some("R_0402"));
+        assert!(entries[1].lcsc_candidates.is_empty());
+
+        assert!(load_bom_csv("Value,LCSC\n10k,C1\n").is_err());
+    }
+
+    /// Source whose keyword search returns every part, like a broad catalog query.
+    struct CatalogSource(SnapshotSource, Vec<JlcPart>);
+
+    impl PartSource for CatalogSource {
+        fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            self.0.get_part(lcsc)
+        }
+
+        fn search(&self, _keyword: &str, _page: i32, _page_size: i32) -> Result<Vec<JlcPart>> {
+            Ok(self.1.clone())
+        }
+
+        fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            self.get_part(lcsc)
+        }
+    }
+
+    #[test]
+    fn test_mpn_search_checks_second_page() {
+        // Extended variants fill the first page; the basic one is on page two
+        let page_size = crate::http::mpn_results() as usize;
+        let mut parts: Vec<JlcPart> = (0..page_size + 5)
+            .map(|i| part(&format!("C{}", 1000 + i), "LM358", 10_000, false))
+            .collect();
+        parts.push(part(&format!("C{}", 1000 + page_size + 5), "LM358", 10_000, true));
+        let source = SnapshotSource::from_parts(parts);
+
+        let (lcsc, part) = resolve_entry(&entry(&["U1"], &[], Some("LM358")), &source)
+            .unwrap()
+            .unwrap();
+        assert!(part.basic);
+        assert_eq!(lcsc, format!("C{}", 1000 + page_size + 5));
+    }
+
+    #[test]
+    fn test_recommend_basic_swaps() {
+        let resistor = |lcsc: &str, value: &str, package: &str, stock: i64, basic: bool| {
+            let mut p = part(lcsc, lcsc, stock, basic);
+            p.category = "Resistors".to_string();
+            p.package = package.to_string();
+            p.attributes.resistance = Some(value.to_string());
+            p
+        };
+        let parts = vec![
+            resistor("C10", "10kΩ", "0402", 10_000, false),
+            resistor("C11", "10kΩ", "0603", 90_000, true),
+            resistor("C12", "1kΩ", "0402", 90_000, true),
+            resistor("C13", "10kΩ", "0402", 5, true),
+            resistor("C14", "10kΩ", "0402", 50_000, true),
+            resistor("C15", "4.7kΩ", "0402", 10_000, false),
+            part("C20", "SOME-IC", 10_000, false),
+        ];
+        let source = CatalogSource(SnapshotSource::from_parts(parts.clone()), parts);
+        let options = CheckOptions {
+            quantity: 10,
+            ..Default::default()
+        };
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1", "R2"], &["C10"], None),
+            entry(&["R3"], &["C15"], None),
+            entry(&["U1"], &["C20"], None),
+            entry(&["R4"], &["C14"], None),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 10, false, OrderRules::default(), &source))
+        .collect();
+
+        let swaps = recommend_basic_swaps(&results, &options, &source);
+        // Wrong package, wrong value and short stock are all passed over
+        let swap = swaps[0].as_ref().unwrap();
+        assert_eq!((swap.from.as_str(), swap.to.as_str()), ("C10", "C14"));
+        assert_eq!(swap.fee_saving, EXTENDED_PART_FEE);
+        assert!(swap.cost_delta.is_none());
+        // No basic 4.7k, an IC has no value, and basic lines aren't swapped
+        assert_eq!(swaps[1..], [None, None, None]);
+    }
+
+    #[test]
+    fn test_basic_swap_keeps_ratings() {
+        let capacitor = |lcsc: &str, voltage: &str, basic: bool| {
+            let mut p = part(lcsc, lcsc, 90_000, basic);
+            p.category = "Capacitors".to_string();
+            p.package = "0402".to_string();
+            p.attributes.capacitance = Some("100nF".to_string());
+            p.attributes.voltage = Some(voltage.to_string());
+            p.attributes.dielectric = Some("X7R".to_string());
+            p
+        };
+        let mut y5v = capacitor("C32", "50V", true);
+        y5v.attributes.dielectric = Some("Y5V".to_string());
+        let mut rated = capacitor("C33", "100V", true);
+        rated.stock = 10_000;
+        let parts = vec![
+            capacitor("C30", "50V", false),
+            capacitor("C31", "16V", true),
+            y5v,
+            rated,
+        ];
+        let source = CatalogSource(SnapshotSource::from_parts(parts.clone()), parts);
+        let options = CheckOptions {
+            quantity: 10,
+            ..Default::default()
+        };
+        let results = vec![check_entry(
+            entry(&["C1"], &["C30"], None),
+            10,
+            false,
+            OrderRules::default(),
+            &source,
+        )];
+
+        // Better-stocked parts with a lower voltage or another dielectric lose
+        let swaps = recommend_basic_swaps(&results, &options, &source);
+        assert_eq!(swaps[0].as_ref().unwrap().to, "C33");
+
+        let mut original = capacitor("C30", "50V", false);
+        assert!(keeps_ratings(&original, &capacitor("C33", "100V", true)));
+        original.attributes.tolerance = Some("±5%".to_string());
+        original.attributes.power = Some("1/16W".to_string());
+        let mut candidate = capacitor("C33", "50V", true);
+        candidate.attributes.tolerance = Some("±10%".to_string());
+        candidate.attributes.power = Some("0.1W".to_string());
+        assert!(!keeps_ratings(&original, &candidate));
+        candidate.attributes.tolerance = Some("±1%".to_string());
+        assert!(keeps_ratings(&original, &candidate));
+        candidate.attributes.power = Some("0.05W".to_string());
+        assert!(!keeps_ratings(&original, &candidate));
+    }
+
+    /// Source whose lookups for `C666` fail like a flaky API.
+    struct FlakySource(SnapshotSource);
+
+    impl PartSource for FlakySource {
+        fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            if lcsc == "C666" {
+                anyhow::bail!("connection reset");
+            }
+            self.0.get_part(lcsc)
+        }
+
+        fn search(&self, keyword: &str, page: i32, page_size: i32) -> Result<Vec<JlcPart>> {
+            if keyword.trim().is_empty() {
+                anyhow::bail!("empty search keyword");
+            }
+            self.0.search(keyword, page, page_size)
+        }
+
+        fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            self.get_part(lcsc)
+        }
+    }
+
+    #[test]
+    fn test_check_entry_lookup_error_is_per_line() {
+        let source = FlakySource(source());
+        let results: Vec<BomCheckResult> = [
+            entry(&["R1"], &["C666"], None),
+            entry(&["R2"], &["C1"], None),
+            entry(&["R3"], &["C666", "C2"], None),
+        ]
+        .into_iter()
+        .map(|e| check_entry(e, 1, false, OrderRules::default(), &source))
+        .collect();
+
+        assert_eq!(results[0].status, BomStatus::Error);
+        assert!(results[0].error.as_deref().unwrap().contains("connection reset"));
+        assert_eq!(results[1].status, BomStatus::Ok);
+        // Another candidate resolved, so the failed one doesn't matter
+        assert_eq!(results[2].status, BomStatus::Extended);
+    }
+
+    #[test]
+    fn test_check_result_diagnostics() {
+        let mut priced = part("C1", "BASIC-PART", 10_000, true);
+        priced.package = "0402".to_string();
+        priced.price_breaks = vec![crate::api::PriceBreak { qty: 1, price: 0.01 }];
+        let source = SnapshotSource::from_parts([priced]);
+        let diagnostics = |e: BomEntry| {
+            check_entry(e, 1, false, OrderRules::default(), &source).diagnostics()
+        };
+
+        assert!(diagnostics(entry(&["R1"], &["C1"], None)).is_empty());
+
+        let mut mismatched = entry(&["R1", "R2"], &["C1"], None);
+        mismatched.package = Some("R_0603_1608Metric".to_string());
+        let found = diagnostics(mismatched);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].subject.as_deref(), Some("R1,R2"));
+        assert!(found[0].message.contains("doesn't match C1 package 0402"));
+
+        let found = diagnostics(entry(&["R3"], &[], Some("BASIC-PART")));
+        assert!(found[0].message.contains("MPN search result C1"));
+
+        let found = diagnostics(entry(&["R4"], &["C1", "C9"], None));
+        assert!(found[0].message.contains("Picked C1 of 2"));
+    }
+
+    #[test]
+    fn test_blank_mpn_is_not_searched() {
+        let source = FlakySource(source());
+        let status = |mpn| {
+            check_entry(entry(&["R1"], &[], Some(mpn)), 1, false, OrderRules::default(), &source)
+                .status
+        };
+        assert_eq!(status("   "), BomStatus::Missing);
+        assert_eq!(status(""), BomStatus::Missing);
+        assert_eq!(status(" BASIC-PART "), BomStatus::Ok);
+    }
+
+    #[test]
+    fn test_parse_kicad_dnp_basic() {
+        let pcb = r#"(kicad_pcb (version 20240108)
+  (footprint "R_0402" (layer "F.Cu")
+    (property "Reference" "R1" (at 0 0))
+    (attr smd dnp)
+  )
+  (footprint "R_0402" (layer "F.Cu")
+    (property "Reference" "R2" (at 0 0))
+    (attr smd)
+  )
+  (footprint "Conn" (layer "F.Cu")
+    (property "Reference" "J1" (at 0 0))
+    (attr through_hole dnp)
+  )
+)"#;
+        let refs = parse_kicad_dnp(pcb);
+        assert_eq!(refs.len(), 2);
+        assert!(refs.contains("R1"));
+        assert!(refs.contains("J1"));
+    }
+
+    #[test]
+    fn test_parse_kicad_dnp_parens_in_strings() {
+        // An unbalanced paren in a property value must not shift the depth
+        // and hide the following footprint.
+        let pcb = r#"(kicad_pcb (version 20240108)
+  (footprint "R_0402" (layer "F.Cu")
+    (property "Reference" "R1" (at 0 0))
+    (property "Note" "see (test" (at 0 0))
+    (attr smd)
+  )
+  (footprint "C_0402" (layer "F.Cu")
+    (property "Reference" "C1" (at 0 0))
+    (property "Value" "(test)" (at 0 0))
+    (property "Desc" "quoted \"(\" paren" (at 0 0))
+    (attr smd dnp)
+  )
+)"#;
+        let refs = parse_kicad_dnp(pcb);
+        assert_eq!(refs.len(), 1);
+        assert!(refs.contains("C1"));
+
+        // The escaped quotes stay inside the string, paren and all
+        let desc = r#""quoted \"(\" paren" (at 0 0)"#;
+        let end = skip_quoted_string(desc.as_bytes(), 0);
+        assert_eq!(&desc[..end], r#""quoted \"(\" paren""#);
+    }
+
+    #[test]
+    fn test_parse_kicad_dnp_missing_reference() {
+        let pcb = r#"(kicad_pcb (version 20240108)
+  (footprint "MountingHole" (layer "F.Cu")
+    (attr dnp)
+  )
+  (footprint "R_0402" (layer "F.Cu")
+    (property "Reference" "R5" (at 0 0))
+    (attr dnp)
+  )
+)"#;
+        let refs = parse_kicad_dnp(pcb);
+        assert_eq!(refs.len(), 1);
+        assert!(refs.contains("R5"));
+    }
+
+    #[test]
+    fn test_parse_kicad_dnp_nested_non_footprint() {
+        // Footprint-like text nested deeper than the top level is ignored.
+        let pcb = r#"(kicad_pcb (version 20240108)
+  (group "" (members (footprint "X" (property "Reference" "U9") (attr dnp))))
+  (footprint "U" (layer "F.Cu")
+    (property "Reference" "U1" (at 0 0))
+    (attr smd dnp)
+  )
+)"#;
+        let refs = parse_kicad_dnp(pcb);
+        assert_eq!(refs.len(), 1);
+        assert!(refs.contains("U1"));
+    }
+
+    #[test]
+    fn test_lcsc_candidates_tied_stock() {
+        let offer = |id: &str, stock| PcbBomOffer {
+            distributor: Some("LCSC".to_string()),
+            part_id: Some(id.to_string()),
+            stock: Some(stock),
+        };
+        let offers = [offer("C300", 10), offer("C200", 50), offer("C100", 10), offer("C200", 10)];
+        assert_eq!(extract_lcsc_candidates(&offers), ["C200", "C100", "C300"]);
+
+        // Labelled and zero-padded ids collapse onto one code; junk is dropped
+        let offers = [offer("LCSC-C100", 20), offer("c000100", 30), offer("ABC123", 99)];
+        assert_eq!(extract_lcsc_candidates(&offers), ["C100"]);
+    }
+
+    #[test]
+    fn test_format_designators() {
+        let refs: Vec<String> = ["C10", "C2", "C1", "C9"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(format_designators(&refs, 3), "C1–C10 (4)");
+        assert_eq!(format_designators(&refs, 4), "C10,C2,C1,C9");
+        assert_eq!(format_designators(&refs, 0), "C10,C2,C1,C9");
+    }
+
+    #[test]
+    fn test_layout_sides_split_entries() {
+        let pcb = r#"(kicad_pcb (version 20240108)
+  (footprint "C_0402" (layer "B.Cu")
+    (property "Reference" "C1" (at 0 0) (layer "B.SilkS"))
+    (attr smd)
+  )
+  (footprint "C_0402" (layer "F.Cu")
+    (property "Reference" "C2" (at 0 0) (layer "F.SilkS"))
+    (attr smd)
+  )
+)"#;
+        let footprints = parse_kicad_footprints(pcb);
+        assert_eq!(footprints[0].side, Side::Bottom);
+        assert_eq!(footprints[1].side, Side::Top);
+
+        let mut entries = vec![entry(&["C1", "C2", "C3"], &["C1525"], None)];
+        tag_layout_sides(&mut entries, &footprints);
+        assert_eq!(entries[0].sides, [Side::Bottom, Side::Top, Side::Top]);
+
+        let entries = split_by_side(entries);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].designators, vec!["C2", "C3"]);
+        assert_eq!(entries[0].side(), Some(Side::Top));
+        assert_eq!(entries[1].designators, vec!["C1"]);
+        assert_eq!(entries[1].quantity, 1);
+        assert_eq!(entries[1].side(), Some(Side::Bottom));
+    }
+
+    #[test]
+    fn test_both_sides_checked_against_combined_quantity() {
+        let footprints: Vec<LayoutFootprint> = [("R1", Side::Top), ("R2", Side::Bottom)]
+            .into_iter()
+            .map(|(reference, side)| LayoutFootprint {
+                reference: reference.to_string(),
+                dnp: false,
+                side,
+            })
+            .collect();
+        let mut entries = vec![entry(&["R1", "R2"], &["C3"], None)];
+        tag_layout_sides(&mut entries, &footprints);
+        assert_eq!(entries.len(), 1);
+
+        // 30 boards need 60 of C3 (50 in stock), though each side alone fits
+        let source = source();
+        let result = check_entry(entries.remove(0), 30, false, OrderRules::default(), &source);
+        assert_eq!(result.status, BomStatus::Limited);
+    }
+
+    #[test]
+    fn test_order_rules_round_up() {
+        let rules = OrderRules {
+            moq: Some(100),
+            multiple: Some(50),
+        };
+        assert_eq!(rules.order_qty(20), 100);
+        assert_eq!(rules.order_qty(120), 150);
+        assert_eq!(rules.order_qty(150), 150);
+        assert_eq!(OrderRules::default().order_qty(7), 7);
+
+        // 2 designators x 30 boards = 60, ordered as 100: C3 only has 50
+        let source = source();
+        let e = entry(&["C1", "C2"], &["C1"], None);
+        assert_eq!(check_entry(e, 30, false, rules, &source).status, BomStatus::Ok);
+        let e = entry(&["R1", "R2"], &["C3"], None);
+        assert_eq!(
+            check_entry(e.clone(), 20, false, OrderRules::default(), &source).status,
+            BomStatus::Ok
+        );
+        assert_eq!(check_entry(e, 20, false, rules, &source).status, BomStatus::Limited);
+
+        // The part's own minimum order applies before rounding to the multiple
+        let part = JlcPart {
+            min_order: Some(120),
+            ..Default::default()
+        };
+        assert_eq!(rules.order_qty_for(20, &part), 150);
+        assert_eq!(OrderRules::default().order_qty_for(20, &part), 120);
+        assert_eq!(OrderRules::default().order_qty_for(200, &part), 200);
+    }
+}
@@ -1,97 +1,273 @@
 //! Generate command - create .zen component files from JLCPCB parts.
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
-use crate::api::{JlcpcbClient, JlcPart};
-use crate::generator::{sanitize_mpn, ZenGenerator};
-use crate::pins::{extract_pins, ExtractionOptions};
+use super::{bom, Diagnostic};
+use crate::api::{
+    normalize_lcsc, CachedSource, JlcpcbClient, JlcPart, PartSource, PartType, SnapshotSource,
+};
+use crate::easyeda::{
+    embedded_source_hash, parse_footprint_shapes, ComponentMeta, EasyEdaClient, FootprintOrigin,
+    KicadVersion, Pin, RefPlacement, SymbolStyle,
+};
+use crate::generator::{check_standard_value, check_value_conflict, sanitize_mpn, ZenGenerator};
+use crate::http;
+use crate::sexpr;
+use crate::pins::{
+    extract_pins, prefetch_pins, ExtractionOptions, ExtractionResult, FOOTPRINT_PAD_SOURCE,
+};
 
-/// Execute the generate command.
-pub fn execute(
-    lcsc: &str,
-    output_dir: Option<PathBuf>,
-    name: Option<String>,
-    options: &ExtractionOptions,
-) -> Result<()> {
-    // Normalize LCSC part number
-    let lcsc_normalized = if lcsc.starts_with('C') {
-        lcsc.to_string()
-    } else {
-        format!("C{}", lcsc)
-    };
+/// Options controlling component generation.
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    /// Pin extraction options
+    pub extraction: ExtractionOptions,
+    /// KiCad version targeted by generated symbol/footprint files
+    pub kicad_version: KicadVersion,
+    /// Where the footprint reference text goes
+    pub ref_placement: RefPlacement,
+    /// Put connector footprints' origin on pad 1 instead of the pad centre
+    pub connector_origin_pin1: bool,
+    /// Pin spacing, margin and fill of generated symbols
+    pub symbol_style: SymbolStyle,
+    /// Emit a JSON manifest instead of human-readable output
+    pub json: bool,
+    /// Skip the detail request for passives and rely on description parsing
+    pub attributes_from_search: bool,
+    /// Directory with .zen templates overriding the built-in ones
+    pub template_dir: Option<PathBuf>,
+    /// Record generated parts in the project component index
+    pub update_index: bool,
+    /// Write batch output straight into `--output` without per-MPN folders
+    pub flat: bool,
+    /// Write a README.md describing the part into the component directory
+    /// (`<name>.README.md` with `flat`, where parts share a directory)
+    pub write_readme: bool,
+    /// Replace an existing README.md instead of keeping it
+    pub force_readme: bool,
+    /// Put footprints in a `JLCPCB.pretty/` KiCad library next to the .zen file
+    pub pretty: bool,
+    /// Formatter run on each written .zen file, with the path appended
+    pub format_cmd: Option<String>,
+    /// Maximum .zen description length in characters (default 100)
+    pub description_length: Option<usize>,
+    /// Fail when the API returns a part code that differs from the requested
+    /// one in case or leading zeros
+    pub strict_lcsc: bool,
+    /// Pin names by number, patched over the extracted pins
+    pub merge_pins: HashMap<String, String>,
+    /// Emit single-line JSON
+    pub json_compact: bool,
+}
+
+impl GenerateOptions {
+    /// Where part records come from: the local cache offline, else the API.
+    fn part_source(&self) -> Box<dyn PartSource> {
+        if self.extraction.offline {
+            Box::new(CachedSource::default())
+        } else {
+            Box::new(JlcpcbClient::new().with_strict_lcsc(self.strict_lcsc))
+        }
+    }
 
-    let client = JlcpcbClient::new();
+    /// Create the .zen generator, honoring a custom template directory.
+    pub(crate) fn zen_generator(&self) -> Result<ZenGenerator> {
+        let generator = match &self.template_dir {
+            Some(dir) => ZenGenerator::with_template_dir(dir)?,
+            None => ZenGenerator::new(),
+        };
+        Ok(match self.description_length {
+            Some(length) => generator.with_description_length(length),
+            None => generator,
+        })
+    }
+}
 
-    let mut part = client
-        .get_part(&lcsc_normalized)?
-        .ok_or_else(|| anyhow::anyhow!("Part {} not found", lcsc_normalized))?;
+/// Catalog of generated components, kept in the library directory that
+/// holds the per-part component directories.
+const INDEX_FILE: &str = "index.json";
 
-    // Fetch detailed attributes if not already populated
-    if part.attributes.capacitance.is_none()
-        && part.attributes.resistance.is_none()
-        && part.attributes.inductance.is_none()
-    {
-        if let Ok(Some(detailed)) = client.get_part_details(&lcsc_normalized) {
-            // Merge detailed attributes
-            part.attributes = detailed.attributes;
-            if part.package.is_empty() && !detailed.package.is_empty() {
-                part.package = detailed.package;
-            }
-            if part.datasheet.is_none() && detailed.datasheet.is_some() {
-                part.datasheet = detailed.datasheet;
-            }
+/// KiCad footprint library used with `--pretty`, kept as `<name>.pretty/`.
+const PRETTY_LIBRARY: &str = "JLCPCB";
+
+/// Worker threads fetching part and EasyEDA data ahead of a batch.
+const PREFETCH_THREADS: usize = 4;
+
+/// Raw shapes printed by [`footprint_diagnostic`] when debugging.
+const DEBUG_SHAPE_LINES: usize = 5;
+
+/// Pad number gaps named in the missing-pads warning before summarizing.
+const MAX_LISTED_GAPS: usize = 5;
+
+/// One component in the project index.
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    lcsc: String,
+    mpn: String,
+    path: PathBuf,
+    generated_at: DateTime<Utc>,
+}
+
+impl IndexEntry {
+    fn new(part: &JlcPart, path: &Path) -> Self {
+        Self {
+            lcsc: part.lcsc.clone(),
+            mpn: part.mpn.clone(),
+            path: path.to_path_buf(),
+            generated_at: Utc::now(),
         }
     }
+}
 
-    // Determine output directory
-    let output_dir = output_dir.unwrap_or_else(|| {
-        PathBuf::from("components")
-            .join("JLCPCB")
-            .join(sanitize_mpn(&part.mpn))
-    });
+/// Index for a single component directory: in its parent, the library
+/// directory (`components/JLCPCB/` by default).
+fn library_index(component_dir: &Path) -> PathBuf {
+    component_dir
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(INDEX_FILE)
+}
 
-    // Create output directory
-    fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
+/// Add entries to the component index, replacing any with the same LCSC code.
+///
+/// Creates the index if it doesn't exist yet.
+fn update_index(index_path: &Path, new_entries: Vec<IndexEntry>) -> Result<()> {
+    let mut entries: Vec<IndexEntry> = if index_path.exists() {
+        let content = fs::read_to_string(index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", index_path.display()))?
+    } else {
+        Vec::new()
+    };
 
-    // Determine component name
-    let component_name = name.unwrap_or_else(|| sanitize_mpn(&part.mpn));
+    for entry in new_entries {
+        match entries.iter_mut().find(|e| e.lcsc == entry.lcsc) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+    }
 
-    // Generate the .zen file
-    let generator = ZenGenerator::new();
-    let result = generate_zen_content(&generator, &part, &component_name, options)?;
+    if let Some(parent) = index_path.parent() {
+        fs::create_dir_all(parent).context("Failed to create index directory")?;
+    }
+    fs::write(index_path, serde_json::to_string_pretty(&entries)? + "\n")
+        .with_context(|| format!("Failed to write {}", index_path.display()))
+}
+
+/// What `generate` produced for a single part.
+///
+/// Serialized as the `--format json` manifest.
+#[derive(Debug, Serialize)]
+pub struct GenerationReport {
+    pub lcsc: String,
+    /// Component name (file stem of the generated files)
+    pub name: String,
+    pub zen: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readme: Option<PathBuf>,
+    pub pin_count: usize,
+    /// Non-fatal problems and heuristics noticed while generating
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Diagnostic>,
+    pub part: JlcPart,
+}
+
+impl GenerationReport {
+    /// Component files written, symbol and footprint before the .zen file.
+    pub fn files_written(&self) -> Vec<&Path> {
+        self.symbol
+            .iter()
+            .chain(self.footprint.iter())
+            .chain(std::iter::once(&self.zen))
+            .chain(self.readme.iter())
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+/// Generate and write the files for an already-resolved part.
+///
+/// Writes `<name>.zen` plus any symbol/footprint into `output_dir` (created if
+/// needed), and an empty `pcb.toml` if there isn't one yet.
+pub fn generate_part(
+    generator: &ZenGenerator,
+    part: JlcPart,
+    output_dir: &Path,
+    name: &str,
+    options: &GenerateOptions,
+) -> Result<GenerationReport> {
+    let result = generate_zen_content(generator, &part, name, options)?;
+    write_part(part, result, output_dir, name, options)
+}
+
+/// Generate and write the files for a part whose EasyEDA data is already
+/// loaded, e.g. from a saved component dump. Never touches the network.
+pub fn generate_imported(
+    generator: &ZenGenerator,
+    part: JlcPart,
+    extraction: &ExtractionResult,
+    output_dir: &Path,
+    name: &str,
+    options: &GenerateOptions,
+) -> Result<GenerationReport> {
+    let result = generate_component_content(generator, &part, name, extraction, options)?;
+    write_part(part, result, output_dir, name, options)
+}
+
+/// Write generated content into `output_dir`.
+fn write_part(
+    part: JlcPart,
+    mut result: GenerateResult,
+    output_dir: &Path,
+    name: &str,
+    options: &GenerateOptions,
+) -> Result<GenerationReport> {
+    fs::create_dir_all(output_dir).context("Failed to create output directory")?;
 
     // Write the .zen file
-    let zen_path = output_dir.join(format!("{}.zen", component_name));
+    let zen_path = output_dir.join(format!("{}.zen", name));
     fs::write(&zen_path, &result.zen_content).context("Failed to write .zen file")?;
 
     // Write symbol file if available
+    let mut symbol_path = None;
     if let (Some(symbol_content), Some(symbol_filename)) =
         (&result.symbol_content, &result.symbol_filename)
     {
-        let symbol_path = output_dir.join(symbol_filename);
-        fs::write(&symbol_path, symbol_content).context("Failed to write .kicad_sym file")?;
-        println!(
-            "{} Created {}",
-            "✓".green().bold(),
-            symbol_path.display().to_string().cyan()
-        );
+        let path = output_dir.join(symbol_filename);
+        result
+            .warnings
+            .extend(hand_edit_warning(&path, symbol_content).map(Diagnostic::warn));
+        fs::write(&path, symbol_content).context("Failed to write .kicad_sym file")?;
+        symbol_path = Some(path);
     }
 
     // Write footprint file if available
+    let mut footprint_path = None;
     if let (Some(footprint_content), Some(footprint_filename)) =
         (&result.footprint_content, &result.footprint_filename)
     {
-        let footprint_path = output_dir.join(footprint_filename);
-        fs::write(&footprint_path, footprint_content).context("Failed to write .kicad_mod file")?;
-        println!(
-            "{} Created {}",
-            "✓".green().bold(),
-            footprint_path.display().to_string().cyan()
-        );
+        let path = output_dir.join(footprint_filename);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create footprint library directory")?;
+        }
+        result
+            .warnings
+            .extend(hand_edit_warning(&path, footprint_content).map(Diagnostic::warn));
+        fs::write(&path, footprint_content).context("Failed to write .kicad_mod file")?;
+        footprint_path = Some(path);
     }
 
     // Write pcb.toml if it doesn't exist
@@ -100,13 +276,96 @@ pub fn execute(
         fs::write(&toml_path, "").context("Failed to write pcb.toml")?;
     }
 
-    println!(
-        "{} Created {}",
-        "✓".green().bold(),
-        zen_path.display().to_string().cyan()
-    );
+    // README.md may have been edited by hand, so only replace it on request
+    let mut readme_path = None;
+    if options.write_readme {
+        let path = output_dir.join(readme_file(name, options.flat));
+        if path.exists() && !options.force_readme {
+            result.warnings.push(Diagnostic::warn(format!(
+                "Kept existing {}; pass --force-readme to replace it",
+                path.display()
+            )));
+        } else {
+            let content =
+                readme_content(&part, name, result.easyeda_url.as_deref(), Utc::now());
+            fs::write(&path, content).context("Failed to write README.md")?;
+            readme_path = Some(path);
+        }
+    }
+
+    // Formatting is cosmetic, so a failing formatter only warns
+    if let Some(cmd) = &options.format_cmd {
+        result
+            .warnings
+            .extend(run_formatter(cmd, &zen_path).map(Diagnostic::warn));
+    }
+
+    let subject = part.lcsc.clone();
+    Ok(GenerationReport {
+        lcsc: part.lcsc.clone(),
+        name: name.to_string(),
+        zen: zen_path,
+        footprint: footprint_path,
+        symbol: symbol_path,
+        readme: readme_path,
+        pin_count: result.pin_count,
+        warnings: result
+            .warnings
+            .into_iter()
+            .map(|w| w.with_subject(subject.clone()))
+            .collect(),
+        part,
+    })
+}
+
+/// Execute the generate command.
+pub fn execute(
+    lcsc: &str,
+    output_dir: Option<PathBuf>,
+    name: Option<String>,
+    options: &GenerateOptions,
+) -> Result<()> {
+    let lcsc_normalized = normalize_lcsc(lcsc)
+        .ok_or_else(|| anyhow::anyhow!("Invalid LCSC part number: {}", lcsc))?;
+
+    let source = options.part_source();
+    let part = resolve_part(source.as_ref(), &lcsc_normalized, options)?;
+
+    // Determine output directory
+    let output_dir = output_dir.unwrap_or_else(|| {
+        PathBuf::from("components")
+            .join("JLCPCB")
+            .join(sanitize_mpn(&part.mpn))
+    });
+
+    // Determine component name
+    let component_name = name.unwrap_or_else(|| sanitize_mpn(&part.mpn));
+
+    let generator = options.zen_generator()?;
+    let report = generate_part(&generator, part, &output_dir, &component_name, options)?;
+
+    if options.update_index {
+        update_index(
+            &library_index(&output_dir),
+            vec![IndexEntry::new(&report.part, &output_dir)],
+        )?;
+    }
+
+    if options.json {
+        println!("{}", super::to_json(&report, options.json_compact)?);
+        return Ok(());
+    }
+
+    for path in report.files_written() {
+        println!(
+            "{} Created {}",
+            "✓".green().bold(),
+            path.display().to_string().cyan()
+        );
+    }
 
     // Print part info
+    let part = &report.part;
     println!("  LCSC: {}", part.lcsc.green());
     println!("  MPN: {}", part.mpn);
     println!("  Manufacturer: {}", part.manufacturer);
@@ -117,10 +376,98 @@ pub fn execute(
     } else {
         println!("  Type: Extended");
     }
+    if let Some(min_order) = part.min_order {
+        println!("  Min order: {}", min_order);
+    }
+    super::print_diagnostics(&report.warnings);
 
     Ok(())
 }
 
+/// Run `cmd` (split on whitespace) with `path` appended, e.g. `pcb fmt`.
+///
+/// Returns a warning when the formatter can't be started or exits non-zero.
+fn run_formatter(cmd: &str, path: &Path) -> Option<String> {
+    let mut words = cmd.split_whitespace();
+    let Some(program) = words.next() else {
+        return Some("--format-cmd is empty; .zen file left unformatted".to_string());
+    };
+    let output = match std::process::Command::new(program).args(words).arg(path).output() {
+        Ok(output) => output,
+        Err(e) => return Some(format!("Failed to run formatter `{}`: {}", cmd, e)),
+    };
+    if output.status.success() {
+        return None;
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let detail = stderr.lines().next().map(str::trim).unwrap_or_default();
+    Some(format!(
+        "Formatter `{} {}` exited with {}{}{}",
+        cmd,
+        path.display(),
+        output.status,
+        if detail.is_empty() { "" } else { ": " },
+        detail
+    ))
+}
+
+/// Warn before overwriting a generated file that looks hand-edited.
+///
+/// The embedded hash covers the EasyEDA source, the generation settings and
+/// the tool version, so a file with the same hash should match the new
+/// content exactly and any difference points to manual edits. A changed
+/// hash means the input moved on, and a file without one predates hashing;
+/// both are overwritten quietly.
+fn hand_edit_warning(path: &Path, content: &str) -> Option<String> {
+    let existing = fs::read_to_string(path).ok()?;
+    if existing == content {
+        return None;
+    }
+    let existing_hash = embedded_source_hash(&existing)?;
+    if Some(existing_hash) != embedded_source_hash(content) {
+        return None;
+    }
+    Some(format!(
+        "{} may have been edited by hand; its changes were overwritten",
+        path.display()
+    ))
+}
+
+/// Look up a part and fill in detailed attributes when search left them empty.
+pub(crate) fn resolve_part(
+    source: &dyn PartSource,
+    lcsc: &str,
+    options: &GenerateOptions,
+) -> Result<JlcPart> {
+    let mut part = match source.get_part(lcsc)? {
+        Some(part) => part,
+        None if options.extraction.offline => anyhow::bail!(
+            "Part {} is not in the part cache\n\n\
+            Run `generate {}` once without --offline to cache it.",
+            lcsc,
+            lcsc
+        ),
+        None => anyhow::bail!("Part {} not found", lcsc),
+    };
+
+    // Fetch detailed attributes if not already populated. Passives can skip
+    // this round-trip when asked to; the generator falls back to parsing
+    // value/tolerance/etc. out of the search description.
+    let skip_details = options.attributes_from_search && part.uses_stdlib_generic();
+    if !skip_details
+        && part.attributes.capacitance.is_none()
+        && part.attributes.resistance.is_none()
+        && part.attributes.inductance.is_none()
+    {
+        if let Ok(Some(detailed)) = source.get_part_details(lcsc) {
+            part.merge_details(detailed);
+        }
+    }
+
+    Ok(part)
+}
+
 /// Result of generating .zen content, may include footprint and symbol data.
 struct GenerateResult {
     /// .zen file content
@@ -133,6 +480,52 @@ struct GenerateResult {
     symbol_content: Option<String>,
     /// Symbol filename (without path)
     symbol_filename: Option<String>,
+    /// Number of pins on the generated component
+    pin_count: usize,
+    /// Non-fatal problems and heuristics noticed while generating
+    warnings: Vec<Diagnostic>,
+    /// EasyEDA component page the symbol and footprint came from
+    easyeda_url: Option<String>,
+}
+
+/// README file name: flat batches write every part into one directory, so
+/// each part gets its own `<name>.README.md` there.
+fn readme_file(name: &str, flat: bool) -> String {
+    if flat {
+        format!("{}.README.md", name)
+    } else {
+        "README.md".to_string()
+    }
+}
+
+/// README.md for a component directory: what the part is and where the
+/// generated files came from.
+fn readme_content(
+    part: &JlcPart,
+    name: &str,
+    easyeda_url: Option<&str>,
+    generated_at: DateTime<Utc>,
+) -> String {
+    let mut out = format!("# {}\n\n", name);
+    if !part.description.is_empty() {
+        out.push_str(&format!("{}\n\n", part.description));
+    }
+    out.push_str(&format!("- MPN: {}\n", part.mpn));
+    if !part.manufacturer.is_empty() {
+        out.push_str(&format!("- Manufacturer: {}\n", part.manufacturer));
+    }
+    out.push_str(&format!("- LCSC: [{}]({})\n", part.lcsc, part.lcsc_url()));
+    if let Some(datasheet) = &part.datasheet {
+        out.push_str(&format!("- Datasheet: <{}>\n", datasheet));
+    }
+    if let Some(url) = easyeda_url {
+        out.push_str(&format!("- EasyEDA: <{}>\n", url));
+    }
+    out.push_str(&format!(
+        "\nGenerated by pcb-jlcpcb on {}.\n",
+        generated_at.format("%Y-%m-%d")
+    ));
+    out
 }
 
 /// Generate the .zen file content based on part type.
@@ -140,9 +533,15 @@ fn generate_zen_content(
     generator: &ZenGenerator,
     part: &JlcPart,
     name: &str,
-    options: &ExtractionOptions,
+    options: &GenerateOptions,
 ) -> Result<GenerateResult> {
     if part.uses_stdlib_generic() {
+        if !options.merge_pins.is_empty() {
+            anyhow::bail!(
+                "--merge-pins can't rename pins of {}: passives use a stdlib generic",
+                part.lcsc
+            );
+        }
         // Use the generic template for passives
         let zen_content = generator.generate_generic(part, name, ("net1", "net2"))?;
         Ok(GenerateResult {
@@ -151,180 +550,624 @@ fn generate_zen_content(
             footprint_filename: None,
             symbol_content: None,
             symbol_filename: None,
+            pin_count: 2,
+            warnings: check_value_conflict(part)
+                .into_iter()
+                .chain(check_standard_value(part))
+                .map(Diagnostic::warn)
+                .collect(),
+            easyeda_url: None,
         })
     } else {
         // Extract pins for non-passive components
-        let result = extract_pins(part, options)?;
+        let mut result = extract_pins(part, &options.extraction)?;
+        merge_pin_names(&mut result.pins, &options.merge_pins)
+            .with_context(|| format!("Failed to apply --merge-pins to {}", part.lcsc))?;
+        generate_component_content(generator, part, name, &result, options)
+    }
+}
 
-        // Convert pins to (number, name) tuples for the generator
-        let pin_tuples: Vec<(String, String)> = result
-            .pins
-            .iter()
-            .map(|p| (p.number.clone(), p.name.clone()))
-            .collect();
+/// Load a `number,name` pin rename CSV for `--merge-pins`.
+///
+/// A leading `number,name` header row is optional.
+pub fn load_pin_names(path: &Path) -> Result<HashMap<String, String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pin names: {}", path.display()))?;
+    parse_pin_names(&content)
+        .with_context(|| format!("Failed to parse pin names: {}", path.display()))
+}
 
-        // Generate footprint if we have shape data
-        let (footprint_content, footprint_filename) =
-            if let Some(footprint) = result.meta.generate_footprint() {
-                let filename = format!("{}.kicad_mod", name);
-                (Some(footprint), Some(filename))
-            } else {
-                (None, None)
-            };
+fn parse_pin_names(content: &str) -> Result<HashMap<String, String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
 
-        // Generate symbol
-        let (symbol_content, symbol_filename) =
-            if let Some(symbol) = result.meta.generate_symbol(name, &result.pins) {
-                let filename = format!("{}.kicad_sym", name);
-                (Some(symbol), Some(filename))
-            } else {
-                (None, None)
-            };
+    let mut names = HashMap::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let (Some(number), Some(name)) = (record.get(0), record.get(1)) else {
+            anyhow::bail!("Line {}: expected `number,name`", i + 1);
+        };
+        if i == 0 && number.eq_ignore_ascii_case("number") && name.eq_ignore_ascii_case("name") {
+            continue;
+        }
+        if number.is_empty() || name.is_empty() {
+            anyhow::bail!("Line {}: empty pin number or name", i + 1);
+        }
+        names.insert(number.to_string(), name.to_string());
+    }
 
-        let zen_content = generator.generate_component(
-            part,
-            name,
-            &pin_tuples,
-            &result.meta,
-            &footprint_filename,
-            &symbol_filename,
-        )?;
+    Ok(names)
+}
 
-        Ok(GenerateResult {
-            zen_content,
-            footprint_content,
-            footprint_filename,
-            symbol_content,
-            symbol_filename,
+/// Rename the listed pins, leaving the rest as extracted.
+///
+/// Every listed number must exist, so a typo doesn't silently do nothing.
+fn merge_pin_names(pins: &mut [Pin], names: &HashMap<String, String>) -> Result<()> {
+    let mut unknown: Vec<&str> = names
+        .keys()
+        .filter(|number| !pins.iter().any(|p| &p.number == *number))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort_unstable();
+        anyhow::bail!("No pin numbered {}", unknown.join(", "));
+    }
+
+    for pin in pins.iter_mut() {
+        if let Some(name) = names.get(&pin.number) {
+            pin.name = name.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Footprint origin for a part: pad 1 for connectors with
+/// `--no-footprint-recenter-for-connectors`, so they line up with a board
+/// edge; the pad centre for everything else.
+fn footprint_origin(part: &JlcPart, options: &GenerateOptions) -> FootprintOrigin {
+    let connector = part.part_type() == PartType::Other && part.is_connector();
+    if options.connector_origin_pin1 && connector {
+        FootprintOrigin::Pin1
+    } else {
+        FootprintOrigin::Center
+    }
+}
+
+/// Footprint path relative to the component directory: `<name>.kicad_mod`,
+/// or inside the `JLCPCB.pretty/` library with `--pretty`.
+fn footprint_file(name: &str, pretty: bool) -> String {
+    if pretty {
+        format!("{}.pretty/{}.kicad_mod", PRETTY_LIBRARY, name)
+    } else {
+        format!("{}.kicad_mod", name)
+    }
+}
+
+/// Generate .zen, footprint and symbol content from extracted EasyEDA data.
+fn generate_component_content(
+    generator: &ZenGenerator,
+    part: &JlcPart,
+    name: &str,
+    result: &ExtractionResult,
+    options: &GenerateOptions,
+) -> Result<GenerateResult> {
+    // Convert pins to (number, name) tuples for the generator
+    let pin_tuples: Vec<(String, String)> = result
+        .pins
+        .iter()
+        .map(|p| (p.number.clone(), p.name.clone()))
+        .collect();
+
+    if result.stub {
+        return Ok(GenerateResult {
+            zen_content: generator.generate_stub(part, name, &pin_tuples)?,
+            footprint_content: None,
+            footprint_filename: None,
+            symbol_content: None,
+            symbol_filename: None,
+            pin_count: pin_tuples.len(),
+            warnings: vec![Diagnostic::warn(format!(
+                "{} is not in EasyEDA; wrote a placeholder with {} stub pin(s) to fill in",
+                part.lcsc,
+                pin_tuples.len()
+            ))],
+            easyeda_url: None,
+        });
+    }
+
+    let mut warnings: Vec<Diagnostic> =
+        result.warnings.iter().cloned().map(Diagnostic::warn).collect();
+    if result.pin_source == Some(FOOTPRINT_PAD_SOURCE) {
+        warnings.push(Diagnostic::warn(
+            "EasyEDA symbol has no pins; pin names were derived from the footprint pads",
+        ));
+    }
+
+    // Generate footprint if we have shape data
+    let origin = footprint_origin(part, options);
+    let (footprint_content, footprint_filename) = if let Some(footprint) = result
+        .meta
+        .generate_footprint(options.kicad_version, options.ref_placement, origin)
+    {
+        (Some(footprint), Some(footprint_file(name, options.pretty)))
+    } else {
+        warnings.push(Diagnostic::warn(footprint_diagnostic(&result.meta)));
+        (None, None)
+    };
+
+    // Generate symbol, pointing at the footprint when it's in a library
+    let footprint_ref = footprint_filename
+        .as_ref()
+        .filter(|_| options.pretty)
+        .map(|_| format!("{}:{}", PRETTY_LIBRARY, name));
+    let (symbol_content, symbol_filename) = if let Some(symbol) = result.meta.generate_symbol(
+        name,
+        &result.pins,
+        footprint_ref.as_deref(),
+        &options.symbol_style,
+        options.kicad_version,
+    ) {
+        check_symbol_pins(&symbol, &pin_tuples)?;
+        for pin in result.meta.snapped_pins() {
+            warnings.push(Diagnostic::warn(format!(
+                "Symbol pin {} is drawn at {}°; placed it at {}°",
+                pin.number, pin.drawn, pin.placed
+            )));
+        }
+        let filename = format!("{}.kicad_sym", name);
+        (Some(symbol), Some(filename))
+    } else {
+        warnings.push(Diagnostic::warn(format!(
+            "No symbol generated: writing the KiCad symbol failed ({} EasyEDA symbol shapes)",
+            result.meta.symbol_shapes.len()
+        )));
+        (None, None)
+    };
+
+    let zen_content = generator.generate_component(
+        part,
+        name,
+        &pin_tuples,
+        &result.meta,
+        &footprint_filename,
+        &symbol_filename,
+    )?;
+
+    let (pads, _) = parse_footprint_shapes(&result.meta.footprint_shapes, false);
+    let pad_numbers: Vec<&str> = pads.iter().map(|p| p.number.as_str()).collect();
+    let gaps = pad_number_gaps(&pad_numbers);
+    if !gaps.is_empty() {
+        warnings.push(Diagnostic::warn(format!(
+            "Footprint pad numbers skip {}; some pads may not have been parsed",
+            format_gaps(&gaps)
+        )));
+    }
+
+    Ok(GenerateResult {
+        zen_content,
+        footprint_content,
+        footprint_filename,
+        symbol_content,
+        symbol_filename,
+        pin_count: pin_tuples.len(),
+        warnings,
+        easyeda_url: result.meta.easyeda_url(),
+    })
+}
+
+/// Check that every `.zen` pin number appears exactly once in the symbol.
+///
+/// The `.zen` pins dict is keyed by the same numbers as the symbol pins; if
+/// they diverge the component can't be wired up.
+fn check_symbol_pins(symbol: &str, pins: &[(String, String)]) -> Result<()> {
+    let root = sexpr::parse(symbol).context("Generated symbol is not valid")?;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut stack = vec![&root];
+    while let Some(node) = stack.pop() {
+        if node.head() == Some("pin") {
+            if let Some(number) = node.child("number").and_then(|n| n.items().get(1)?.as_atom()) {
+                *counts.entry(number).or_default() += 1;
+            }
+        }
+        stack.extend(node.items().iter().filter(|c| c.head().is_some()));
+    }
+
+    let mut problems = Vec::new();
+    for (number, _) in pins {
+        match counts.get(number.as_str()) {
+            Some(1) => {}
+            None => problems.push(format!("{} is missing", number)),
+            Some(n) => problems.push(format!("{} appears {} times", number, n)),
+        }
+    }
+    problems.dedup();
+
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "Symbol pins don't match the component pins: {}",
+            problems.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Explain why no footprint could be generated from the EasyEDA data.
+///
+/// With request debugging on, the first few raw shapes are also printed.
+fn footprint_diagnostic(meta: &ComponentMeta) -> String {
+    let shapes = &meta.footprint_shapes;
+    if http::debug_enabled() {
+        for shape in shapes.iter().take(DEBUG_SHAPE_LINES) {
+            eprintln!("[debug] footprint shape: {}", shape);
+        }
+    }
+
+    let reason = if meta.footprint_name.is_none() {
+        "EasyEDA has no package for this part".to_string()
+    } else if shapes.is_empty() {
+        "EasyEDA returned no footprint shapes".to_string()
+    } else {
+        let (pads, _) = parse_footprint_shapes(shapes, false);
+        if pads.is_empty() {
+            format!("none of the {} footprint shapes parsed as pads", shapes.len())
+        } else {
+            format!(
+                "writing the KiCad footprint failed ({} pads from {} shapes)",
+                pads.len(),
+                shapes.len()
+            )
+        }
+    };
+    format!("No footprint generated: {}", reason)
+}
+
+/// Runs of numbers missing from an otherwise numeric pad sequence
+/// (1, 2, 4, 7 -> 3, 5-6).
+///
+/// Returns nothing when any pad number isn't purely numeric (e.g. BGA balls
+/// like `A1`), since those grids aren't expected to be contiguous.
+fn pad_number_gaps(numbers: &[&str]) -> Vec<RangeInclusive<u32>> {
+    let Some(mut parsed) = numbers
+        .iter()
+        .map(|n| n.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()
+    else {
+        return Vec::new();
+    };
+    parsed.sort_unstable();
+    parsed.dedup();
+
+    parsed
+        .windows(2)
+        .filter(|w| w[1] > w[0] + 1)
+        .map(|w| w[0] + 1..=w[1] - 1)
+        .collect()
+}
+
+/// List pad number gaps as `3, 5–6`, naming at most [`MAX_LISTED_GAPS`].
+fn format_gaps(gaps: &[RangeInclusive<u32>]) -> String {
+    let mut listed: Vec<String> = gaps
+        .iter()
+        .take(MAX_LISTED_GAPS)
+        .map(|gap| {
+            if gap.start() == gap.end() {
+                gap.start().to_string()
+            } else {
+                format!("{}–{}", gap.start(), gap.end())
+            }
         })
+        .collect();
+    if gaps.len() > MAX_LISTED_GAPS {
+        listed.push(format!("and {} more", gaps.len() - MAX_LISTED_GAPS));
     }
+    listed.join(", ")
+}
+
+/// Generate components for every part referenced in a BOM.
+///
+/// Resolves each placed BOM line to an LCSC code and hands the unique codes
+/// to [`execute_batch`]. With `skip_generics`, passives that are covered by
+/// stdlib generics are left out.
+pub fn execute_from_bom(
+    bom_path: &PathBuf,
+    output_dir: Option<PathBuf>,
+    skip_generics: bool,
+    options: &GenerateOptions,
+) -> Result<()> {
+    let source = options.part_source();
+    let lcsc_parts: Vec<String> = bom::resolve_bom_parts(bom_path, source.as_ref())?
+        .into_iter()
+        .filter(|p| !(skip_generics && p.uses_stdlib_generic()))
+        .map(|p| p.lcsc)
+        .collect();
+
+    if lcsc_parts.is_empty() {
+        anyhow::bail!("No parts to generate from {}", bom_path.display());
+    }
+
+    execute_batch(&lcsc_parts, output_dir, options)
+}
+
+/// Generate parts from a result set saved with `search --save`.
+///
+/// `indices` are 1-based positions in the saved results; `all` takes every
+/// part. Parts come from the file, so nothing is searched again.
+pub fn execute_from_file(
+    path: &Path,
+    indices: &[usize],
+    all: bool,
+    output_dir: Option<PathBuf>,
+    options: &GenerateOptions,
+) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let saved: Vec<JlcPart> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse saved search results: {}", path.display()))?;
+
+    let selected = select_saved(saved, indices, all)
+        .with_context(|| format!("Invalid selection from {}", path.display()))?;
+    let lcsc_parts: Vec<String> = selected.iter().map(|p| p.lcsc.clone()).collect();
+
+    let source = SnapshotSource::from_parts(selected);
+    generate_batch(&source, &lcsc_parts, output_dir, options, Prefetched::default())
+}
+
+/// Pick saved search results by 1-based position, or all of them.
+fn select_saved(saved: Vec<JlcPart>, indices: &[usize], all: bool) -> Result<Vec<JlcPart>> {
+    if all {
+        return Ok(saved);
+    }
+    if indices.is_empty() {
+        anyhow::bail!("Pass --index N or --all to choose parts");
+    }
+
+    indices
+        .iter()
+        .map(|&i| {
+            i.checked_sub(1)
+                .and_then(|i| saved.get(i))
+                .cloned()
+                .with_context(|| {
+                    format!("--index {} is out of range (1-{})", i, saved.len())
+                })
+        })
+        .collect()
 }
 
 /// Generate components for multiple parts at once.
 pub fn execute_batch(
     lcsc_parts: &[String],
     output_dir: Option<PathBuf>,
-    options: &ExtractionOptions,
+    options: &GenerateOptions,
 ) -> Result<()> {
-    let client = JlcpcbClient::new();
-    let generator = ZenGenerator::new();
+    if options.extraction.offline || lcsc_parts.len() < 2 {
+        let source = options.part_source();
+        return generate_batch(
+            source.as_ref(),
+            lcsc_parts,
+            output_dir,
+            options,
+            Prefetched::default(),
+        );
+    }
 
-    let mut success_count = 0;
-    let mut fail_count = 0;
+    let source = options.part_source();
+    let prefetched = prefetch(source.as_ref(), lcsc_parts, options)?;
+    generate_batch(source.as_ref(), lcsc_parts, output_dir, options, prefetched)
+}
 
-    for lcsc in lcsc_parts {
-        let lcsc_normalized = if lcsc.starts_with('C') {
-            lcsc.to_string()
+/// What the parallel prefetch left in the caches.
+#[derive(Default)]
+struct Prefetched {
+    /// Parts whose pin data is fresh, so `--refresh` needn't fetch them again
+    refreshed: HashSet<String>,
+    /// Prefetch failures, reported with the batch's other diagnostics
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Warm the part and pin caches for `lcsc_parts` on a few threads, so the
+/// network round trips overlap instead of adding up.
+///
+/// Part lookup failures are left for the generation pass, which retries the
+/// lookup and reports the error for that part. Pin fetch failures are
+/// returned as warnings, and those parts keep `--refresh` for the retry.
+///
+/// The threads share `source` and one EasyEDA client, and so their
+/// connection pools.
+fn prefetch(
+    source: &dyn PartSource,
+    lcsc_parts: &[String],
+    options: &GenerateOptions,
+) -> Result<Prefetched> {
+    eprintln!(
+        "{} Fetching {} parts ({} at a time)...",
+        "→".cyan(),
+        lcsc_parts.len(),
+        PREFETCH_THREADS.min(lcsc_parts.len())
+    );
+    let easyeda = EasyEdaClient::new()?;
+    let prefetched = std::sync::Mutex::new(Prefetched::default());
+    for_each_parallel(lcsc_parts, PREFETCH_THREADS, |lcsc| {
+        let Some(lcsc) = normalize_lcsc(lcsc) else {
+            return;
+        };
+        let Ok(Some(part)) = source.get_part(&lcsc) else {
+            return;
+        };
+        let fetched = if part.uses_stdlib_generic() {
+            Ok(())
         } else {
-            format!("C{}", lcsc)
+            prefetch_pins(&part, &options.extraction, &easyeda)
         };
 
-        // Get the part from API
-        let part = match client.get_part(&lcsc_normalized) {
+        let mut prefetched = prefetched.lock().unwrap();
+        match fetched {
+            Ok(()) => {
+                prefetched.refreshed.insert(lcsc);
+            }
+            Err(e) => prefetched.diagnostics.push(
+                Diagnostic::warn(format!("Failed to prefetch pins, retrying: {:#}", e))
+                    .with_subject(lcsc),
+            ),
+        }
+    });
+    Ok(prefetched.into_inner().unwrap())
+}
+
+/// Call `f` on every item, spread over at most `threads` scoped threads.
+fn for_each_parallel<T: Sync>(items: &[T], threads: usize, f: impl Fn(&T) + Sync) {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, items.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(item) =
+                    items.get(next.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+                {
+                    f(item);
+                }
+            });
+        }
+    });
+}
+
+/// `--format json` output of a batch.
+#[derive(Serialize)]
+struct BatchReport<'a> {
+    parts: &'a [GenerationReport],
+    /// Problems with the batch as a whole, e.g. a failed prefetch; each
+    /// part's own warnings are in its report
+    warnings: &'a [Diagnostic],
+    /// Parts that weren't generated, with the reason
+    failed: &'a [Diagnostic],
+}
+
+/// Reject options that only make sense for a single part.
+fn check_batch_options(lcsc_parts: &[String], options: &GenerateOptions) -> Result<()> {
+    // The CSV is keyed by pin number alone, so it fits just one part
+    if lcsc_parts.len() > 1 && !options.merge_pins.is_empty() {
+        anyhow::bail!("--merge-pins applies to a single part, not {}", lcsc_parts.len());
+    }
+    Ok(())
+}
+
+/// Generate each part in turn, looking parts up in `source`.
+///
+/// Parts in `prefetched.refreshed` were just fetched, so they are generated
+/// from the cache even with `--refresh`.
+fn generate_batch(
+    source: &dyn PartSource,
+    lcsc_parts: &[String],
+    output_dir: Option<PathBuf>,
+    options: &GenerateOptions,
+    prefetched: Prefetched,
+) -> Result<()> {
+    check_batch_options(lcsc_parts, options)?;
+    let generator = options.zen_generator()?;
+    let library_dir = output_dir.unwrap_or_else(|| PathBuf::from("components").join("JLCPCB"));
+    let mut cached = options.clone();
+    cached.extraction.refresh = false;
+
+    let mut reports: Vec<GenerationReport> = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut index_entries: Vec<IndexEntry> = Vec::new();
+    let mut failed: Vec<Diagnostic> = Vec::new();
+    let mut fail = |lcsc: &str, message: String| {
+        if !options.json {
+            eprintln!("{} {}", "✗".red(), message);
+        }
+        failed.push(Diagnostic::error(message).with_subject(lcsc));
+    };
+
+    for lcsc in lcsc_parts {
+        let lcsc_normalized = match normalize_lcsc(lcsc) {
+            Some(code) => code,
+            None => {
+                fail(lcsc, format!("Invalid LCSC part number: {}", lcsc));
+                continue;
+            }
+        };
+
+        // Get the part from API (or the part cache when offline)
+        let part = match source.get_part(&lcsc_normalized) {
             Ok(Some(p)) => p,
+            Ok(None) if options.extraction.offline => {
+                let message = format!("Part {} is not in the part cache", lcsc_normalized);
+                fail(&lcsc_normalized, message);
+                continue;
+            }
             Ok(None) => {
-                eprintln!("{} Part {} not found", "✗".red(), lcsc_normalized);
-                fail_count += 1;
+                fail(&lcsc_normalized, format!("Part {} not found", lcsc_normalized));
                 continue;
             }
             Err(e) => {
-                eprintln!("{} Failed to fetch {}: {}", "✗".red(), lcsc_normalized, e);
-                fail_count += 1;
+                let message = format!("Failed to fetch {}: {}", lcsc_normalized, e);
+                fail(&lcsc_normalized, message);
                 continue;
             }
         };
 
         // Determine output directory
-        let part_dir = output_dir
-            .clone()
-            .unwrap_or_else(|| PathBuf::from("components").join("JLCPCB"))
-            .join(sanitize_mpn(&part.mpn));
-
-        // Create output directory
-        if let Err(e) = fs::create_dir_all(&part_dir) {
-            eprintln!(
-                "{} Failed to create directory for {}: {}",
-                "✗".red(),
-                lcsc_normalized,
-                e
-            );
-            fail_count += 1;
-            continue;
-        }
+        let part_dir = if options.flat {
+            library_dir.clone()
+        } else {
+            library_dir.join(sanitize_mpn(&part.mpn))
+        };
 
         let component_name = sanitize_mpn(&part.mpn);
+        let part_options = if prefetched.refreshed.contains(&lcsc_normalized) {
+            &cached
+        } else {
+            options
+        };
 
         // Generate and write
-        match generate_zen_content(&generator, &part, &component_name, options) {
-            Ok(result) => {
-                let zen_path = part_dir.join(format!("{}.zen", component_name));
-                if let Err(e) = fs::write(&zen_path, &result.zen_content) {
-                    eprintln!(
-                        "{} Failed to write {}: {}",
-                        "✗".red(),
-                        zen_path.display(),
-                        e
-                    );
-                    fail_count += 1;
-                    continue;
-                }
-
-                // Write symbol file if available
-                if let (Some(symbol_content), Some(symbol_filename)) =
-                    (&result.symbol_content, &result.symbol_filename)
-                {
-                    let symbol_path = part_dir.join(symbol_filename);
-                    if let Err(e) = fs::write(&symbol_path, symbol_content) {
-                        eprintln!(
-                            "{} Failed to write {}: {}",
-                            "✗".red(),
-                            symbol_path.display(),
-                            e
-                        );
-                    }
-                }
+        match generate_part(&generator, part, &part_dir, &component_name, part_options) {
+            Ok(report) => {
+                diagnostics.extend(report.warnings.iter().cloned());
 
-                // Write footprint file if available
-                if let (Some(footprint_content), Some(footprint_filename)) =
-                    (&result.footprint_content, &result.footprint_filename)
-                {
-                    let footprint_path = part_dir.join(footprint_filename);
-                    if let Err(e) = fs::write(&footprint_path, footprint_content) {
-                        eprintln!(
-                            "{} Failed to write {}: {}",
-                            "✗".red(),
-                            footprint_path.display(),
-                            e
-                        );
-                    }
+                if options.update_index {
+                    index_entries.push(IndexEntry::new(&report.part, &part_dir));
                 }
 
-                // Write pcb.toml
-                let toml_path = part_dir.join("pcb.toml");
-                if !toml_path.exists() {
-                    let _ = fs::write(&toml_path, "");
+                if !options.json {
+                    println!(
+                        "{} {} → {}",
+                        "✓".green(),
+                        lcsc_normalized,
+                        report.zen.display().to_string().cyan()
+                    );
                 }
-
-                println!(
-                    "{} {} → {}",
-                    "✓".green(),
-                    lcsc_normalized,
-                    zen_path.display().to_string().cyan()
-                );
-                success_count += 1;
+                reports.push(report);
             }
             Err(e) => {
-                eprintln!(
-                    "{} Failed to generate for {}: {}",
-                    "✗".red(),
-                    lcsc_normalized,
-                    e
-                );
-                fail_count += 1;
+                let message = format!("Failed to generate for {}: {}", lcsc_normalized, e);
+                fail(&lcsc_normalized, message);
             }
         }
     }
 
+    if !index_entries.is_empty() {
+        update_index(&library_dir.join(INDEX_FILE), index_entries)?;
+    }
+
+    if options.json {
+        let batch = BatchReport {
+            parts: &reports,
+            warnings: &prefetched.diagnostics,
+            failed: &failed,
+        };
+        println!("{}", super::to_json(&batch, options.json_compact)?);
+        return Ok(());
+    }
+
+    let warnings: Vec<Diagnostic> = prefetched.diagnostics.into_iter().chain(diagnostics).collect();
+    if !warnings.is_empty() {
+        eprintln!();
+        super::print_diagnostics(&warnings);
+    }
+    let fail_count = failed.len();
     println!(
         "\n{} Generated {} components, {} failed",
         if fail_count == 0 {
@@ -332,9 +1175,400 @@ pub fn execute_batch(
         } else {
             "!".yellow().bold()
         },
-        success_count,
+        reports.len(),
         fail_count
     );
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easyeda::{generate_kicad_sym, Pin, SymbolProperties};
+
+    #[test]
+    fn test_pad_number_gaps() {
+        assert_eq!(pad_number_gaps(&["1", "2", "4", "7"]), vec![3..=3, 5..=6]);
+        assert!(pad_number_gaps(&["2", "1", "3", "3"]).is_empty());
+        assert!(pad_number_gaps(&["A1", "A3", "B2"]).is_empty());
+        assert!(pad_number_gaps(&[]).is_empty());
+
+        assert_eq!(format_gaps(&pad_number_gaps(&["1", "2", "1000"])), "3–999");
+        assert_eq!(format_gaps(&[3..=3, 5..=6]), "3, 5–6");
+        let many: Vec<String> = (1..=20).map(|n| (n * 2).to_string()).collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        assert_eq!(
+            format_gaps(&pad_number_gaps(&many)),
+            "3, 5, 7, 9, 11, and 14 more"
+        );
+    }
+
+    #[test]
+    fn test_footprint_diagnostic() {
+        let mut meta = ComponentMeta::default();
+        assert!(footprint_diagnostic(&meta).contains("no package"));
+
+        meta.footprint_name = Some("SOT-23-3".to_string());
+        assert!(footprint_diagnostic(&meta).contains("no footprint shapes"));
+
+        meta.footprint_shapes = vec!["TEXT~N~4000~3000".to_string(); 3];
+        assert!(footprint_diagnostic(&meta).contains("none of the 3 footprint shapes"));
+    }
+
+    #[test]
+    fn test_footprint_origin_for_connectors() {
+        let header = JlcPart {
+            category: "Connectors".to_string(),
+            subcategory: "Pin Headers".to_string(),
+            ..Default::default()
+        };
+        let mcu = JlcPart {
+            category: "Embedded Processors & Controllers".to_string(),
+            subcategory: "Microcontroller Units (MCUs/MPUs/SOCs)".to_string(),
+            ..Default::default()
+        };
+        let options = GenerateOptions {
+            connector_origin_pin1: true,
+            ..Default::default()
+        };
+
+        assert_eq!(footprint_origin(&header, &options), FootprintOrigin::Pin1);
+        assert_eq!(footprint_origin(&mcu, &options), FootprintOrigin::Center);
+        assert_eq!(
+            footprint_origin(&header, &GenerateOptions::default()),
+            FootprintOrigin::Center
+        );
+    }
+
+    #[test]
+    fn test_write_pretty_footprint() {
+        let result = GenerateResult {
+            zen_content: String::new(),
+            footprint_content: Some("(footprint \"X\")".to_string()),
+            footprint_filename: Some(footprint_file("X", true)),
+            symbol_content: None,
+            symbol_filename: None,
+            pin_count: 0,
+            warnings: Vec::new(),
+            easyeda_url: None,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let report =
+            write_part(JlcPart::default(), result, dir.path(), "X", &GenerateOptions::default())
+                .unwrap();
+        assert_eq!(report.footprint, Some(dir.path().join("JLCPCB.pretty/X.kicad_mod")));
+        assert!(dir.path().join("JLCPCB.pretty/X.kicad_mod").exists());
+        assert_eq!(footprint_file("X", false), "X.kicad_mod");
+    }
+
+    #[test]
+    fn test_run_formatter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("X.zen");
+        fs::write(&path, "").unwrap();
+
+        // cargo is on every machine that runs the tests, unlike true/false
+        assert_eq!(run_formatter("cargo --version", &path), None);
+        let warning = run_formatter("cargo pcb-jlcpcb-no-such-command", &path).unwrap();
+        assert!(warning.contains("exited with"), "{}", warning);
+        let warning = run_formatter("pcb-jlcpcb-no-such-formatter", &path).unwrap();
+        assert!(warning.starts_with("Failed to run formatter"), "{}", warning);
+        assert!(run_formatter("  ", &path).is_some());
+    }
+
+    #[test]
+    fn test_write_readme() {
+        let part = JlcPart {
+            lcsc: "C307331".to_string(),
+            mpn: "MAX98357AETE+T".to_string(),
+            description: "Class-D audio amplifier".to_string(),
+            datasheet: Some("https://example.com/max98357.pdf".to_string()),
+            ..Default::default()
+        };
+        let result = || GenerateResult {
+            zen_content: String::new(),
+            footprint_content: None,
+            footprint_filename: None,
+            symbol_content: None,
+            symbol_filename: None,
+            pin_count: 0,
+            warnings: Vec::new(),
+            easyeda_url: Some("https://easyeda.com/component/abc".to_string()),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = GenerateOptions {
+            write_readme: true,
+            ..Default::default()
+        };
+
+        let report = write_part(part.clone(), result(), dir.path(), "MAX98357", &options).unwrap();
+        let readme = fs::read_to_string(report.readme.unwrap()).unwrap();
+        assert!(readme.starts_with("# MAX98357\n\nClass-D audio amplifier\n"));
+        assert!(readme.contains("- LCSC: [C307331](https://www.lcsc.com/product-detail/C307331.html)"));
+        assert!(readme.contains("- Datasheet: <https://example.com/max98357.pdf>"));
+        assert!(readme.contains("- EasyEDA: <https://easyeda.com/component/abc>"));
+
+        // An existing README is kept unless forced
+        fs::write(dir.path().join("README.md"), "notes").unwrap();
+        let report = write_part(part.clone(), result(), dir.path(), "MAX98357", &options).unwrap();
+        assert!(report.readme.is_none());
+        assert_eq!(report.warnings.len(), 1);
+
+        options.force_readme = true;
+        let report = write_part(part.clone(), result(), dir.path(), "MAX98357", &options).unwrap();
+        assert!(report.readme.is_some());
+        assert_ne!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "notes");
+
+        // Flat batches share the directory, so each part gets its own README
+        options.flat = true;
+        let report = write_part(part, result(), dir.path(), "MAX98357", &options).unwrap();
+        assert_eq!(report.readme.unwrap(), dir.path().join("MAX98357.README.md"));
+    }
+
+    #[test]
+    fn test_for_each_parallel_visits_each_item_once() {
+        let items: Vec<usize> = (1..=25).collect();
+        let sum = std::sync::atomic::AtomicUsize::new(0);
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        for_each_parallel(&items, 4, |n| {
+            sum.fetch_add(*n, std::sync::atomic::Ordering::Relaxed);
+            calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+        assert_eq!(sum.into_inner(), 325);
+        assert_eq!(calls.into_inner(), 25);
+
+        for_each_parallel(&[] as &[usize], 4, |_| panic!("no items"));
+    }
+
+    /// Search knows the library flags; the detail endpoint only attributes.
+    struct DetailSource;
+
+    impl PartSource for DetailSource {
+        fn get_part(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            Ok(Some(JlcPart {
+                lcsc: lcsc.to_string(),
+                basic: true,
+                preferred: true,
+                ..Default::default()
+            }))
+        }
+
+        fn search(&self, _keyword: &str, _page: i32, _page_size: i32) -> Result<Vec<JlcPart>> {
+            Ok(Vec::new())
+        }
+
+        fn get_part_details(&self, lcsc: &str) -> Result<Option<JlcPart>> {
+            let mut part = JlcPart {
+                lcsc: lcsc.to_string(),
+                ..Default::default()
+            };
+            part.attributes.resistance = Some("10kΩ".to_string());
+            Ok(Some(part))
+        }
+    }
+
+    #[test]
+    fn test_resolve_part_keeps_library_flags() {
+        let part = resolve_part(&DetailSource, "C25744", &GenerateOptions::default()).unwrap();
+        assert_eq!(part.attributes.resistance.as_deref(), Some("10kΩ"));
+        assert!(part.basic);
+        assert!(part.preferred);
+    }
+
+    #[test]
+    fn test_merge_pin_names() {
+        let mut pins: Vec<Pin> = ["VCC", "GND", "SDA", "SCL", "NC"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Pin {
+                number: (i + 1).to_string(),
+                name: name.to_string(),
+            })
+            .collect();
+
+        let names = parse_pin_names("number,name\n3,SCL\n4,SDA\n").unwrap();
+        merge_pin_names(&mut pins, &names).unwrap();
+        let merged: Vec<&str> = pins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(merged, ["VCC", "GND", "SCL", "SDA", "NC"]);
+
+        let typo = parse_pin_names("9,EN\n").unwrap();
+        assert!(merge_pin_names(&mut pins, &typo).is_err());
+        assert!(parse_pin_names("3,\n").is_err());
+    }
+
+    #[test]
+    fn test_merge_pins_rejected_for_batches_and_passives() {
+        let options = GenerateOptions {
+            merge_pins: parse_pin_names("1,EN\n").unwrap(),
+            ..Default::default()
+        };
+        let resistor = JlcPart {
+            lcsc: "C25744".to_string(),
+            category: "Resistors".to_string(),
+            ..Default::default()
+        };
+        let source = SnapshotSource::from_parts([resistor.clone()]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let two = ["C25744".to_string(), "C307331".to_string()];
+        let err = generate_batch(&source, &two, None, &options, Prefetched::default());
+        assert!(err.unwrap_err().to_string().contains("single part"));
+
+        let err = generate_part(&ZenGenerator::new(), resistor, dir.path(), "R", &options);
+        assert!(err.unwrap_err().to_string().contains("stdlib generic"));
+        assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_check_symbol_pins() {
+        let pins = |list: &[(&str, &str)]| -> Vec<Pin> {
+            list.iter()
+                .map(|(number, name)| Pin {
+                    number: number.to_string(),
+                    name: name.to_string(),
+                })
+                .collect()
+        };
+        let tuples = |pins: &[Pin]| -> Vec<(String, String)> {
+            pins.iter().map(|p| (p.number.clone(), p.name.clone())).collect()
+        };
+        let symbol_for = |pins: &[Pin]| {
+            let (props, style) = (SymbolProperties::default(), SymbolStyle::default());
+            generate_kicad_sym("T", pins, &[], &props, &style, KicadVersion::default()).unwrap()
+        };
+
+        let good = pins(&[("1", "VCC"), ("2", "GND"), ("A1", "OUT")]);
+        assert!(check_symbol_pins(&symbol_for(&good), &tuples(&good)).is_ok());
+
+        // A duplicated pin number ends up in the symbol twice
+        let duplicated = pins(&[("1", "VCC"), ("1", "VDD"), ("2", "GND")]);
+        let err = check_symbol_pins(&symbol_for(&duplicated), &tuples(&duplicated)).unwrap_err();
+        assert!(err.to_string().contains("1 appears 2 times"), "{}", err);
+
+        let mut extra = tuples(&good);
+        extra.push(("3".to_string(), "EN".to_string()));
+        let err = check_symbol_pins(&symbol_for(&good), &extra).unwrap_err();
+        assert!(err.to_string().contains("3 is missing"), "{}", err);
+    }
+
+    #[test]
+    fn test_select_saved() {
+        let saved: Vec<JlcPart> = ["C1", "C2", "C3"]
+            .iter()
+            .map(|lcsc| JlcPart {
+                lcsc: lcsc.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let picked = select_saved(saved.clone(), &[3, 1], false).unwrap();
+        assert_eq!(picked[0].lcsc, "C3");
+        assert_eq!(picked[1].lcsc, "C1");
+        assert_eq!(select_saved(saved.clone(), &[], true).unwrap().len(), 3);
+        assert!(select_saved(saved.clone(), &[0], false).is_err());
+        assert!(select_saved(saved.clone(), &[4], false).is_err());
+        assert!(select_saved(saved, &[], false).is_err());
+    }
+
+    #[test]
+    fn test_generate_part_report_for_passive() {
+        let dir = tempfile::tempdir().unwrap();
+        let part = JlcPart {
+            lcsc: "C25744".to_string(),
+            mpn: "0402WGF1002TCE".to_string(),
+            category: "Resistors".to_string(),
+            subcategory: "Chip Resistor - Surface Mount".to_string(),
+            package: "0402".to_string(),
+            description: "62.5mW ±1% 10kΩ 0402 Chip Resistor".to_string(),
+            ..Default::default()
+        };
+
+        let report = generate_part(
+            &ZenGenerator::new(),
+            part,
+            dir.path(),
+            "R_10k",
+            &GenerateOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.lcsc, "C25744");
+        assert_eq!(report.name, "R_10k");
+        assert_eq!(report.pin_count, 2);
+        assert!(report.symbol.is_none() && report.footprint.is_none());
+        assert_eq!(report.files_written(), vec![dir.path().join("R_10k.zen")]);
+        assert!(report.zen.exists());
+        assert!(dir.path().join("pcb.toml").exists());
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_hand_edit_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("X.kicad_mod");
+        let generated =
+            "(footprint \"X\"\n  (descr \"pcb-jlcpcb source fnv1a:0123456789abcdef\")\n)";
+        assert!(hand_edit_warning(&path, generated).is_none());
+
+        fs::write(&path, generated).unwrap();
+        assert!(hand_edit_warning(&path, generated).is_none());
+
+        // Same source, different content: edited
+        fs::write(&path, generated.replace(")\n)", ")\n  (attr smd)\n)")).unwrap();
+        assert!(hand_edit_warning(&path, generated).is_some());
+
+        // Source changed: expected to differ
+        let newer = generated.replace("0123456789abcdef", "fedcba9876543210");
+        assert!(hand_edit_warning(&path, &newer).is_none());
+
+        // Written before hashing: nothing to compare against
+        fs::write(&path, "(footprint \"X\")").unwrap();
+        assert!(hand_edit_warning(&path, generated).is_none());
+    }
+
+    #[test]
+    fn test_update_index_replaces_existing_lcsc() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_path = dir.path().join("components/JLCPCB/index.json");
+        let part = |lcsc: &str, mpn: &str| JlcPart {
+            lcsc: lcsc.to_string(),
+            mpn: mpn.to_string(),
+            ..Default::default()
+        };
+
+        update_index(
+            &index_path,
+            vec![IndexEntry::new(&part("C25744", "OLD"), Path::new("a"))],
+        )
+        .unwrap();
+        update_index(
+            &index_path,
+            vec![
+                IndexEntry::new(&part("C1525", "CL05B104KO5NNNC"), Path::new("b")),
+                IndexEntry::new(&part("C25744", "0402WGF1002TCE"), Path::new("c")),
+            ],
+        )
+        .unwrap();
+
+        let entries: Vec<IndexEntry> =
+            serde_json::from_str(&fs::read_to_string(&index_path).unwrap()).unwrap();
+        let lcsc: Vec<&str> = entries.iter().map(|e| e.lcsc.as_str()).collect();
+        assert_eq!(lcsc, ["C25744", "C1525"]);
+        assert_eq!(entries[0].mpn, "0402WGF1002TCE");
+        assert_eq!(entries[0].path, Path::new("c"));
+    }
+
+    #[test]
+    fn test_library_index_follows_output() {
+        assert_eq!(
+            library_index(Path::new("components/JLCPCB/TPS5430")),
+            Path::new("components/JLCPCB/index.json")
+        );
+        assert_eq!(
+            library_index(Path::new("elsewhere/lib/TPS5430")),
+            Path::new("elsewhere/lib/index.json")
+        );
+        assert_eq!(library_index(Path::new("TPS5430")), Path::new("index.json"));
+    }
+}
@@ -5,3 +5,73 @@ pub mod generate;
 pub mod search;
 pub mod setup_claude;
 pub mod util;
+
+use colored::Colorize;
+use serde::Serialize;
+
+/// Severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Warn,
+    Error,
+}
+
+/// A problem or heuristic noticed while processing.
+///
+/// Collected alongside the results, listed under `warnings` in JSON output
+/// and printed once processing is done in human mode.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// What it's about, e.g. an LCSC code or BOM designators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warn(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warn,
+            subject: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Error,
+            ..Self::warn(message)
+        }
+    }
+
+    /// Attach the LCSC code or designators the diagnostic is about.
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+}
+
+/// Print diagnostics to stderr, one per line.
+pub fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        let mark = match diagnostic.level {
+            Level::Warn => "⚠".yellow().bold(),
+            Level::Error => "✗".red().bold(),
+        };
+        match &diagnostic.subject {
+            Some(subject) => eprintln!("{} {}: {}", mark, subject, diagnostic.message),
+            None => eprintln!("{} {}", mark, diagnostic.message),
+        }
+    }
+}
+
+/// Serialize `--format json` output, on a single line when `compact`.
+pub fn to_json<T: Serialize + ?Sized>(value: &T, compact: bool) -> serde_json::Result<String> {
+    if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
@@ -1,13 +1,24 @@
 //! Search command - find parts in JLCPCB parts library.
 
-use anyhow::Result;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 use tabled::{
-    settings::{style::Style, Alignment, Modify},
+    builder::Builder,
+    settings::{object::Cell, style::Style, Alignment, Modify, Remove},
     Table, Tabled,
 };
 
-use crate::api::{JlcpcbClient, JlcPart, LibraryType};
+use super::generate::{resolve_part, GenerateOptions};
+use crate::api::{normalize_lcsc, JlcpcbClient, JlcPart, LibraryType, PartSource};
+use crate::generator::{part_dielectric, part_tolerance};
+use crate::{atomic, config};
 
 /// Output format for search results.
 #[derive(Debug, Clone, Copy, Default)]
@@ -17,6 +28,208 @@ pub enum OutputFormat {
     Json,
 }
 
+/// Default order quantity used for displayed and filtered prices.
+const PRICE_QTY: i32 = 100;
+
+/// Page size used when scanning results for client-side filters.
+const FILTER_PAGE_SIZE: i32 = 100;
+
+/// Maximum number of API pages scanned to fill a filtered page.
+const MAX_FILTER_PAGES: i32 = 10;
+
+/// Stock below this multiple of `--stock-min` is highlighted as limited.
+const STOCK_WARN_FACTOR: i64 = 2;
+
+/// Maximum number of trailing terms `--min-results` drops from the query.
+const MAX_BROADEN_STEPS: usize = 3;
+
+/// Shortest query sent to the API; single characters match most of the catalog.
+const MIN_QUERY_CHARS: usize = 2;
+
+/// Search history file under the cache root.
+const HISTORY_FILE: &str = "history.json";
+
+/// Most recent searches kept in the history.
+const HISTORY_LIMIT: usize = 50;
+
+/// Client-side filters applied on top of the API search.
+#[derive(Debug, Clone, Default)]
+pub struct PartFilter {
+    /// Minimum unit price (USD) at the display quantity
+    pub min_price: Option<f64>,
+    /// Maximum unit price (USD) at the display quantity
+    pub max_price: Option<f64>,
+    /// Drop parts without a price instead of listing them last
+    pub require_price: bool,
+    /// Minimum stock; parts close to it are highlighted
+    pub stock_min: Option<i64>,
+    /// Display quantity for prices (default [`PRICE_QTY`])
+    pub price_qty: Option<i32>,
+    /// Case-insensitive subcategory prefix
+    pub subcategory: Option<String>,
+    /// Package, matched case-insensitively (e.g. `0402`)
+    pub package: Option<String>,
+    /// Maximum tolerance in percent
+    pub tolerance: Option<f64>,
+    /// Capacitor dielectric (e.g. `X7R`); `C0G` and `NP0` are the same
+    pub dielectric: Option<String>,
+}
+
+impl PartFilter {
+    /// Order quantity prices are shown and filtered at.
+    fn price_qty(&self) -> i32 {
+        self.price_qty.unwrap_or(PRICE_QTY)
+    }
+
+    /// Whether any filter is set.
+    fn is_active(&self) -> bool {
+        self.min_price.is_some()
+            || self.max_price.is_some()
+            || self.require_price
+            || self.stock_min.is_some()
+            || self.subcategory.is_some()
+            || self.package.is_some()
+            || self.tolerance.is_some()
+            || self.dielectric.is_some()
+    }
+
+    /// Whether stock passes `--stock-min` but without much headroom.
+    fn stock_is_tight(&self, stock: i64) -> bool {
+        self.stock_min
+            .is_some_and(|min| stock >= min && stock < min.saturating_mul(STOCK_WARN_FACTOR))
+    }
+
+    /// Whether a part passes the filter.
+    fn matches(&self, part: &JlcPart) -> bool {
+        if self.stock_min.is_some_and(|min| part.stock < min) {
+            return false;
+        }
+        if let Some(prefix) = &self.subcategory {
+            let prefix = prefix.to_lowercase();
+            if !part.subcategory.to_lowercase().starts_with(&prefix) {
+                return false;
+            }
+        }
+        if self.package.as_ref().is_some_and(|p| !part.matches_package(p)) {
+            return false;
+        }
+        if let Some(max) = self.tolerance {
+            let tolerance = part_tolerance(part).and_then(|t| parse_tolerance(&t).ok());
+            if tolerance.is_none_or(|t| t > max) {
+                return false;
+            }
+        }
+        if let Some(wanted) = &self.dielectric {
+            let matches = part_dielectric(part)
+                .is_some_and(|d| dielectric_class(&d) == dielectric_class(wanted));
+            if !matches {
+                return false;
+            }
+        }
+        match part.price_at_qty(self.price_qty()) {
+            Some(price) => {
+                self.min_price.is_none_or(|min| price >= min)
+                    && self.max_price.is_none_or(|max| price <= max)
+            }
+            None => !self.require_price,
+        }
+    }
+}
+
+/// Parse a tolerance like `1%`, `±0.5%` or `5` into percent.
+pub fn parse_tolerance(tolerance: &str) -> Result<f64, String> {
+    let number = tolerance
+        .trim()
+        .trim_start_matches(['±', '+', '-'])
+        .trim_end_matches('%')
+        .trim();
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|t| t.is_finite() && *t >= 0.0)
+        .ok_or_else(|| format!("invalid tolerance '{}' (expected e.g. 1%)", tolerance))
+}
+
+/// Dielectric name for comparison: uppercase, with `NP0` as its `C0G` alias.
+pub(crate) fn dielectric_class(dielectric: &str) -> String {
+    match dielectric.trim().to_uppercase().as_str() {
+        "NP0" | "NPO" => "C0G".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Exact-match post-filter for `--exact`, chosen by the shape of the query.
+#[derive(Debug, Clone, PartialEq)]
+enum ExactQuery {
+    /// Query looks like an LCSC code (`C` followed by digits)
+    Lcsc(String),
+    /// Anything else is matched against the MPN, ignoring case
+    Mpn(String),
+}
+
+impl ExactQuery {
+    fn parse(query: &str) -> Self {
+        let query = query.trim();
+        match normalize_lcsc(query) {
+            Some(lcsc) if query.starts_with(['C', 'c']) => ExactQuery::Lcsc(lcsc),
+            _ => ExactQuery::Mpn(query.to_string()),
+        }
+    }
+
+    fn matches(&self, part: &JlcPart) -> bool {
+        match self {
+            ExactQuery::Lcsc(lcsc) => part.lcsc == *lcsc,
+            ExactQuery::Mpn(mpn) => part.mpn.eq_ignore_ascii_case(mpn),
+        }
+    }
+}
+
+/// Options for the search command.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub format: OutputFormat,
+    pub library_type: LibraryType,
+    /// Results per page
+    pub limit: usize,
+    /// Page number (1-indexed)
+    pub page: i32,
+    pub filter: PartFilter,
+    /// Only keep exact LCSC or MPN matches for the query
+    pub exact: bool,
+    /// Treat the query as comma-separated alternatives and merge the results
+    pub any: bool,
+    /// Show manufacturer, category and key attributes (one detail request per row)
+    pub wide: bool,
+    /// Emit single-line JSON
+    pub json_compact: bool,
+    /// Also write the results as JSON to this file, for `generate --from-file`
+    pub save: Option<PathBuf>,
+    /// Broaden the query when fewer than this many parts match
+    pub min_results: Option<usize>,
+    /// Print the categories found instead of the parts
+    pub category_list: bool,
+    /// Copy the LCSC code of this result (1-based) to the clipboard
+    pub copy: Option<usize>,
+}
+
+/// Clipboard tools tried in order by `--copy`: macOS, Wayland, X11, Windows.
+const CLIPBOARD_COMMANDS: [(&str, &[&str]); 5] = [
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip", &[]),
+];
+
+/// One page of (possibly filtered) search results.
+struct ResultsPage {
+    parts: Vec<JlcPart>,
+    /// Total matching parts (filtered matches when a filter is active)
+    total: i64,
+    /// API results scanned to apply the filter, if one is active
+    scanned: Option<i64>,
+}
+
 /// Table row for search results.
 #[derive(Tabled)]
 struct PartRow {
@@ -36,27 +249,452 @@ struct PartRow {
     price: String,
 }
 
+/// Table row for `--wide` search results.
+#[derive(Tabled)]
+struct WidePartRow {
+    #[tabled(rename = "")]
+    indicator: String,
+    #[tabled(rename = "LCSC")]
+    lcsc: String,
+    #[tabled(rename = "MPN")]
+    mpn: String,
+    #[tabled(rename = "Manufacturer")]
+    manufacturer: String,
+    #[tabled(rename = "Category")]
+    category: String,
+    #[tabled(rename = "Subcategory")]
+    subcategory: String,
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Value")]
+    value: String,
+    #[tabled(rename = "Tolerance")]
+    tolerance: String,
+    #[tabled(rename = "Voltage")]
+    voltage: String,
+    #[tabled(rename = "Dielectric")]
+    dielectric: String,
+    #[tabled(rename = "Stock")]
+    stock: String,
+    #[tabled(rename = "Price@100")]
+    price: String,
+    #[tabled(rename = "Min Order")]
+    min_order: String,
+}
+
 /// Execute the search command.
-pub fn execute(
-    query: &str,
-    format: OutputFormat,
-    library_type: LibraryType,
-    limit: usize,
-    page: i32,
-) -> Result<()> {
+pub fn execute(query: &str, options: &SearchOptions) -> Result<()> {
+    let query = validate_query(query, options.any)?;
     let client = JlcpcbClient::new();
-    let result = client.search_page(query, page, limit as i32, library_type)?;
-    let refs: Vec<&JlcPart> = result.parts.iter().collect();
+    let mut results = if options.any {
+        fetch_any(&client, query, options)?
+    } else {
+        fetch_page(&client, query, options)?
+    };
+
+    // Drop trailing terms from an over-specified query until enough match
+    let mut query = query.to_string();
+    if let Some(min) = options.min_results {
+        for _ in 0..MAX_BROADEN_STEPS {
+            if results.total >= min as i64 {
+                break;
+            }
+            let Some(broader) = broaden_query(&query) else {
+                break;
+            };
+            let broader_results = fetch_page(&client, &broader, options)?;
+            eprintln!(
+                "{} {} result(s) for '{}', broadened to '{}' ({} results)",
+                "→".cyan(),
+                results.total,
+                query,
+                broader.cyan(),
+                broader_results.total
+            );
+            query = broader;
+            results = broader_results;
+        }
+    }
+    let query = query.as_str();
+    record_search(query, results.total);
 
+    if options.category_list {
+        let counts = category_counts(&results.parts);
+        match options.format {
+            OutputFormat::Human => print_categories(&counts, query, results.parts.len()),
+            OutputFormat::Json => println!("{}", super::to_json(&counts, options.json_compact)?),
+        }
+        return Ok(());
+    }
+
+    if options.wide {
+        // Search results rarely carry structured attributes
+        for part in &mut results.parts {
+            if let Ok(Some(detailed)) = client.get_part_details(&part.lcsc) {
+                part.merge_details(detailed);
+            }
+        }
+    }
+    if let Some(path) = &options.save {
+        save_results(path, &results.parts)?;
+    }
+    let refs: Vec<&JlcPart> = results.parts.iter().collect();
+
+    match options.format {
+        OutputFormat::Human => print_human(&refs, query, options, &results),
+        OutputFormat::Json => print_json(&refs, options.json_compact)?,
+    }
+
+    if let Some(index) = options.copy {
+        copy_result(&results.parts, index)?;
+    }
+
+    Ok(())
+}
+
+/// Trim a search query and reject ones too short to search for.
+///
+/// With `--any`, each comma-separated keyword must pass on its own.
+fn validate_query(query: &str, any: bool) -> Result<&str> {
+    let query = query.trim();
+    let keywords: Vec<&str> = if any {
+        query.split(',').map(str::trim).collect()
+    } else {
+        vec![query]
+    };
+    for keyword in keywords {
+        if keyword.is_empty() {
+            anyhow::bail!("Search query is empty; give a part number, MPN or keywords");
+        }
+        if keyword.chars().count() < MIN_QUERY_CHARS {
+            anyhow::bail!(
+                "Search query '{}' is too short; use at least {} characters",
+                keyword,
+                MIN_QUERY_CHARS
+            );
+        }
+    }
+    Ok(query)
+}
+
+/// Put the LCSC code of result `index` (1-based) on the clipboard.
+///
+/// Without a usable clipboard tool (e.g. over SSH) the code is printed instead.
+fn copy_result(parts: &[JlcPart], index: usize) -> Result<()> {
+    let part = index
+        .checked_sub(1)
+        .and_then(|i| parts.get(i))
+        .with_context(|| {
+            format!("--copy {} is out of range: {} result(s) shown", index, parts.len())
+        })?;
+
+    match copy_to_clipboard(&part.lcsc) {
+        Some(tool) => eprintln!(
+            "{} Copied {} ({}) to the clipboard via {}",
+            "✓".green(),
+            part.lcsc.cyan(),
+            part.mpn,
+            tool
+        ),
+        None => eprintln!(
+            "{} No clipboard available; LCSC code: {}",
+            "!".yellow(),
+            part.lcsc.cyan()
+        ),
+    }
+    Ok(())
+}
+
+/// Pipe `text` into the first clipboard tool that accepts it.
+fn copy_to_clipboard(text: &str) -> Option<&'static str> {
+    CLIPBOARD_COMMANDS.iter().find_map(|&(program, args)| {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let written = child.stdin.take()?.write_all(text.as_bytes()).is_ok();
+        let status = child.wait().ok()?;
+        (written && status.success()).then_some(program)
+    })
+}
+
+/// A category/subcategory pair and how many results fall in it.
+#[derive(Debug, Serialize)]
+struct CategoryCount {
+    category: String,
+    subcategory: String,
+    count: usize,
+}
+
+/// Distinct categories among `parts`, sorted by category then subcategory.
+fn category_counts(parts: &[JlcPart]) -> Vec<CategoryCount> {
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for part in parts {
+        *counts
+            .entry((part.category.as_str(), part.subcategory.as_str()))
+            .or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|((category, subcategory), count)| CategoryCount {
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+            count,
+        })
+        .collect()
+}
+
+/// Print categories grouped under their top-level name.
+fn print_categories(counts: &[CategoryCount], query: &str, scanned: usize) {
+    if counts.is_empty() {
+        println!("{} No results for '{}'", "✗".red(), query);
+        return;
+    }
+
+    println!("Categories in {} results for '{}':", scanned, query.cyan());
+    let mut current = None;
+    for entry in counts {
+        if current != Some(entry.category.as_str()) {
+            println!("\n{}", entry.category.bold());
+            current = Some(entry.category.as_str());
+        }
+        let subcategory = match entry.subcategory.as_str() {
+            "" => "—",
+            s => s,
+        };
+        println!("  {} ({})", subcategory, entry.count);
+    }
+}
+
+/// Drop the last (most specific) term of a multi-word query.
+fn broaden_query(query: &str) -> Option<String> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    (terms.len() > 1).then(|| terms[..terms.len() - 1].join(" "))
+}
+
+/// Fetch the requested page, applying client-side filters.
+///
+/// With a filter (or `--exact`) active, API pages are scanned from the start until the
+/// requested page of filtered results is full (or [`MAX_FILTER_PAGES`] is
+/// reached), so pages stay `limit` long and totals count filtered matches.
+fn fetch_page(client: &JlcpcbClient, query: &str, options: &SearchOptions) -> Result<ResultsPage> {
+    let SearchOptions {
+        library_type,
+        limit,
+        page,
+        ref filter,
+        exact,
+        ..
+    } = *options;
+    let exact = exact.then(|| ExactQuery::parse(query));
+
+    if !filter.is_active() && exact.is_none() {
+        let result = client.search_page(query, page, limit as i32, library_type)?;
+        return Ok(ResultsPage {
+            parts: result.parts,
+            total: result.total,
+            scanned: None,
+        });
+    }
+
+    let wanted = page.max(1) as usize * limit;
+    let mut matches: Vec<JlcPart> = Vec::new();
+    let mut scanned = 0i64;
+
+    for api_page in 1..=MAX_FILTER_PAGES {
+        let result = client.search_page(query, api_page, FILTER_PAGE_SIZE, library_type)?;
+        scanned += result.parts.len() as i64;
+        let exhausted =
+            result.parts.len() < FILTER_PAGE_SIZE as usize || scanned >= result.total;
+        matches.extend(result.parts.into_iter().filter(|p| {
+            filter.matches(p) && exact.as_ref().is_none_or(|e| e.matches(p))
+        }));
+        if matches.len() >= wanted || exhausted {
+            break;
+        }
+    }
+
+    Ok(ResultsPage {
+        total: matches.len() as i64,
+        parts: filtered_page(matches, page, limit),
+        scanned: Some(scanned),
+    })
+}
+
+/// Run each comma-separated keyword as its own search and merge the results.
+///
+/// Every keyword contributes up to the first `page * limit` results, so the
+/// requested page of the merged set is complete.
+fn fetch_any(client: &JlcpcbClient, query: &str, options: &SearchOptions) -> Result<ResultsPage> {
+    let per_keyword = SearchOptions {
+        page: 1,
+        limit: options.page.max(1) as usize * options.limit,
+        ..options.clone()
+    };
+
+    let mut result_sets = Vec::new();
+    for keyword in query.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        result_sets.push(fetch_page(client, keyword, &per_keyword)?.parts);
+    }
+
+    let merged = merge_results(result_sets);
+    Ok(ResultsPage {
+        total: merged.len() as i64,
+        parts: merged
+            .into_iter()
+            .skip((options.page.max(1) as usize - 1) * options.limit)
+            .take(options.limit)
+            .collect(),
+        scanned: None,
+    })
+}
+
+/// Merge result sets, dropping repeated LCSC codes.
+///
+/// Sorted Basic first, then Preferred, then by stock (highest first).
+fn merge_results(result_sets: Vec<Vec<JlcPart>>) -> Vec<JlcPart> {
+    let mut seen = std::collections::HashSet::new();
+    let mut merged: Vec<JlcPart> = result_sets
+        .into_iter()
+        .flatten()
+        .filter(|p| seen.insert(p.lcsc.clone()))
+        .collect();
+    merged.sort_by_key(|p| (!p.basic, !p.preferred, std::cmp::Reverse(p.stock)));
+    merged
+}
+
+/// Order filtered matches (unpriced parts last) and cut out one page.
+fn filtered_page(mut matches: Vec<JlcPart>, page: i32, limit: usize) -> Vec<JlcPart> {
+    matches.sort_by_key(|p| p.price_at_qty(PRICE_QTY).is_none());
+    matches
+        .into_iter()
+        .skip((page.max(1) as usize - 1) * limit)
+        .take(limit)
+        .collect()
+}
+
+/// Execute the search command in compare mode.
+///
+/// Looks up each LCSC code directly and prints the parts side by side.
+pub fn execute_compare(codes: &[String], format: OutputFormat, json_compact: bool) -> Result<()> {
+    let parts = compare_parts(&JlcpcbClient::new(), codes)?;
+
+    let refs: Vec<&JlcPart> = parts.iter().collect();
     match format {
-        OutputFormat::Human => print_human(&refs, query, page, result.total, limit),
-        OutputFormat::Json => print_json(&refs)?,
+        OutputFormat::Human => print_compare(&refs),
+        OutputFormat::Json => print_json(&refs, json_compact)?,
     }
 
     Ok(())
 }
 
-fn print_human(results: &[&JlcPart], query: &str, page: i32, total: i64, page_size: usize) {
+/// Look up each code the way `generate` does, in order.
+///
+/// A part that can't be resolved is reported and left out.
+fn compare_parts(source: &dyn PartSource, codes: &[String]) -> Result<Vec<JlcPart>> {
+    let options = GenerateOptions::default();
+    let mut parts = Vec::new();
+
+    for code in codes {
+        let lcsc = normalize_lcsc(code)
+            .ok_or_else(|| anyhow::anyhow!("Invalid LCSC part number: {}", code))?;
+
+        match resolve_part(source, &lcsc, &options) {
+            Ok(part) => parts.push(part),
+            Err(e) => eprintln!("{} {:#}", "✗".red().bold(), e),
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Print parts as a transposed table: one row per attribute, one column per part.
+fn print_compare(parts: &[&JlcPart]) {
+    if parts.is_empty() {
+        println!("{} No parts to compare", "✗".red().bold());
+        return;
+    }
+
+    let mut builder = Builder::default();
+    for row in compare_rows(parts) {
+        builder.push_record(row);
+    }
+
+    let table = builder
+        .build()
+        .with(Style::rounded())
+        .with(Modify::new(tabled::settings::object::Columns::first()).with(Alignment::left()))
+        .to_string();
+
+    println!("{}", table);
+}
+
+/// Cell formatter for one row of the comparison table.
+type CompareField = fn(&JlcPart) -> String;
+
+/// Build the rows of the comparison table, including the LCSC header row.
+fn compare_rows(parts: &[&JlcPart]) -> Vec<Vec<String>> {
+    let fields: [(&str, CompareField); 11] = [
+        ("MPN", |p| truncate(&p.mpn, 24)),
+        ("Manufacturer", |p| truncate(&p.manufacturer, 24)),
+        ("Package", |p| p.package.clone()),
+        ("Value", extract_display_value),
+        ("Tolerance", |p| or_dash(&p.attributes.tolerance)),
+        ("Voltage", |p| or_dash(&p.attributes.voltage)),
+        ("Power", |p| or_dash(&p.attributes.power)),
+        ("Stock", |p| format_stock(p.stock)),
+        ("Min Order", min_order_cell),
+        ("Price@100", |p| {
+            p.price_at_qty(100)
+                .map(|price| format!("${:.4}", price))
+                .unwrap_or_else(|| "—".to_string())
+        }),
+        ("Library", |p| {
+            if p.basic {
+                "Basic".to_string()
+            } else if p.preferred {
+                "Preferred".to_string()
+            } else {
+                "Extended".to_string()
+            }
+        }),
+    ];
+
+    let mut rows = Vec::with_capacity(fields.len() + 2);
+    rows.push(
+        std::iter::once("LCSC".to_string())
+            .chain(parts.iter().map(|p| p.lcsc.clone()))
+            .collect(),
+    );
+    for (label, field) in fields {
+        rows.push(
+            std::iter::once(label.to_string())
+                .chain(parts.iter().map(|p| field(p)))
+                .collect(),
+        );
+    }
+
+    // Only show dielectric when at least one part has it (capacitors)
+    if parts.iter().any(|p| p.attributes.dielectric.is_some()) {
+        rows.push(
+            std::iter::once("Dielectric".to_string())
+                .chain(parts.iter().map(|p| or_dash(&p.attributes.dielectric)))
+                .collect(),
+        );
+    }
+
+    rows
+}
+
+/// Display an optional attribute, using a dash when missing.
+fn or_dash(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "—".to_string())
+}
+
+fn print_human(results: &[&JlcPart], query: &str, options: &SearchOptions, summary: &ResultsPage) {
     if results.is_empty() {
         println!(
             "{} No results found for '{}'",
@@ -66,45 +704,22 @@ fn print_human(results: &[&JlcPart], query: &str, page: i32, total: i64, page_si
         return;
     }
 
-    // Build table rows
-    let rows: Vec<PartRow> = results
-        .iter()
-        .map(|part| {
-            let indicator = if part.basic {
-                "■".green().to_string()
-            } else if part.preferred {
-                "■".yellow().to_string()
-            } else {
-                " ".to_string()
-            };
-
-            PartRow {
-                indicator,
-                lcsc: part.lcsc.clone(),
-                mpn: truncate(&part.mpn, 24),
-                package: part.package.clone(),
-                value: extract_display_value(part),
-                stock: format_stock(part.stock),
-                price: part
-                    .price_at_qty(100)
-                    .map(|p| format!("${:.4}", p))
-                    .unwrap_or_else(|| "—".to_string()),
-            }
-        })
-        .collect();
-
-    let table = Table::new(rows)
-        .with(Style::rounded())
-        .with(Modify::new(tabled::settings::object::Columns::new(4..=5)).with(Alignment::right()))
-        .to_string();
-
+    let table = if options.wide {
+        wide_table(results, options)
+    } else {
+        narrow_table(results, options)
+    };
     println!("{}", table);
 
     // Footer with pagination and legend
-    let total_pages = (total as usize + page_size - 1) / page_size;
+    let total_pages = (summary.total as usize).div_ceil(options.limit);
+    let total = match summary.scanned {
+        Some(scanned) => format!("{} matching, {} scanned", summary.total, scanned),
+        None => format!("{} total", summary.total),
+    };
     println!(
-        "Page {}/{} ({} total)  {} Basic  {} Preferred",
-        page,
+        "Page {}/{} ({})  {} Basic  {} Preferred",
+        options.page,
         total_pages,
         total,
         "■".green(),
@@ -112,12 +727,208 @@ fn print_human(results: &[&JlcPart], query: &str, page: i32, total: i64, page_si
     );
 }
 
-fn print_json(results: &[&JlcPart]) -> Result<()> {
-    let json = serde_json::to_string_pretty(results)?;
+fn print_json(results: &[&JlcPart], compact: bool) -> Result<()> {
+    let json = super::to_json(results, compact)?;
     println!("{}", json);
     Ok(())
 }
 
+/// Write a result set for a later `generate --from-file`.
+fn save_results(path: &Path, parts: &[JlcPart]) -> Result<()> {
+    let json = serde_json::to_string_pretty(parts)?;
+    std::fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    eprintln!(
+        "{} Saved {} results to {}",
+        "✓".green(),
+        parts.len(),
+        path.display().to_string().cyan()
+    );
+    Ok(())
+}
+
+/// A past search, as listed by `search --history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct HistoryEntry {
+    query: String,
+    /// Total matching parts
+    results: i64,
+    searched_at: DateTime<Utc>,
+}
+
+fn history_path() -> PathBuf {
+    config::cache_dir().join(HISTORY_FILE)
+}
+
+/// Load the search history, oldest first. A missing or unreadable file is
+/// an empty history.
+fn load_history(path: &Path) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Append a search to the history, replacing the last entry when it was
+/// the same query and dropping the oldest beyond [`HISTORY_LIMIT`].
+fn push_history(history: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    if history.last().is_some_and(|last| last.query == entry.query) {
+        history.pop();
+    }
+    history.push(entry);
+    let excess = history.len().saturating_sub(HISTORY_LIMIT);
+    history.drain(..excess);
+}
+
+/// Record a search in the history file. Failures are ignored; the history
+/// is only a convenience.
+fn record_search(query: &str, results: i64) {
+    let path = history_path();
+    let mut history = load_history(&path);
+    push_history(
+        &mut history,
+        HistoryEntry {
+            query: query.to_string(),
+            results,
+            searched_at: Utc::now(),
+        },
+    );
+    if let Ok(json) = serde_json::to_string_pretty(&history) {
+        let _ = std::fs::create_dir_all(config::cache_dir());
+        let _ = atomic::write_file(&path, json + "\n");
+    }
+}
+
+/// Execute `search --history`: list recent searches, newest first.
+pub fn execute_history(format: OutputFormat, json_compact: bool) -> Result<()> {
+    let mut history = load_history(&history_path());
+    history.reverse();
+
+    if let OutputFormat::Json = format {
+        println!("{}", super::to_json(&history, json_compact)?);
+        return Ok(());
+    }
+    if history.is_empty() {
+        println!("No searches recorded yet");
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["#", "Query", "Results", "Searched"]);
+    for (i, entry) in history.iter().enumerate() {
+        builder.push_record([
+            (i + 1).to_string(),
+            entry.query.clone(),
+            entry.results.to_string(),
+            entry
+                .searched_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+        ]);
+    }
+    let mut table = builder.build();
+    table
+        .with(Style::rounded())
+        .with(Modify::new(tabled::settings::object::Columns::first()).with(Alignment::right()))
+        .with(Modify::new(tabled::settings::object::Columns::single(2)).with(Alignment::right()));
+    println!("{}", table);
+    Ok(())
+}
+
+/// Default search table.
+fn narrow_table(results: &[&JlcPart], options: &SearchOptions) -> String {
+    let rows: Vec<PartRow> = results
+        .iter()
+        .map(|part| PartRow {
+            indicator: library_indicator(part),
+            lcsc: part.lcsc.clone(),
+            mpn: truncate(&part.mpn, 24),
+            package: part.package.clone(),
+            value: extract_display_value(part),
+            stock: stock_cell(part, options),
+            price: price_cell(part, options),
+        })
+        .collect();
+
+    Table::new(rows)
+        .with(Style::rounded())
+        .with(Modify::new(tabled::settings::object::Columns::new(4..=5)).with(Alignment::right()))
+        .with(Modify::new(Cell::new(0, 6)).with(price_header(options)))
+        .to_string()
+}
+
+/// `--wide` search table; the Dielectric column only appears when some part has one.
+fn wide_table(results: &[&JlcPart], options: &SearchOptions) -> String {
+    let rows: Vec<WidePartRow> = results
+        .iter()
+        .map(|part| WidePartRow {
+            indicator: library_indicator(part),
+            lcsc: part.lcsc.clone(),
+            mpn: truncate(&part.mpn, 24),
+            manufacturer: truncate(&part.manufacturer, 20),
+            category: truncate(&part.category, 20),
+            subcategory: truncate(&part.subcategory, 24),
+            package: part.package.clone(),
+            value: extract_display_value(part),
+            tolerance: or_dash(&part.attributes.tolerance),
+            voltage: or_dash(&part.attributes.voltage),
+            dielectric: or_dash(&part.attributes.dielectric),
+            stock: stock_cell(part, options),
+            price: price_cell(part, options),
+            min_order: min_order_cell(part),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table
+        .with(Style::rounded())
+        .with(Modify::new(tabled::settings::object::Columns::new(11..=13)).with(Alignment::right()))
+        .with(Modify::new(Cell::new(0, 12)).with(price_header(options)));
+    if results.iter().all(|p| p.attributes.dielectric.is_none()) {
+        table.with(Remove::column(tabled::settings::object::Columns::single(10)));
+    }
+    table.to_string()
+}
+
+/// Minimum order quantity, or a dash when JLCPCB doesn't set one.
+fn min_order_cell(part: &JlcPart) -> String {
+    part.min_order
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "—".to_string())
+}
+
+/// Basic/Preferred marker for the first table column.
+fn library_indicator(part: &JlcPart) -> String {
+    if part.basic {
+        "■".green().to_string()
+    } else if part.preferred {
+        "■".yellow().to_string()
+    } else {
+        " ".to_string()
+    }
+}
+
+/// Stock cell, highlighted when it's close to `--stock-min`.
+fn stock_cell(part: &JlcPart, options: &SearchOptions) -> String {
+    if options.filter.stock_is_tight(part.stock) {
+        format_stock(part.stock).yellow().to_string()
+    } else {
+        format_stock(part.stock)
+    }
+}
+
+fn price_cell(part: &JlcPart, options: &SearchOptions) -> String {
+    part.price_at_qty(options.filter.price_qty())
+        .map(|p| format!("${:.4}", p))
+        .unwrap_or_else(|| "—".to_string())
+}
+
+/// Price column header, labelled with the display quantity.
+fn price_header(options: &SearchOptions) -> String {
+    format!("Price@{}", options.filter.price_qty())
+}
+
 /// Extract a display value from a part (resistance, capacitance, etc.).
 fn extract_display_value(part: &JlcPart) -> String {
     if let Some(ref r) = part.attributes.resistance {
@@ -170,3 +981,274 @@ fn truncate(s: &str, max_len: usize) -> String {
         format!("{}…", &s[..max_len - 1])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::PriceBreak;
+
+    #[test]
+    fn test_push_history() {
+        let entry = |query: &str, results: i64| HistoryEntry {
+            query: query.to_string(),
+            results,
+            searched_at: Utc::now(),
+        };
+        let mut history = Vec::new();
+        push_history(&mut history, entry("10k 0402", 120));
+        push_history(&mut history, entry("100nF", 80));
+        // A repeated query replaces its entry with the latest count
+        push_history(&mut history, entry("100nF", 81));
+        push_history(&mut history, entry("10k 0402", 119));
+        let queries: Vec<(&str, i64)> =
+            history.iter().map(|e| (e.query.as_str(), e.results)).collect();
+        assert_eq!(queries, [("10k 0402", 120), ("100nF", 81), ("10k 0402", 119)]);
+
+        for i in 0..HISTORY_LIMIT {
+            push_history(&mut history, entry(&format!("q{}", i), 1));
+        }
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history[0].query, "q0");
+        assert_eq!(history.last().unwrap().query, format!("q{}", HISTORY_LIMIT - 1));
+    }
+
+    #[test]
+    fn test_compare_parts() {
+        let mut resistor = JlcPart {
+            lcsc: "C25744".to_string(),
+            mpn: "0402WGF1002TCE".to_string(),
+            ..Default::default()
+        };
+        resistor.attributes.tolerance = Some("±1%".to_string());
+        let other = JlcPart {
+            lcsc: "C1525".to_string(),
+            ..Default::default()
+        };
+        let source = crate::api::SnapshotSource::from_parts([resistor, other]);
+
+        let codes = ["c25744", "C999", "1525"].map(String::from);
+        let parts = compare_parts(&source, &codes).unwrap();
+        let refs: Vec<&JlcPart> = parts.iter().collect();
+        let rows = compare_rows(&refs);
+        assert_eq!(rows[0], ["LCSC", "C25744", "C1525"]);
+        assert_eq!(rows[1], ["MPN", "0402WGF1002TCE", ""]);
+        let tolerance = rows.iter().find(|r| r[0] == "Tolerance").unwrap();
+        assert_eq!(tolerance[1..], ["±1%", "—"]);
+
+        assert!(compare_parts(&source, &["bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_validate_query() {
+        assert_eq!(validate_query("  C25744 ", false).unwrap(), "C25744");
+        assert!(validate_query("", false).is_err());
+        assert!(validate_query("   \t", false).is_err());
+        let err = validate_query("R", false).unwrap_err();
+        assert!(err.to_string().contains("too short"), "{}", err);
+
+        assert_eq!(validate_query("NE555, LM358", true).unwrap(), "NE555, LM358");
+        assert!(validate_query("NE555,,LM358", true).is_err());
+        assert!(validate_query("NE555,", true).is_err());
+    }
+
+    #[test]
+    fn test_copy_result_index() {
+        let parts = [priced("C1", None)];
+        assert!(copy_result(&parts, 0).is_err());
+        assert!(copy_result(&parts, 2).is_err());
+        let err = copy_result(&[], 1).unwrap_err();
+        assert!(err.to_string().contains("0 result(s)"), "{}", err);
+    }
+
+    fn priced(lcsc: &str, price: Option<f64>) -> JlcPart {
+        JlcPart {
+            lcsc: lcsc.to_string(),
+            price_breaks: price
+                .map(|price| vec![PriceBreak { qty: 1, price }])
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_price_filter() {
+        let filter = PartFilter {
+            min_price: Some(0.01),
+            max_price: Some(0.10),
+            ..Default::default()
+        };
+        assert!(filter.matches(&priced("C1", Some(0.05))));
+        assert!(filter.matches(&priced("C2", Some(0.10))));
+        assert!(!filter.matches(&priced("C3", Some(0.5))));
+        assert!(!filter.matches(&priced("C4", Some(0.001))));
+        assert!(filter.matches(&priced("C5", None)));
+
+        let strict = PartFilter {
+            require_price: true,
+            ..filter
+        };
+        assert!(!strict.matches(&priced("C5", None)));
+        assert!(!PartFilter::default().is_active());
+    }
+
+    #[test]
+    fn test_attribute_filters() {
+        let part = |package: &str, description: &str, tolerance: Option<&str>| {
+            let mut part = JlcPart {
+                package: package.to_string(),
+                description: description.to_string(),
+                ..Default::default()
+            };
+            part.attributes.tolerance = tolerance.map(String::from);
+            part
+        };
+        let x7r = part("0402", "100nF ±10% 16V X7R 0402 Multilayer Ceramic Capacitors", None);
+        let np0 = part("0402", "100pF ±5% 50V NP0 0402 Multilayer Ceramic Capacitors", None);
+        let precise = part("0603", "10kΩ 62.5mW Thick Film Resistors", Some("±0.5%"));
+
+        let dielectric = |d: &str| PartFilter {
+            dielectric: Some(d.to_string()),
+            ..Default::default()
+        };
+        assert!(dielectric("x7r").matches(&x7r));
+        assert!(!dielectric("X5R").matches(&x7r));
+        assert!(dielectric("C0G").matches(&np0));
+        assert!(!dielectric("X7R").matches(&precise));
+
+        // Tolerance is a maximum: 1% accepts 0.5% but not 10%
+        let tolerance = |t: &str| PartFilter {
+            tolerance: Some(parse_tolerance(t).unwrap()),
+            ..Default::default()
+        };
+        assert!(tolerance("1%").matches(&precise));
+        assert!(!tolerance("1%").matches(&x7r));
+        assert!(tolerance("±10%").matches(&x7r));
+        assert!(parse_tolerance("tight").is_err());
+
+        let combined = PartFilter {
+            package: Some("0402".to_string()),
+            ..tolerance("5")
+        };
+        assert!(combined.is_active());
+        assert!(combined.matches(&np0));
+        assert!(!combined.matches(&x7r));
+        assert!(!combined.matches(&precise));
+    }
+
+    #[test]
+    fn test_category_counts() {
+        let part = |category: &str, subcategory: &str| JlcPart {
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+            ..Default::default()
+        };
+        let parts = [
+            part("Resistors", "Chip Resistor - Surface Mount"),
+            part("Capacitors", "Multilayer Ceramic Capacitors MLCC - SMD/SMT"),
+            part("Resistors", "Chip Resistor - Surface Mount"),
+            part("Resistors", "Current Sense Resistors"),
+        ];
+
+        let counts = category_counts(&parts);
+        let rows: Vec<(&str, &str, usize)> = counts
+            .iter()
+            .map(|c| (c.category.as_str(), c.subcategory.as_str(), c.count))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("Capacitors", "Multilayer Ceramic Capacitors MLCC - SMD/SMT", 1),
+                ("Resistors", "Chip Resistor - Surface Mount", 2),
+                ("Resistors", "Current Sense Resistors", 1),
+            ]
+        );
+        assert!(category_counts(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_broaden_query() {
+        assert_eq!(broaden_query("100nF 0402 X7R 50V").as_deref(), Some("100nF 0402 X7R"));
+        assert_eq!(broaden_query(" 10k  0603 ").as_deref(), Some("10k"));
+        assert_eq!(broaden_query("STM32F103C8T6"), None);
+    }
+
+    #[test]
+    fn test_merge_results_dedups() {
+        let part = |lcsc: &str, stock, basic| JlcPart {
+            lcsc: lcsc.to_string(),
+            stock,
+            basic,
+            ..Default::default()
+        };
+        let merged = merge_results(vec![
+            vec![part("C1", 10, false), part("C2", 500, false)],
+            vec![part("C2", 500, false), part("C3", 5, true)],
+        ]);
+        let codes: Vec<&str> = merged.iter().map(|p| p.lcsc.as_str()).collect();
+        assert_eq!(codes, vec!["C3", "C2", "C1"]);
+    }
+
+    #[test]
+    fn test_subcategory_filter() {
+        let filter = PartFilter {
+            subcategory: Some("chip resistor".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.is_active());
+        let with_subcategory = |subcategory: &str| JlcPart {
+            subcategory: subcategory.to_string(),
+            ..priced("C1", Some(0.01))
+        };
+        assert!(filter.matches(&with_subcategory("Chip Resistor - Surface Mount")));
+        assert!(!filter.matches(&with_subcategory("Through Hole Resistors")));
+        assert!(!filter.matches(&with_subcategory("")));
+    }
+
+    #[test]
+    fn test_stock_min() {
+        let filter = PartFilter {
+            stock_min: Some(1000),
+            ..Default::default()
+        };
+        let stocked = |stock| JlcPart {
+            stock,
+            ..priced("C1", Some(0.01))
+        };
+        assert!(!filter.matches(&stocked(999)));
+        assert!(filter.matches(&stocked(1000)));
+
+        assert!(filter.stock_is_tight(1000));
+        assert!(filter.stock_is_tight(1999));
+        assert!(!filter.stock_is_tight(2000));
+        assert!(!PartFilter::default().stock_is_tight(0));
+    }
+
+    #[test]
+    fn test_exact_query() {
+        let part = JlcPart {
+            lcsc: "C25744".to_string(),
+            mpn: "0402WGF1002TCE".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(ExactQuery::parse(" c25744 "), ExactQuery::Lcsc("C25744".to_string()));
+        assert!(ExactQuery::parse("C25744").matches(&part));
+        assert!(!ExactQuery::parse("C2574").matches(&part));
+        assert!(ExactQuery::parse("0402wgf1002tce").matches(&part));
+        assert!(!ExactQuery::parse("0402WGF1002").matches(&part));
+    }
+
+    #[test]
+    fn test_filtered_page_puts_unpriced_last() {
+        let matches = vec![
+            priced("C1", None),
+            priced("C2", Some(0.02)),
+            priced("C3", Some(0.01)),
+        ];
+        let page: Vec<String> = filtered_page(matches, 1, 2)
+            .into_iter()
+            .map(|p| p.lcsc)
+            .collect();
+        assert_eq!(page, ["C2", "C3"]);
+    }
+}
@@ -1,9 +1,24 @@
 //! Utility commands (cache management, etc.).
 
-use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use super::generate::{self, GenerateOptions};
 use crate::api::cache::PartCache;
-use crate::pins::cache::PinCache;
+use crate::api::{normalize_lcsc, JlcPart, JlcpcbClient};
+use crate::atomic;
+use crate::config;
+use crate::easyeda::{ComponentData, ComponentMeta, EasyEdaClient};
+use crate::generator::sanitize_mpn;
+use crate::pins::cache::{CachedPins, PinCache};
+use crate::pins::{pins_from_meta, ExtractionResult};
+use crate::sexpr::{self, Sexpr};
 
 /// Clear cached API data.
 ///
@@ -37,3 +52,685 @@ pub fn execute_clean_cache(parts: bool, pins: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// A cache that can be exported and imported, as its directory name under
+/// the cache root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheKind {
+    Parts,
+    Pins,
+}
+
+impl CacheKind {
+    const ALL: [CacheKind; 2] = [CacheKind::Parts, CacheKind::Pins];
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            CacheKind::Parts => "parts",
+            CacheKind::Pins => "pins",
+        }
+    }
+
+    /// The local cache directory.
+    fn local_dir(self) -> PathBuf {
+        match self {
+            CacheKind::Parts => PartCache::new().dir().to_path_buf(),
+            CacheKind::Pins => PinCache::new().cache_dir().clone(),
+        }
+    }
+
+    /// The LCSC code a cache entry is for and when it was fetched:
+    /// `extracted_at` for pins, the file mtime for parts (the part cache TTL
+    /// uses it too). `None` when the file doesn't hold a valid entry.
+    fn entry(self, path: &Path) -> Option<(String, SystemTime)> {
+        let content = fs::read_to_string(path).ok()?;
+        match self {
+            CacheKind::Parts => {
+                let part: JlcPart = serde_json::from_str(&content).ok()?;
+                Some((part.lcsc, fs::metadata(path).ok()?.modified().ok()?))
+            }
+            CacheKind::Pins => {
+                let cached: CachedPins = serde_json::from_str(&content).ok()?;
+                Some((cached.lcsc, cached.extracted_at.into()))
+            }
+        }
+    }
+}
+
+/// Outcome of merging one cache directory into another.
+#[derive(Debug, Default, PartialEq)]
+struct CacheMerge {
+    /// Entries copied over
+    copied: usize,
+    /// Entries skipped because the destination's copy is as new or newer
+    kept: usize,
+    /// Files skipped because they aren't valid cache entries
+    invalid: usize,
+}
+
+/// Copy cache entries from `from` into `to`, never replacing a destination
+/// entry that is as new or newer.
+///
+/// Copies keep the source mtime, so a part's cache age (and TTL) survives the
+/// round trip. Lookups find entries by file name, so a file whose name
+/// isn't `<lcsc>.json` for the LCSC code inside is an error, and nothing is
+/// copied.
+fn merge_cache_dir(kind: CacheKind, from: &Path, to: &Path) -> Result<CacheMerge> {
+    let mut merge = CacheMerge::default();
+    if !from.is_dir() {
+        return Ok(merge);
+    }
+    fs::create_dir_all(to)
+        .with_context(|| format!("Failed to create directory: {}", to.display()))?;
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(from)
+        .with_context(|| format!("Failed to read directory: {}", from.display()))?
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    paths.sort();
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let Some((lcsc, time)) = kind.entry(&path) else {
+            merge.invalid += 1;
+            continue;
+        };
+        if path.file_stem().and_then(|s| s.to_str()) != Some(lcsc.as_str()) {
+            anyhow::bail!(
+                "{} holds the {} entry for {:?}; cache files must be named <lcsc>.json",
+                path.display(),
+                kind.dir_name(),
+                lcsc
+            );
+        }
+        entries.push((path, time));
+    }
+
+    for (path, time) in entries {
+        let dest = to.join(path.file_name().unwrap_or_default());
+        if kind.entry(&dest).is_some_and(|(_, existing)| existing >= time) {
+            merge.kept += 1;
+            continue;
+        }
+
+        let content = fs::read(&path)
+            .with_context(|| format!("Failed to read cache file: {}", path.display()))?;
+        atomic::write_file(&dest, content)
+            .with_context(|| format!("Failed to write cache file: {}", dest.display()))?;
+        if let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) {
+            fs::File::options()
+                .write(true)
+                .open(&dest)
+                .and_then(|f| f.set_modified(modified))
+                .with_context(|| format!("Failed to set mtime: {}", dest.display()))?;
+        }
+        merge.copied += 1;
+    }
+    Ok(merge)
+}
+
+/// Merge every cache kind between the local cache and a shared directory.
+fn merge_caches(export: bool, dir: &Path) -> Result<()> {
+    let verb = if export { "Exported" } else { "Imported" };
+    for kind in CacheKind::ALL {
+        let (local, shared) = (kind.local_dir(), dir.join(kind.dir_name()));
+        let (from, to) = if export { (&local, &shared) } else { (&shared, &local) };
+        let merge = merge_cache_dir(kind, from, to)?;
+
+        println!(
+            "{} {} {} {} ({} newer or unchanged kept)",
+            "✓".green(),
+            verb,
+            merge.copied,
+            kind.dir_name(),
+            merge.kept
+        );
+        if merge.invalid > 0 {
+            eprintln!(
+                "{} Skipped {} invalid {} files in {}",
+                "!".yellow().bold(),
+                merge.invalid,
+                kind.dir_name(),
+                from.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Copy the part and pin caches into `dir` (as `parts/` and `pins/`) for
+/// sharing, e.g. committed to a repository for CI.
+pub fn execute_export_cache(dir: &Path) -> Result<()> {
+    merge_caches(true, dir)
+}
+
+/// Merge a directory written by `util export-cache` into the local caches.
+pub fn execute_import_cache(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        anyhow::bail!("Cache directory not found: {}", dir.display());
+    }
+    merge_caches(false, dir)
+}
+
+/// Part looked up by `util doctor` (a common basic 10k resistor).
+const DOCTOR_PROBE_LCSC: &str = "C25744";
+
+/// One `util doctor` check.
+#[derive(Debug, Serialize)]
+struct DoctorCheck {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, result: Result<String>) -> Self {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, format!("{:#}", e)),
+        };
+        Self { name, ok, detail }
+    }
+}
+
+/// Check the environment: the `pcb` CLI, both APIs and the cache directories.
+pub fn execute_doctor(json: bool, json_compact: bool) -> Result<()> {
+    let cache_dir = config::cache_dir();
+    let checks = [
+        DoctorCheck::new("pcb CLI", check_pcb()),
+        DoctorCheck::new("JLCPCB search API", check_jlcpcb()),
+        DoctorCheck::new("EasyEDA API", check_easyeda()),
+        DoctorCheck::new("Part cache", check_writable(&cache_dir.join("parts"))),
+        DoctorCheck::new("Pin cache", check_writable(&cache_dir.join("pins"))),
+    ];
+    let failed = checks.iter().filter(|c| !c.ok).count();
+
+    if json {
+        println!("{}", super::to_json(&checks, json_compact)?);
+    } else {
+        for check in &checks {
+            let mark = if check.ok {
+                "✓".green().bold()
+            } else {
+                "✗".red().bold()
+            };
+            println!("{} {}: {}", mark, check.name.bold(), check.detail);
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, checks.len());
+    }
+    Ok(())
+}
+
+/// The `pcb` binary (or `PCB_BIN`) runs, with its version.
+fn check_pcb() -> Result<String> {
+    let bin = super::bom::pcb_bin();
+    let output = std::process::Command::new(&bin)
+        .arg("--version")
+        .output()
+        .with_context(|| {
+            format!("`{}` not found; install it or set PCB_BIN (needed for .zen BOMs)", bin)
+        })?;
+    if !output.status.success() {
+        anyhow::bail!("`{} --version` exited with {}", bin, output.status);
+    }
+    let version = String::from_utf8_lossy(&output.stdout);
+    Ok(format!("{} ({})", version.lines().next().unwrap_or_default().trim(), bin))
+}
+
+/// A one-result search against the JLCPCB API.
+fn check_jlcpcb() -> Result<String> {
+    let client = JlcpcbClient::new().with_cache(false).with_cache_writes(false);
+    let parts = client.search(DOCTOR_PROBE_LCSC, 1, 1).context(
+        "Search failed; check the network, or the API may have changed (e.g. an expired secret key)",
+    )?;
+    if parts.is_empty() {
+        anyhow::bail!("Reachable, but searching {} returned nothing", DOCTOR_PROBE_LCSC);
+    }
+    Ok(format!("reachable (found {})", parts[0].lcsc))
+}
+
+/// A component lookup against the EasyEDA API.
+fn check_easyeda() -> Result<String> {
+    let component = EasyEdaClient::new()?
+        .get_component(DOCTOR_PROBE_LCSC)
+        .context("Lookup failed; check the network")?;
+    Ok(match component {
+        Some(_) => format!("reachable (found {})", DOCTOR_PROBE_LCSC),
+        None => format!("reachable, but {} has no EasyEDA entry", DOCTOR_PROBE_LCSC),
+    })
+}
+
+/// `dir` exists (or can be created) and accepts new files.
+fn check_writable(dir: &Path) -> Result<String> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Can't create {}", dir.display()))?;
+    let probe = dir.join(".doctor-probe");
+    std::fs::write(&probe, b"")
+        .with_context(|| format!("{} is not writable", dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(format!("{} is writable", dir.display()))
+}
+
+/// Generate a component from a saved EasyEDA component JSON, offline.
+///
+/// The file holds the `result` of the EasyEDA components API, bare or still
+/// wrapped in the response. The LCSC code comes from `lcsc`, else from the
+/// dump itself. Output defaults to `components/JLCPCB/<mpn>/`.
+pub fn execute_import_easyeda(
+    path: &Path,
+    lcsc: Option<String>,
+    output_dir: Option<PathBuf>,
+    name: Option<String>,
+    options: &GenerateOptions,
+) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let data = ComponentData::from_json(&content)
+        .with_context(|| format!("Failed to parse EasyEDA component: {}", path.display()))?;
+
+    let lcsc = match lcsc.or_else(|| data.lcsc.as_ref().map(|l| l.number.clone())) {
+        Some(code) => normalize_lcsc(&code)
+            .ok_or_else(|| anyhow::anyhow!("Invalid LCSC part number: {}", code))?,
+        None => anyhow::bail!(
+            "{} doesn't say which LCSC part it is; pass --lcsc",
+            path.display()
+        ),
+    };
+
+    let meta = ComponentMeta::from_component_data(&data);
+    let (source, pins) = pins_from_meta(&meta, false).with_context(|| {
+        format!(
+            "No pin information found in {}\n\n\
+            The dump has neither symbol pins nor footprint pads.",
+            path.display()
+        )
+    })?;
+    eprintln!(
+        "  {} Extracted {} pins from {}",
+        "✓".green(),
+        pins.len(),
+        source
+    );
+
+    let part = JlcPart {
+        lcsc,
+        mpn: data.title.clone(),
+        package: meta.package_token().unwrap_or_default(),
+        ..Default::default()
+    };
+    let output_dir = output_dir.unwrap_or_else(|| {
+        PathBuf::from("components")
+            .join("JLCPCB")
+            .join(sanitize_mpn(&part.mpn))
+    });
+    let name = name.unwrap_or_else(|| sanitize_mpn(&part.mpn));
+
+    let generator = options.zen_generator()?;
+    let extraction = ExtractionResult {
+        pins,
+        meta,
+        stub: false,
+        pin_source: Some(source),
+        warnings: Vec::new(),
+    };
+    let report =
+        generate::generate_imported(&generator, part, &extraction, &output_dir, &name, options)?;
+
+    if options.json {
+        println!("{}", super::to_json(&report, options.json_compact)?);
+        return Ok(());
+    }
+
+    for path in report.files_written() {
+        println!(
+            "{} Created {}",
+            "✓".green().bold(),
+            path.display().to_string().cyan()
+        );
+    }
+    super::print_diagnostics(&report.warnings);
+
+    Ok(())
+}
+
+/// Maximum distance (mm) of the pad bounding-box center from the origin
+/// for a footprint to count as centered.
+const CENTER_TOLERANCE_MM: f64 = 0.05;
+
+/// Result of validating a `.kicad_mod` footprint.
+#[derive(Debug, Serialize)]
+struct FootprintReport {
+    name: String,
+    pad_count: usize,
+    /// Pad numbers used by more than one pad
+    duplicate_pads: Vec<String>,
+    /// Pad numbers of pads with zero width or height
+    zero_size_pads: Vec<String>,
+    has_courtyard: bool,
+    /// Center of the pad bounding box (mm)
+    pad_center: (f64, f64),
+    centered: bool,
+}
+
+impl FootprintReport {
+    /// Problems that make the footprint unusable.
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.pad_count == 0 {
+            errors.push("Footprint has no pads".to_string());
+        }
+        if !self.zero_size_pads.is_empty() {
+            errors.push(format!("Zero-size pads: {}", self.zero_size_pads.join(", ")));
+        }
+        errors
+    }
+
+    /// Suspicious but possibly intentional findings.
+    fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.duplicate_pads.is_empty() {
+            warnings.push(format!(
+                "Duplicate pad numbers: {}",
+                self.duplicate_pads.join(", ")
+            ));
+        }
+        if !self.has_courtyard {
+            warnings.push("No courtyard (F.CrtYd/B.CrtYd)".to_string());
+        }
+        if !self.centered {
+            warnings.push(format!(
+                "Pads are not centered on the origin (center at {:.3}, {:.3})",
+                self.pad_center.0, self.pad_center.1
+            ));
+        }
+        warnings
+    }
+}
+
+/// Check a `.kicad_mod` footprint for common generation problems.
+pub fn execute_validate_footprint(path: &Path, json: bool, json_compact: bool) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let report = validate_footprint(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let errors = report.errors();
+
+    if json {
+        let output = serde_json::json!({
+            "report": report,
+            "errors": errors,
+            "warnings": report.warnings(),
+        });
+        println!("{}", super::to_json(&output, json_compact)?);
+    } else {
+        println!("{} ({} pads)", report.name.bold(), report.pad_count);
+        for error in &errors {
+            println!("  {} {}", "✗".red().bold(), error);
+        }
+        for warning in report.warnings() {
+            println!("  {} {}", "⚠".yellow().bold(), warning);
+        }
+        if errors.is_empty() && report.warnings().is_empty() {
+            println!("  {} No problems found", "✓".green().bold());
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!("{} has {} problem(s)", path.display(), errors.len());
+    }
+    Ok(())
+}
+
+/// Parse footprint content and collect the validation report.
+fn validate_footprint(content: &str) -> Result<FootprintReport> {
+    let root = sexpr::parse(content)?;
+    if !matches!(root.head(), Some("footprint") | Some("module")) {
+        anyhow::bail!("Not a KiCad footprint (expected a top-level `footprint` list)");
+    }
+    let name = root
+        .items()
+        .get(1)
+        .and_then(Sexpr::as_atom)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut pad_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut zero_size_pads = Vec::new();
+    let (mut min_x, mut max_x) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut pad_count = 0;
+
+    for pad in root.children("pad") {
+        pad_count += 1;
+        let number = pad
+            .items()
+            .get(1)
+            .and_then(Sexpr::as_atom)
+            .unwrap_or_default()
+            .to_string();
+
+        let at = pad.child("at").map(Sexpr::numbers).unwrap_or_default();
+        let (x, y) = (at.first().copied().unwrap_or(0.0), at.get(1).copied().unwrap_or(0.0));
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+
+        let size = pad.child("size").map(Sexpr::numbers).unwrap_or_default();
+        if size.len() < 2 || size.iter().any(|v| *v <= 0.0) {
+            zero_size_pads.push(if number.is_empty() {
+                "(unnumbered)".to_string()
+            } else {
+                number.clone()
+            });
+        }
+
+        // Unnumbered pads are mechanical holes and may repeat freely
+        if !number.is_empty() {
+            *pad_counts.entry(number).or_default() += 1;
+        }
+    }
+
+    let has_courtyard = root.items().iter().any(|item| {
+        item.head().is_some_and(|h| h.starts_with("fp_"))
+            && item
+                .child("layer")
+                .and_then(|l| l.items().get(1))
+                .and_then(Sexpr::as_atom)
+                .is_some_and(|layer| layer.ends_with(".CrtYd"))
+    });
+
+    let pad_center = if pad_count > 0 {
+        ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0)
+    } else {
+        (0.0, 0.0)
+    };
+    let centered =
+        pad_center.0.abs() <= CENTER_TOLERANCE_MM && pad_center.1.abs() <= CENTER_TOLERANCE_MM;
+
+    Ok(FootprintReport {
+        name,
+        pad_count,
+        duplicate_pads: pad_counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(number, _)| number)
+            .collect(),
+        zero_size_pads,
+        has_courtyard,
+        pad_center,
+        centered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easyeda::{
+        generate_kicad_mod, parse_footprint_shapes, FootprintOrigin, KicadVersion, RefPlacement,
+    };
+
+    #[test]
+    fn test_validate_generated_footprint() {
+        let shapes = vec![
+            "PAD~RECT~4000~3000~6~6~1~~1~0~~0~pad1".to_string(),
+            "PAD~RECT~4010~3000~6~6~1~~2~0~~0~pad2".to_string(),
+        ];
+        let (pads, lines) = parse_footprint_shapes(&shapes, false);
+        let content = generate_kicad_mod(
+            "TEST",
+            &pads,
+            &lines,
+            None,
+            KicadVersion::default(),
+            RefPlacement::default(),
+            FootprintOrigin::default(),
+        )
+        .unwrap();
+
+        let report = validate_footprint(&content).unwrap();
+        assert_eq!(report.name, "TEST");
+        assert_eq!(report.pad_count, 2);
+        assert!(report.centered);
+        assert!(report.errors().is_empty());
+        assert!(!report.has_courtyard);
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let content = r#"(footprint "BAD"
+            (pad "1" smd rect (at 1 0) (size 1 1) (layers "F.Cu"))
+            (pad "1" smd rect (at 3 0) (size 0 1) (layers "F.Cu"))
+            (pad "" np_thru_hole circle (at 2 1) (size 1 1) (drill 1) (layers "*.Cu"))
+            (pad "" np_thru_hole circle (at 2 -1) (size 1 1) (drill 1) (layers "*.Cu"))
+            (fp_rect (start -1 -1) (end 5 2) (layer "F.CrtYd"))
+        )"#;
+
+        let report = validate_footprint(content).unwrap();
+        assert_eq!(report.pad_count, 4);
+        assert_eq!(report.duplicate_pads, vec!["1"]);
+        assert_eq!(report.zero_size_pads, vec!["1"]);
+        assert!(report.has_courtyard);
+        assert!(!report.centered);
+        assert_eq!(report.errors().len(), 1);
+
+        assert!(validate_footprint("(kicad_symbol_lib)").is_err());
+    }
+
+    #[test]
+    fn test_import_easyeda_dump() {
+        let dir = tempfile::tempdir().unwrap();
+        let dump = dir.path().join("dump.json");
+        std::fs::write(
+            &dump,
+            r#"{"success": true, "result": {
+                "uuid": "abc", "title": "TEST-PART", "lcsc": {"number": "C1234"},
+                "packageDetail": {"uuid": "def", "title": "SOT-23_L2.9-W1.3", "dataStr": {
+                    "shape": ["PAD~RECT~4000~3000~6~6~1~~1~0~~0~pad1",
+                              "PAD~RECT~4010~3000~6~6~1~~2~0~~0~pad2"]}}}}"#,
+        )
+        .unwrap();
+
+        let output = dir.path().join("out");
+        let options = GenerateOptions {
+            json: true,
+            ..Default::default()
+        };
+        execute_import_easyeda(&dump, None, Some(output.clone()), None, &options).unwrap();
+        assert!(output.join("TEST-PART.zen").exists());
+        assert!(output.join("TEST-PART.kicad_mod").exists());
+
+        std::fs::write(&dump, r#"{"uuid": "abc", "title": "X"}"#).unwrap();
+        let err = execute_import_easyeda(&dump, None, None, None, &options).unwrap_err();
+        assert!(err.to_string().contains("--lcsc"));
+    }
+
+    #[test]
+    fn test_merge_cache_roundtrip() {
+        use chrono::{Duration, Utc};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (local, shared) = (dir.path().join("local"), dir.path().join("shared"));
+        let pins = |lcsc: &str, mpn: &str, age_days: i64| CachedPins {
+            lcsc: lcsc.to_string(),
+            mpn: mpn.to_string(),
+            extracted_at: Utc::now() - Duration::days(age_days),
+            pins: Vec::new(),
+            meta: None,
+        };
+        let write = |dir: &Path, cached: &CachedPins| {
+            fs::create_dir_all(dir).unwrap();
+            let content = serde_json::to_string(cached).unwrap();
+            fs::write(dir.join(format!("{}.json", cached.lcsc)), content).unwrap();
+        };
+        let mpn = |dir: &Path, lcsc: &str| {
+            let content = fs::read_to_string(dir.join(format!("{}.json", lcsc))).unwrap();
+            serde_json::from_str::<CachedPins>(&content).unwrap().mpn
+        };
+
+        write(&local, &pins("C1", "OLD", 5));
+        write(&local, &pins("C2", "NEWER-LOCAL", 1));
+        fs::write(local.join("C3.json"), "{").unwrap();
+        write(&shared, &pins("C2", "OLDER-SHARED", 3));
+
+        let merge = merge_cache_dir(CacheKind::Pins, &local, &shared).unwrap();
+        assert_eq!(
+            merge,
+            CacheMerge {
+                copied: 2,
+                kept: 0,
+                invalid: 1
+            }
+        );
+        assert_eq!(mpn(&shared, "C2"), "NEWER-LOCAL");
+
+        // A newer shared entry replaces the local one; the rest are unchanged
+        write(&shared, &pins("C1", "NEW", 0));
+        let merge = merge_cache_dir(CacheKind::Pins, &shared, &local).unwrap();
+        assert_eq!((merge.copied, merge.kept), (1, 1));
+        assert_eq!(mpn(&local, "C1"), "NEW");
+
+        // Exported parts keep their cache age
+        let parts = dir.path().join("parts");
+        fs::create_dir_all(&parts).unwrap();
+        let part = parts.join("C25744.json");
+        let cached = JlcPart {
+            lcsc: "C25744".to_string(),
+            ..Default::default()
+        };
+        fs::write(&part, serde_json::to_string(&cached).unwrap()).unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(7 * 24 * 60 * 60);
+        fs::File::options().write(true).open(&part).unwrap().set_modified(old).unwrap();
+        merge_cache_dir(CacheKind::Parts, &parts, &shared).unwrap();
+        let copied = fs::metadata(shared.join("C25744.json")).unwrap().modified().unwrap();
+        assert_eq!(copied, old);
+
+        // A file named for another part would be looked up under the wrong code
+        let renamed = dir.path().join("renamed");
+        write(&renamed, &pins("C4", "MISNAMED", 0));
+        fs::rename(renamed.join("C4.json"), renamed.join("C5.json")).unwrap();
+        let err = merge_cache_dir(CacheKind::Pins, &renamed, &local).unwrap_err();
+        assert!(err.to_string().contains("\"C4\""), "{}", err);
+        assert!(!local.join("C5.json").exists());
+    }
+
+    #[test]
+    fn test_doctor_cache_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("parts");
+        let check = DoctorCheck::new("cache", check_writable(&cache));
+        assert!(check.ok, "{}", check.detail);
+        assert!(std::fs::read_dir(&cache).unwrap().next().is_none());
+
+        // A file where the directory should be
+        let file = dir.path().join("pins");
+        std::fs::write(&file, "").unwrap();
+        let check = DoctorCheck::new("cache", check_writable(&file));
+        assert!(!check.ok);
+        assert!(check.detail.contains("pins"));
+    }
+}
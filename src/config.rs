@@ -0,0 +1,211 @@
+//! Optional user config with defaults for common flags.
+//!
+//! Read from `~/.pcb/jlcpcb/config.toml` (or `--config <path>`). Every key is
+//! optional; flags given on the command line always win over the config,
+//! which wins over the built-in defaults.
+//!
+//! ```toml
+//! format = "json"
+//! basic = true
+//! timeout = 60
+//! retries = 3
+//! parts_per_request = 5
+//! mpn_results = 40
+//! cache_dir = "/tmp/jlcpcb-cache"
+//! price_qty = 1000
+//! format_cmd = "pcb fmt"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Built-in output format.
+const DEFAULT_FORMAT: &str = "human";
+
+/// Output formats accepted for `format`.
+const FORMATS: [&str; 2] = ["human", "json"];
+
+/// Most retries allowed, in the config and for `--retries`.
+pub const MAX_RETRIES: u32 = 10;
+
+/// Largest search page allowed for `parts_per_request` and `mpn_results`
+/// (and their flags); pages start at one result.
+pub const MAX_PAGE_SIZE: i32 = 100;
+
+/// Config file contents.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Output format for every command (human, json)
+    pub format: Option<String>,
+    /// Only search JLCPCB basic parts
+    pub basic: Option<bool>,
+    /// Network request timeout in seconds
+    pub timeout: Option<u64>,
+    /// Retries for failed EasyEDA requests
+    pub retries: Option<u32>,
+    /// Search results requested per exact part-number lookup
+    pub parts_per_request: Option<i32>,
+    /// Search results requested per page when resolving a BOM line by MPN
+    pub mpn_results: Option<i32>,
+    /// Directory holding the `parts/` and `pins/` caches
+    pub cache_dir: Option<PathBuf>,
+    /// Order quantity for displayed and filtered search prices
+    pub price_qty: Option<i32>,
+    /// Formatter `generate` runs on each written .zen file
+    pub format_cmd: Option<String>,
+}
+
+impl Config {
+    /// Load the config.
+    ///
+    /// An explicit `path` must exist; the default location is optional and
+    /// yields an empty config when missing.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (pcb_dir().join("config.toml"), false),
+        };
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read config: {}", path.display()))
+            }
+        };
+        Self::parse(&content).with_context(|| format!("Invalid config: {}", path.display()))
+    }
+
+    /// Parse config TOML, checking values against the ranges the
+    /// matching flags accept.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(format) = &self.format {
+            if !FORMATS.iter().any(|f| f.eq_ignore_ascii_case(format)) {
+                anyhow::bail!("format must be one of {}, not {:?}", FORMATS.join(", "), format);
+            }
+        }
+        if let Some(retries) = self.retries.filter(|&r| r > MAX_RETRIES) {
+            anyhow::bail!("retries must be at most {}, not {}", MAX_RETRIES, retries);
+        }
+        let page_sizes = [
+            ("parts_per_request", self.parts_per_request),
+            ("mpn_results", self.mpn_results),
+        ];
+        for (key, value) in page_sizes {
+            if let Some(value) = value.filter(|v| !(1..=MAX_PAGE_SIZE).contains(v)) {
+                anyhow::bail!("{} must be 1-{}, not {}", key, MAX_PAGE_SIZE, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Output format: the flag, else the config, else `human`.
+    pub fn format(&self, flag: Option<String>) -> String {
+        flag.or_else(|| self.format.clone())
+            .unwrap_or_else(|| DEFAULT_FORMAT.to_string())
+    }
+}
+
+/// Pick a setting: command line, then config, then built-in default.
+pub fn resolve<T>(flag: Option<T>, config: Option<T>, default: T) -> T {
+    flag.or(config).unwrap_or(default)
+}
+
+/// A boolean setting's command-line value from its `--x`/`--no-x` pair,
+/// `None` when neither was given.
+pub fn switch(on: bool, off: bool) -> Option<bool> {
+    if on {
+        Some(true)
+    } else if off {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Override the cache root. Only the first call has any effect.
+pub fn set_cache_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+/// Root of the part and pin caches (`~/.pcb/jlcpcb` unless configured).
+pub fn cache_dir() -> PathBuf {
+    CACHE_DIR.get().cloned().unwrap_or_else(pcb_dir)
+}
+
+/// `~/.pcb/jlcpcb`, falling back to the working directory without a home.
+fn pcb_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".pcb")
+        .join("jlcpcb")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let config = Config::parse("format = \"json\"\ntimeout = 60\n").unwrap();
+
+        // CLI > config > built-in
+        assert_eq!(config.format(Some("human".to_string())), "human");
+        assert_eq!(config.format(None), "json");
+        assert_eq!(Config::default().format(None), "human");
+
+        assert_eq!(resolve(Some(5), config.timeout, 30), 5);
+        assert_eq!(resolve(None, config.timeout, 30), 60);
+        assert_eq!(resolve(None, config.retries, 2), 2);
+
+        // Boolean settings: --x/--no-x override the config either way
+        let config = Config::parse("basic = true\n").unwrap();
+        assert!(!resolve(switch(false, true), config.basic, false));
+        assert!(resolve(switch(false, false), config.basic, false));
+        assert!(resolve(switch(true, false), Some(false), false));
+        assert!(!resolve(switch(false, false), None, false));
+
+        assert!(Config::parse("formt = \"json\"").is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_values() {
+        assert!(Config::parse("format = \"JSON\"\nretries = 10\nmpn_results = 100\n").is_ok());
+        for bad in [
+            "format = \"yaml\"",
+            "retries = 1000",
+            "parts_per_request = 0",
+            "mpn_results = 0",
+            "mpn_results = 101",
+        ] {
+            assert!(Config::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert!(Config::load(Some(&path)).is_err());
+
+        std::fs::write(&path, "basic = true\nprice_qty = 1000\nparts_per_request = 5\n").unwrap();
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.basic, Some(true));
+        assert_eq!(config.price_qty, Some(1000));
+        assert_eq!(config.parts_per_request, Some(5));
+    }
+}
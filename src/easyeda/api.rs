@@ -1,8 +1,9 @@
 //! EasyEDA API client.
 
-use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
+use colored::Colorize;
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
@@ -12,6 +13,10 @@ const EASYEDA_API_URL: &str = "https://easyeda.com/api/products";
 /// API version parameter.
 const API_VERSION: &str = "6.4.19.5";
 
+/// Other version parameters tried, in order, when a response doesn't match
+/// the expected schema under [`API_VERSION`].
+const FALLBACK_API_VERSIONS: [&str; 2] = ["6.5.22", "6.4.7"];
+
 /// EasyEDA API client.
 pub struct EasyEdaClient {
     client: Client,
@@ -20,8 +25,7 @@ pub struct EasyEdaClient {
 impl EasyEdaClient {
     /// Create a new EasyEDA client.
     pub fn new() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+        let client = crate::http::client_builder()
             .build()
             .context("Failed to create HTTP client")?;
 
@@ -30,34 +34,73 @@ impl EasyEdaClient {
 
     /// Fetch component data from EasyEDA.
     ///
-    /// Returns the raw component data including symbol shapes.
+    /// Returns the raw component data including symbol shapes. A response
+    /// that doesn't match the expected schema is retried with each of
+    /// [`FALLBACK_API_VERSIONS`] before giving up.
     pub fn get_component(&self, lcsc_id: &str) -> Result<Option<ComponentData>> {
+        let mut schema_error = None;
+
+        for version in std::iter::once(API_VERSION).chain(FALLBACK_API_VERSIONS) {
+            let Some(value) = self.get_component_json(lcsc_id, version)? else {
+                return Ok(None);
+            };
+
+            match serde_json::from_value::<ApiResponse>(value) {
+                Ok(api_response) => {
+                    if version != API_VERSION {
+                        eprintln!(
+                            "  {} EasyEDA response parsed with API version {}",
+                            "→".cyan(),
+                            version
+                        );
+                    }
+                    if !api_response.success {
+                        return Ok(None);
+                    }
+                    return Ok(api_response.result);
+                }
+                Err(e) => {
+                    if crate::http::debug_enabled() {
+                        eprintln!("[debug] easyeda: version {} didn't parse: {}", version, e);
+                    }
+                    schema_error = Some(e);
+                }
+            }
+        }
+
+        Err(schema_error.expect("at least one version is tried"))
+            .context("Failed to parse EasyEDA response")
+    }
+
+    /// Fetch the raw component response for one API version.
+    ///
+    /// Returns `None` for a non-success HTTP status.
+    fn get_component_json(
+        &self,
+        lcsc_id: &str,
+        version: &str,
+    ) -> Result<Option<serde_json::Value>> {
         let url = format!(
             "{}/{}/components?version={}",
-            EASYEDA_API_URL, lcsc_id, API_VERSION
+            EASYEDA_API_URL, lcsc_id, version
         );
 
-        let response = self
+        let started = Instant::now();
+        let request = self
             .client
             .get(&url)
             .header("Accept", "application/json")
-            .header("User-Agent", "pcb-jlcpcb")
-            .send()
+            .header("User-Agent", "pcb-jlcpcb");
+        let response = crate::http::send_with_retry(request, "easyeda")
             .context("Failed to fetch component from EasyEDA")?;
 
         if !response.status().is_success() {
             return Ok(None);
         }
 
-        let api_response: ApiResponse = response
-            .json()
+        let value = crate::http::read_json(response, "easyeda", started)
             .context("Failed to parse EasyEDA response")?;
-
-        if !api_response.success {
-            return Ok(None);
-        }
-
-        Ok(api_response.result)
+        Ok(Some(value))
     }
 }
 
@@ -69,6 +112,7 @@ struct ApiResponse {
 }
 
 /// Component data from EasyEDA.
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct ComponentData {
     /// Component UUID.
@@ -84,6 +128,41 @@ pub struct ComponentData {
     /// Package/footprint details.
     #[serde(rename = "packageDetail")]
     pub package_detail: Option<PackageDetail>,
+
+    /// Symbol units of multi-part components (arrays, dual gates).
+    #[serde(default)]
+    pub subparts: Vec<SubPart>,
+
+    /// LCSC part the component belongs to.
+    pub lcsc: Option<LcscInfo>,
+}
+
+impl ComponentData {
+    /// Parse saved component JSON, either bare or still wrapped in the API
+    /// response (`{"success": true, "result": {...}}`).
+    pub fn from_json(content: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        let value = match value.get("result") {
+            Some(result) if value.get("success").is_some() => result.clone(),
+            _ => value,
+        };
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// LCSC link of an EasyEDA component.
+#[derive(Debug, Deserialize)]
+pub struct LcscInfo {
+    /// LCSC part number (e.g. "C2040").
+    pub number: String,
+}
+
+/// One unit of a multi-part symbol.
+#[derive(Debug, Deserialize)]
+pub struct SubPart {
+    /// Raw data string with this unit's shapes.
+    #[serde(rename = "dataStr")]
+    pub data_str: Option<DataStr>,
 }
 
 /// Symbol data structure.
@@ -94,6 +173,7 @@ pub struct DataStr {
 }
 
 /// Package/footprint details.
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct PackageDetail {
     /// Footprint UUID.
@@ -118,6 +198,7 @@ pub struct PackageDataStr {
 }
 
 /// Package head metadata.
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct PackageHead {
     /// Package parameters.
@@ -3,6 +3,9 @@
 use anyhow::Result;
 use std::fmt::Write;
 
+use super::KicadVersion;
+use crate::natural::natural_cmp;
+
 /// EasyEDA to KiCad coordinate conversion factor.
 /// EasyEDA uses 10 mil units, KiCad uses mm.
 const EASYEDA_TO_MM: f64 = 0.254;
@@ -26,8 +29,14 @@ pub struct FootprintPad {
     pub rotation: f64,
     /// Whether this is a through-hole pad.
     pub through_hole: bool,
-    /// Drill hole diameter in mm (for TH pads).
+    /// Drill hole diameter in mm (for TH pads); the slot width if slotted.
     pub drill: Option<f64>,
+    /// Slot length in mm for slotted (oval) drills.
+    pub slot_length: Option<f64>,
+    /// Solder mask expansion in mm (None = KiCad default).
+    pub solder_mask_margin: Option<f64>,
+    /// Solder paste expansion in mm (None = KiCad default).
+    pub solder_paste_margin: Option<f64>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,7 +47,7 @@ pub enum PadShape {
 }
 
 impl PadShape {
-    fn to_kicad(&self) -> &'static str {
+    fn to_kicad(self) -> &'static str {
         match self {
             PadShape::Rect => "rect",
             PadShape::Oval => "oval",
@@ -47,6 +56,44 @@ impl PadShape {
     }
 }
 
+/// Where the footprint's reference designator is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefPlacement {
+    /// Reference on F.SilkS above the part, plus `${REFERENCE}` on F.Fab
+    /// inside the body (KiCad library convention)
+    #[default]
+    SilkAndFab,
+    /// Reference on F.Fab only, keeping the silkscreen free of text
+    FabOnly,
+}
+
+/// Where the footprint origin goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FootprintOrigin {
+    /// Centre of the pad bounding box (KiCad library convention)
+    #[default]
+    Center,
+    /// Pad 1, so connectors line up with a board edge or grid
+    Pin1,
+}
+
+/// Silkscreen reference text size in mm (KiCad library convention).
+const SILK_TEXT_SIZE: f64 = 1.0;
+
+/// Gap between the pads and text placed above/below them, in mm.
+const TEXT_GAP: f64 = 0.5;
+
+/// A centre pad at least this many times the area of every other pad is
+/// treated as an exposed thermal pad.
+const THERMAL_AREA_RATIO: f64 = 4.0;
+
+/// Target size in mm of each paste aperture cell on a thermal pad.
+const THERMAL_PASTE_CELL: f64 = 1.25;
+
+/// Fraction of a thermal pad's area covered by paste (KiCad libraries use
+/// 50-80% to keep the part from floating on the solder).
+const THERMAL_PASTE_COVERAGE: f64 = 0.6;
+
 /// Parsed track/line from EasyEDA footprint (for silkscreen).
 #[derive(Debug, Clone)]
 pub struct FootprintLine {
@@ -65,7 +112,12 @@ pub struct FootprintLine {
 }
 
 /// Parse EasyEDA footprint shapes into pads and lines.
-pub fn parse_footprint_shapes(shapes: &[String]) -> (Vec<FootprintPad>, Vec<FootprintLine>) {
+///
+/// Pads are sorted by number unless `preserve_order` is set.
+pub fn parse_footprint_shapes(
+    shapes: &[String],
+    preserve_order: bool,
+) -> (Vec<FootprintPad>, Vec<FootprintLine>) {
     let mut pads = Vec::new();
     let mut lines = Vec::new();
 
@@ -80,18 +132,21 @@ pub fn parse_footprint_shapes(shapes: &[String]) -> (Vec<FootprintPad>, Vec<Foot
     }
 
     // Sort pads by number
-    pads.sort_by(|a, b| {
-        match (a.number.parse::<u32>(), b.number.parse::<u32>()) {
-            (Ok(na), Ok(nb)) => na.cmp(&nb),
-            _ => alphanum_cmp(&a.number, &b.number),
-        }
-    });
+    if !preserve_order {
+        pads.sort_by(|a, b| {
+            match (a.number.parse::<u32>(), b.number.parse::<u32>()) {
+                (Ok(na), Ok(nb)) => na.cmp(&nb),
+                _ => natural_cmp(&a.number, &b.number),
+            }
+        });
+    }
 
     (pads, lines)
 }
 
 /// Parse a PAD shape string.
-/// Format: PAD~shape~cx~cy~width~height~layer~net~number~holeRad~points~rotation~id~...
+/// Format: PAD~shape~cx~cy~width~height~layer~net~number~holeRad~points~rotation~id~
+///         holeLength~holePoints~plated~locked~pasteExpansion~maskExpansion
 fn parse_pad(shape: &str) -> Option<FootprintPad> {
     let parts: Vec<&str> = shape.split('~').collect();
     if parts.len() < 13 {
@@ -107,6 +162,15 @@ fn parse_pad(shape: &str) -> Option<FootprintPad> {
     let number = parts.get(8)?.to_string();
     let hole_rad: f64 = parts.get(9)?.parse().unwrap_or(0.0);
     let rotation: f64 = parts.get(11)?.parse().unwrap_or(0.0);
+    let hole_length: f64 = parts.get(13).and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    // Optional trailing fields; absent or zero means "use the default"
+    let margin = |idx: usize| {
+        parts
+            .get(idx)
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|v| v.abs() > f64::EPSILON)
+            .map(|v| v * EASYEDA_TO_MM)
+    };
 
     if number.is_empty() {
         return None;
@@ -143,11 +207,22 @@ fn parse_pad(shape: &str) -> Option<FootprintPad> {
         } else {
             None
         },
+        // A hole longer than its diameter is a slot
+        slot_length: if hole_rad > 0.0 && hole_length > hole_rad * 2.0 {
+            Some(hole_length * EASYEDA_TO_MM)
+        } else {
+            None
+        },
+        solder_mask_margin: margin(18),
+        solder_paste_margin: margin(17),
     })
 }
 
 /// Parse a TRACK shape string into line segments.
 /// Format: TRACK~width~layer~net~points~id~locked
+///
+/// `points` is a polyline `x1 y1 x2 y2 ...`; each pair of neighbouring
+/// vertices becomes one segment, skipping zero-length ones.
 fn parse_track(shape: &str) -> Vec<FootprintLine> {
     let parts: Vec<&str> = shape.split('~').collect();
     if parts.len() < 5 {
@@ -177,54 +252,121 @@ fn parse_track(shape: &str) -> Vec<FootprintLine> {
         return Vec::new();
     }
 
-    let coords: Vec<f64> = points_str
-        .split_whitespace()
-        .filter_map(|s| s.parse().ok())
+    // A bad number or a dangling coordinate leaves the pairing ambiguous,
+    // so the whole track is dropped rather than drawn wrong
+    let coords: Option<Vec<f64>> = points_str
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
         .collect();
+    let Some(coords) = coords.filter(|c| c.len() % 2 == 0) else {
+        return Vec::new();
+    };
 
-    let mut lines = Vec::new();
-    for chunk in coords.chunks(2).collect::<Vec<_>>().windows(2) {
-        if let [p1, p2] = chunk {
-            if p1.len() == 2 && p2.len() == 2 {
-                lines.push(FootprintLine {
-                    x1: p1[0] * EASYEDA_TO_MM,
-                    y1: p1[1] * EASYEDA_TO_MM,
-                    x2: p2[0] * EASYEDA_TO_MM,
-                    y2: p2[1] * EASYEDA_TO_MM,
-                    width: width * EASYEDA_TO_MM,
-                    layer: layer.to_string(),
-                });
+    let points: Vec<(f64, f64)> = coords.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    points
+        .windows(2)
+        .filter(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1) > 1e-6)
+        .map(|w| FootprintLine {
+            x1: w[0].0 * EASYEDA_TO_MM,
+            y1: w[0].1 * EASYEDA_TO_MM,
+            x2: w[1].0 * EASYEDA_TO_MM,
+            y2: w[1].1 * EASYEDA_TO_MM,
+            width: width * EASYEDA_TO_MM,
+            layer: layer.to_string(),
+        })
+        .collect()
+}
+
+/// Derive a short package label from an EasyEDA footprint name.
+///
+/// EasyEDA names append body dimensions after an underscore
+/// (`WLP-9_L1.4-W1.3-P0.40-R3-C3-BR` → `WLP-9`), and prefix chip passives
+/// with their type (`R0402` → `0402`).
+pub fn package_token(footprint_name: &str) -> String {
+    let base = footprint_name
+        .split('_')
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    for prefix in ["LED", "R", "C", "L", "D", "F"] {
+        if let Some(size) = base.strip_prefix(prefix) {
+            if size.len() == 4 && size.bytes().all(|b| b.is_ascii_digit()) {
+                return size.to_string();
             }
         }
     }
 
-    lines
+    if base.is_empty() {
+        footprint_name.trim().to_string()
+    } else {
+        base.to_string()
+    }
 }
 
 /// Generate KiCad .kicad_mod file content.
-pub fn generate_kicad_mod(name: &str, pads: &[FootprintPad], lines: &[FootprintLine]) -> Result<String> {
+///
+/// `source` is the hash of the EasyEDA input, recorded in the description.
+pub fn generate_kicad_mod(
+    name: &str,
+    pads: &[FootprintPad],
+    lines: &[FootprintLine],
+    source: Option<&str>,
+    version: KicadVersion,
+    placement: RefPlacement,
+    origin: FootprintOrigin,
+) -> Result<String> {
     let mut out = String::new();
 
     // Calculate center offset (EasyEDA footprints may not be centered)
-    let (offset_x, offset_y) = calculate_center_offset(pads);
+    let (center_x, center_y) = calculate_center_offset(pads);
+    let (half_w, half_h) = pad_half_extents(pads, center_x, center_y);
+    let fab_size = fab_text_size(half_w, half_h);
+
+    let (offset_x, offset_y) = match origin {
+        FootprintOrigin::Center => (center_x, center_y),
+        FootprintOrigin::Pin1 => pin1_position(pads).unwrap_or((center_x, center_y)),
+    };
+    // Text stays on the body centre wherever the origin is
+    let (text_x, text_y) = (center_x - offset_x, center_y - offset_y);
 
     writeln!(out, "(footprint \"{}\"", name)?;
-    writeln!(out, "  (version 20240108)")?;
+    writeln!(out, "  (version {})", version.footprint_version())?;
     writeln!(out, "  (generator \"pcb-jlcpcb\")")?;
-    writeln!(out, "  (generator_version \"1.0\")")?;
+    if version.has_generator_version() {
+        writeln!(out, "  (generator_version \"1.0\")")?;
+    }
     writeln!(out, "  (layer \"F.Cu\")")?;
-
-    // Reference and value text
-    writeln!(out, "  (fp_text reference \"REF**\" (at 0 -2) (layer \"F.SilkS\")")?;
-    writeln!(out, "    (effects (font (size 1 1) (thickness 0.15)))")?;
-    writeln!(out, "  )")?;
-    writeln!(out, "  (fp_text value \"{}\" (at 0 2) (layer \"F.Fab\")", name)?;
-    writeln!(out, "    (effects (font (size 1 1) (thickness 0.15)))")?;
-    writeln!(out, "  )")?;
-
-    // Write pads
-    for pad in pads {
-        write_pad(&mut out, pad, offset_x, offset_y)?;
+    if let Some(source) = source {
+        writeln!(out, "  (descr \"pcb-jlcpcb source {}\")", source)?;
+    }
+    writeln!(out, "  (attr {})", footprint_attr(pads))?;
+
+    // Reference and value text, placed just outside the pads; fab text is
+    // sized to the part so it fits on tiny packages
+    match placement {
+        RefPlacement::SilkAndFab => {
+            let y = text_y - (half_h + TEXT_GAP + SILK_TEXT_SIZE / 2.0);
+            write_text(&mut out, "reference", "REF**", text_x, y, "F.SilkS", SILK_TEXT_SIZE)?;
+            write_text(&mut out, "user", "${REFERENCE}", text_x, text_y, "F.Fab", fab_size)?;
+        }
+        RefPlacement::FabOnly => {
+            write_text(&mut out, "reference", "REF**", text_x, text_y, "F.Fab", fab_size)?;
+        }
+    }
+    let y = text_y + half_h + TEXT_GAP + fab_size / 2.0;
+    write_text(&mut out, "value", name, text_x, y, "F.Fab", fab_size)?;
+
+    // Write pads; an exposed thermal pad gets its paste split into a grid
+    // of smaller apertures instead of one full-size opening
+    let thermal = thermal_pad_index(pads, center_x, center_y);
+    for (i, pad) in pads.iter().enumerate() {
+        if thermal == Some(i) {
+            write_thermal_pad(&mut out, pad, offset_x, offset_y)?;
+        } else {
+            write_pad(&mut out, pad, offset_x, offset_y, true)?;
+        }
     }
 
     // Write silkscreen lines
@@ -237,6 +379,66 @@ pub fn generate_kicad_mod(name: &str, pads: &[FootprintPad], lines: &[FootprintL
     Ok(out)
 }
 
+/// Write an `fp_text` item with square glyphs of `size` mm.
+fn write_text(
+    out: &mut String,
+    kind: &str,
+    text: &str,
+    x: f64,
+    y: f64,
+    layer: &str,
+    size: f64,
+) -> Result<()> {
+    writeln!(
+        out,
+        "  (fp_text {} \"{}\" (at {:.4} {:.4}) (layer \"{}\")",
+        kind, text, x, y, layer
+    )?;
+    writeln!(
+        out,
+        "    (effects (font (size {:.2} {:.2}) (thickness {:.3})))",
+        size,
+        size,
+        size * 0.15
+    )?;
+    writeln!(out, "  )")?;
+    Ok(())
+}
+
+/// Half width/height in mm of the box around all pads, after centering.
+fn pad_half_extents(pads: &[FootprintPad], offset_x: f64, offset_y: f64) -> (f64, f64) {
+    pads.iter().fold((0.0_f64, 0.0_f64), |(hw, hh), pad| {
+        // Pads turned a quarter turn swap their width and height
+        let quarter = ((pad.rotation / 90.0).round() as i64).rem_euclid(2) == 1;
+        let (w, h) = if quarter {
+            (pad.height, pad.width)
+        } else {
+            (pad.width, pad.height)
+        };
+        (
+            hw.max((pad.x - offset_x).abs() + w / 2.0),
+            hh.max((pad.y - offset_y).abs() + h / 2.0),
+        )
+    })
+}
+
+/// F.Fab text size in mm: 1 mm where there's room, shrinking with the pad
+/// area on small parts (down to 0.25 mm) so the text stays inside the body.
+fn fab_text_size(half_w: f64, half_h: f64) -> f64 {
+    let size = (half_w.min(half_h) * 2.0 * 0.7).clamp(0.25, 1.0);
+    (size * 100.0).round() / 100.0
+}
+
+/// KiCad footprint type: `smd` only when every pad is SMD, so mixed
+/// footprints (e.g. SMD connectors with TH pegs) count as through-hole.
+fn footprint_attr(pads: &[FootprintPad]) -> &'static str {
+    if pads.iter().any(|p| p.through_hole) {
+        "through_hole"
+    } else {
+        "smd"
+    }
+}
+
 /// Calculate offset to center the footprint.
 fn calculate_center_offset(pads: &[FootprintPad]) -> (f64, f64) {
     if pads.is_empty() {
@@ -254,16 +456,32 @@ fn calculate_center_offset(pads: &[FootprintPad]) -> (f64, f64) {
     (center_x, center_y)
 }
 
+/// Position of pad 1, or of the first pad when none is numbered `1`.
+fn pin1_position(pads: &[FootprintPad]) -> Option<(f64, f64)> {
+    let pad = pads.iter().find(|p| p.number == "1").or(pads.first())?;
+    Some((pad.x, pad.y))
+}
+
 /// Write a single pad to the output.
-fn write_pad(out: &mut String, pad: &FootprintPad, offset_x: f64, offset_y: f64) -> Result<()> {
+///
+/// `paste` is false for SMD pads whose paste is written separately.
+fn write_pad(
+    out: &mut String,
+    pad: &FootprintPad,
+    offset_x: f64,
+    offset_y: f64,
+    paste: bool,
+) -> Result<()> {
     let x = pad.x - offset_x;
     let y = pad.y - offset_y;
 
     let pad_type = if pad.through_hole { "thru_hole" } else { "smd" };
     let layers = if pad.through_hole {
         "\"*.Cu\" \"*.Mask\""
-    } else {
+    } else if paste {
         "\"F.Cu\" \"F.Paste\" \"F.Mask\""
+    } else {
+        "\"F.Cu\" \"F.Mask\""
     };
 
     write!(
@@ -282,11 +500,110 @@ fn write_pad(out: &mut String, pad: &FootprintPad, offset_x: f64, offset_y: f64)
 
     write!(out, ") (size {:.4} {:.4})", pad.width, pad.height)?;
 
-    if let Some(drill) = pad.drill {
-        write!(out, " (drill {:.4})", drill)?;
+    match (pad.drill, pad.slot_length) {
+        // Run the slot along the pad's longer side
+        (Some(drill), Some(length)) if pad.width >= pad.height => {
+            write!(out, " (drill oval {:.4} {:.4})", length, drill)?;
+        }
+        (Some(drill), Some(length)) => {
+            write!(out, " (drill oval {:.4} {:.4})", drill, length)?;
+        }
+        (Some(drill), None) => write!(out, " (drill {:.4})", drill)?,
+        (None, _) => {}
     }
 
-    writeln!(out, " (layers {}))", layers)?;
+    write!(out, " (layers {})", layers)?;
+
+    if let Some(margin) = pad.solder_mask_margin {
+        write!(out, " (solder_mask_margin {:.4})", margin)?;
+    }
+
+    if let Some(margin) = pad.solder_paste_margin.filter(|_| paste) {
+        write!(out, " (solder_paste_margin {:.4})", margin)?;
+    }
+
+    writeln!(out, ")")?;
+
+    Ok(())
+}
+
+/// Index of the exposed thermal pad (e.g. a QFN's centre pad), if any.
+///
+/// That's an unrotated (or quarter-turned) rectangular SMD pad covering the
+/// footprint centre whose area is at least [`THERMAL_AREA_RATIO`] times that
+/// of every other pad. Pads with an explicit paste margin are left alone.
+fn thermal_pad_index(pads: &[FootprintPad], offset_x: f64, offset_y: f64) -> Option<usize> {
+    if pads.len() < 3 {
+        return None;
+    }
+    let area = |p: &FootprintPad| p.width * p.height;
+    let (index, pad) = pads
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| area(a).total_cmp(&area(b)))?;
+
+    let (w, h) = axis_aligned_size(pad)?;
+    let covers_center =
+        (pad.x - offset_x).abs() < w / 2.0 && (pad.y - offset_y).abs() < h / 2.0;
+    let dominant = pads
+        .iter()
+        .enumerate()
+        .all(|(i, p)| i == index || area(pad) >= THERMAL_AREA_RATIO * area(p));
+
+    (matches!(pad.shape, PadShape::Rect)
+        && !pad.through_hole
+        && pad.solder_paste_margin.is_none()
+        && covers_center
+        && dominant)
+        .then_some(index)
+}
+
+/// Pad width/height along the footprint axes, or None for pads at an angle
+/// other than a multiple of 90 degrees.
+fn axis_aligned_size(pad: &FootprintPad) -> Option<(f64, f64)> {
+    let turns = pad.rotation / 90.0;
+    if (turns - turns.round()).abs() > 0.01 {
+        return None;
+    }
+    if (turns.round() as i64).rem_euclid(2) == 1 {
+        Some((pad.height, pad.width))
+    } else {
+        Some((pad.width, pad.height))
+    }
+}
+
+/// Write a thermal pad without paste, followed by unnumbered paste-only pads
+/// splitting its paste into a grid of apertures.
+fn write_thermal_pad(
+    out: &mut String,
+    pad: &FootprintPad,
+    offset_x: f64,
+    offset_y: f64,
+) -> Result<()> {
+    write_pad(out, pad, offset_x, offset_y, false)?;
+
+    let Some((w, h)) = axis_aligned_size(pad) else {
+        return Ok(());
+    };
+    let cols = (w / THERMAL_PASTE_CELL).ceil().max(1.0);
+    let rows = (h / THERMAL_PASTE_CELL).ceil().max(1.0);
+    let (cell_w, cell_h) = (w / cols, h / rows);
+    let scale = THERMAL_PASTE_COVERAGE.sqrt();
+
+    for row in 0..rows as usize {
+        for col in 0..cols as usize {
+            let x = pad.x - offset_x - w / 2.0 + cell_w * (col as f64 + 0.5);
+            let y = pad.y - offset_y - h / 2.0 + cell_h * (row as f64 + 0.5);
+            writeln!(
+                out,
+                "  (pad \"\" smd rect (at {:.4} {:.4}) (size {:.4} {:.4}) (layers \"F.Paste\"))",
+                x,
+                y,
+                cell_w * scale,
+                cell_h * scale
+            )?;
+        }
+    }
 
     Ok(())
 }
@@ -307,28 +624,35 @@ fn write_line(out: &mut String, line: &FootprintLine, offset_x: f64, offset_y: f
     Ok(())
 }
 
-/// Alphanumeric comparison for pad numbers.
-fn alphanum_cmp(a: &str, b: &str) -> std::cmp::Ordering {
-    let (a_prefix, a_num) = split_alphanum(a);
-    let (b_prefix, b_num) = split_alphanum(b);
-
-    match a_prefix.cmp(&b_prefix) {
-        std::cmp::Ordering::Equal => a_num.cmp(&b_num),
-        other => other,
-    }
-}
-
-fn split_alphanum(s: &str) -> (&str, u32) {
-    let idx = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
-    let prefix = &s[..idx];
-    let num = s[idx..].parse().unwrap_or(0);
-    (prefix, num)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_package_token() {
+        assert_eq!(package_token("WLP-9_L1.4-W1.3-P0.40-R3-C3-BR"), "WLP-9");
+        assert_eq!(package_token("SOT-23-3_L2.9-W1.3-P1.90-LS2.4-BR"), "SOT-23-3");
+        assert_eq!(package_token("QFN-16_L3.0-W3.0-P0.50-BL-EP1.7"), "QFN-16");
+        assert_eq!(package_token("R0402"), "0402");
+        assert_eq!(package_token("LED0603-RD"), "LED0603-RD");
+        assert_eq!(package_token("SMA(DO-214AC)"), "SMA(DO-214AC)");
+        assert_eq!(package_token("_odd"), "_odd");
+    }
+
+    #[test]
+    fn test_parse_track_polyline() {
+        let lines = parse_track("TRACK~1~3~~0 0 10 0 10 10 10 10~gge1~0");
+        // The repeated last vertex adds no zero-length segment
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].x1, lines[0].x2), (0.0, 2.54));
+        assert_eq!((lines[1].y1, lines[1].y2), (0.0, 2.54));
+        assert_eq!(lines[1].layer, "F.SilkS");
+
+        assert!(parse_track("TRACK~1~3~~0 0 10 0 10~gge1~0").is_empty());
+        assert!(parse_track("TRACK~1~3~~0 0 10 x~gge1~0").is_empty());
+        assert!(parse_track("TRACK~1~3~~5 5~gge1~0").is_empty());
+    }
+
     #[test]
     fn test_parse_smd_pad() {
         let shape = "PAD~RECT~100~100~10~20~1~~1~~~0~gge1~~~~";
@@ -339,6 +663,34 @@ mod tests {
         assert!((pad.height - 5.08).abs() < 0.01); // 20 * 0.254
     }
 
+    #[test]
+    fn test_parse_pad_margins() {
+        let shape = "PAD~RECT~100~100~10~20~1~~1~0~~0~gge1~0~~Y~0~-0.5~0.2";
+        let pad = parse_pad(shape).unwrap();
+        assert!((pad.solder_paste_margin.unwrap() + 0.127).abs() < 0.001); // -0.5 * 0.254
+        assert!((pad.solder_mask_margin.unwrap() - 0.0508).abs() < 0.001); // 0.2 * 0.254
+
+        let out = generate_kicad_mod("TEST", &[pad], &[], None, KicadVersion::default(), RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(out.contains("(solder_mask_margin 0.0508)"));
+        assert!(out.contains("(solder_paste_margin -0.1270)"));
+
+        // Absent margins leave KiCad defaults in place
+        let pad = parse_pad("PAD~RECT~100~100~10~20~1~~1~~~0~gge1~~~~").unwrap();
+        assert!(pad.solder_mask_margin.is_none());
+        assert!(pad.solder_paste_margin.is_none());
+    }
+
+    #[test]
+    fn test_footprint_kicad_version() {
+        let pads = vec![parse_pad("PAD~RECT~100~100~10~20~1~~1~~~0~gge1~~~~").unwrap()];
+        let v7 = generate_kicad_mod("TEST", &pads, &[], None, KicadVersion::V7, RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(v7.contains("(version 20221018)"));
+        assert!(!v7.contains("generator_version"));
+
+        let v9 = generate_kicad_mod("TEST", &pads, &[], None, KicadVersion::V9, RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(v9.contains("(version 20241229)"));
+    }
+
     #[test]
     fn test_parse_through_hole_pad() {
         let shape = "PAD~ELLIPSE~100~100~10~10~11~~1~3~~~0~gge1~~~~";
@@ -347,4 +699,134 @@ mod tests {
         assert!(pad.through_hole);
         assert!(pad.drill.is_some());
     }
+
+    #[test]
+    fn test_parse_slotted_hole_pad() {
+        // 3.6 x 1.6 mm pad with a 2.54 x 0.762 mm slot
+        let shape = "PAD~OVAL~100~100~14.1732~6.2992~11~~1~1.5~~0~gge1~10~~Y~0";
+        let pad = parse_pad(shape).unwrap();
+        assert!((pad.drill.unwrap() - 0.762).abs() < 0.001);
+        assert!((pad.slot_length.unwrap() - 2.54).abs() < 0.001);
+
+        let out = generate_kicad_mod("TEST", &[pad], &[], None, KicadVersion::default(), RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(out.contains("(drill oval 2.5400 0.7620)"));
+
+        // Hole length equal to the diameter is a plain round drill
+        let round = parse_pad("PAD~ELLIPSE~100~100~10~10~11~~1~3~~0~gge1~6~~Y~0").unwrap();
+        assert!(round.slot_length.is_none());
+    }
+
+    #[test]
+    fn test_text_scales_for_0201() {
+        // 0201: two 0.4 x 0.4 mm pads, centers 0.7 mm apart
+        let pads = vec![
+            parse_pad("PAD~RECT~98.622~100~1.5748~1.5748~1~~1~~~0~gge1~~~~").unwrap(),
+            parse_pad("PAD~RECT~101.378~100~1.5748~1.5748~1~~2~~~0~gge2~~~~").unwrap(),
+        ];
+        let center = 100.0 * EASYEDA_TO_MM;
+        let (half_w, half_h) = pad_half_extents(&pads, center, center);
+        assert!((half_w - 0.55).abs() < 0.001);
+        assert!((half_h - 0.2).abs() < 0.001);
+
+        let version = KicadVersion::default();
+        let out = generate_kicad_mod("R_0201", &pads, &[], None, version, RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(out.contains("(fp_text reference \"REF**\" (at 0.0000 -1.2000) (layer \"F.SilkS\")"));
+        assert!(out.contains("(fp_text user \"${REFERENCE}\" (at 0.0000 0.0000) (layer \"F.Fab\")"));
+        assert!(out.contains("(effects (font (size 0.28 0.28) (thickness 0.042)))"));
+        assert!(!out.contains("(size 1 1)"));
+
+        let fab_only =
+            generate_kicad_mod("R_0201", &pads, &[], None, version, RefPlacement::FabOnly, FootprintOrigin::default()).unwrap();
+        assert!(!fab_only.contains("F.SilkS"));
+        assert!(fab_only.contains("(fp_text reference \"REF**\" (at 0.0000 0.0000) (layer \"F.Fab\")"));
+
+        // Large parts keep 1 mm fab text
+        assert_eq!(fab_text_size(3.0, 2.5), 1.0);
+    }
+
+    #[test]
+    fn test_footprint_attr() {
+        let smd = vec![
+            parse_pad("PAD~RECT~100~100~10~20~1~~1~~~0~gge1~~~~").unwrap(),
+            parse_pad("PAD~RECT~120~100~10~20~1~~2~~~0~gge2~~~~").unwrap(),
+        ];
+        let out = generate_kicad_mod("TEST", &smd, &[], None, KicadVersion::default(), RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(out.contains("  (attr smd)\n"));
+
+        let mut mixed = smd.clone();
+        mixed.push(parse_pad("PAD~ELLIPSE~100~100~10~10~11~~3~3~~~0~gge3~~~~").unwrap());
+        let out = generate_kicad_mod("TEST", &mixed, &[], None, KicadVersion::default(), RefPlacement::default(), FootprintOrigin::default()).unwrap();
+        assert!(out.contains("  (attr through_hole)\n"));
+    }
+
+    #[test]
+    fn test_qfn_thermal_pad_paste_split() {
+        // QFN-16 3x3: four 0.25 x 0.8 mm signal pads per side and a 1.7 mm
+        // exposed pad in the middle
+        let mut pads: Vec<FootprintPad> = (0..16)
+            .map(|i| {
+                let along = (i % 4) as f64 * 0.5 - 0.75;
+                let (x, y, width, height) = match i / 4 {
+                    0 => (-1.45, along, 0.8, 0.25),
+                    1 => (along, 1.45, 0.25, 0.8),
+                    2 => (1.45, -along, 0.8, 0.25),
+                    _ => (-along, -1.45, 0.25, 0.8),
+                };
+                FootprintPad {
+                    number: (i + 1).to_string(),
+                    shape: PadShape::Rect,
+                    x,
+                    y,
+                    width,
+                    height,
+                    rotation: 0.0,
+                    through_hole: false,
+                    drill: None,
+                    slot_length: None,
+                    solder_mask_margin: None,
+                    solder_paste_margin: None,
+                }
+            })
+            .collect();
+        let mut ep = pads[0].clone();
+        ep.number = "17".to_string();
+        (ep.x, ep.y, ep.width, ep.height) = (0.0, 0.0, 1.7, 1.7);
+        pads.push(ep);
+
+        assert_eq!(thermal_pad_index(&pads, 0.0, 0.0), Some(16));
+        let version = KicadVersion::default();
+        let out = generate_kicad_mod("QFN-16", &pads, &[], None, version, RefPlacement::default(), FootprintOrigin::default()).unwrap();
+
+        assert!(out.contains("(pad \"17\" smd rect (at 0.0000 0.0000) (size 1.7000 1.7000) (layers \"F.Cu\" \"F.Mask\"))"));
+        assert!(out.contains("(pad \"1\" smd rect (at -1.4500 -0.7500) (size 0.8000 0.2500) (layers \"F.Cu\" \"F.Paste\" \"F.Mask\"))"));
+        // 2x2 grid of 0.6584 mm apertures, 60% of the pad area
+        assert_eq!(out.matches("(layers \"F.Paste\")").count(), 4);
+        assert!(out.contains("(pad \"\" smd rect (at -0.4250 -0.4250) (size 0.6584 0.6584) (layers \"F.Paste\"))"));
+
+        // Off-centre tabs (SOT-223, DPAK) keep their full paste opening
+        assert_eq!(thermal_pad_index(&pads, 1.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_connector_origin_at_pin1() {
+        // 1x4 pin header, 2.54 mm pitch, pad 1 on the left
+        let pads: Vec<FootprintPad> = (0..4)
+            .map(|i| {
+                parse_pad(&format!("PAD~RECT~{}~100~6.8~6.8~11~~{}~1.9~~0~gge{}~~~~", 100 + i * 10, i + 1, i))
+                    .unwrap()
+            })
+            .collect();
+        let version = KicadVersion::default();
+        let placement = RefPlacement::default();
+
+        let centered = generate_kicad_mod("CONN", &pads, &[], None, version, placement, FootprintOrigin::Center).unwrap();
+        assert!(centered.contains("(pad \"1\" thru_hole rect (at -3.8100 0.0000)"));
+        assert!(centered.contains("(fp_text user \"${REFERENCE}\" (at 0.0000 0.0000)"));
+
+        let pin1 = generate_kicad_mod("CONN", &pads, &[], None, version, placement, FootprintOrigin::Pin1).unwrap();
+        assert!(pin1.contains("(pad \"1\" thru_hole rect (at 0.0000 0.0000)"));
+        assert!(pin1.contains("(pad \"4\" thru_hole rect (at 7.6200 0.0000)"));
+        // Text stays over the body
+        assert!(pin1.contains("(fp_text user \"${REFERENCE}\" (at 3.8100 0.0000)"));
+    }
 }
@@ -0,0 +1,81 @@
+//! KiCad file format version tokens.
+//!
+//! Each KiCad major release bumps the `(version ...)` token written at the
+//! top of `.kicad_mod`/`.kicad_sym` files and occasionally changes syntax.
+//! All version-dependent output is routed through [`KicadVersion`].
+
+/// Target KiCad major version for generated symbol/footprint files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KicadVersion {
+    /// KiCad 7.x
+    V7,
+    /// KiCad 8.x
+    #[default]
+    V8,
+    /// KiCad 9.x
+    V9,
+}
+
+impl KicadVersion {
+    /// Look up a version by KiCad major number (7, 8 or 9).
+    pub fn from_major(major: u8) -> Option<Self> {
+        match major {
+            7 => Some(KicadVersion::V7),
+            8 => Some(KicadVersion::V8),
+            9 => Some(KicadVersion::V9),
+            _ => None,
+        }
+    }
+
+    /// `(version ...)` token for `.kicad_mod` footprint files.
+    pub fn footprint_version(self) -> u32 {
+        match self {
+            KicadVersion::V7 => 20221018,
+            KicadVersion::V8 => 20240108,
+            KicadVersion::V9 => 20241229,
+        }
+    }
+
+    /// `(version ...)` token for `.kicad_sym` symbol library files.
+    pub fn symbol_version(self) -> u32 {
+        match self {
+            KicadVersion::V7 => 20220914,
+            KicadVersion::V8 => 20231120,
+            KicadVersion::V9 => 20241209,
+        }
+    }
+
+    /// Whether the `(generator_version ...)` token is understood (KiCad 8+).
+    pub fn has_generator_version(self) -> bool {
+        self != KicadVersion::V7
+    }
+
+    /// Whether symbols carry an `(exclude_from_sim ...)` flag (KiCad 8+).
+    pub fn has_exclude_from_sim(self) -> bool {
+        self != KicadVersion::V7
+    }
+
+    /// Token used inside `(effects ...)` to hide text.
+    ///
+    /// KiCad 9 switched from a bare `hide` keyword to `(hide yes)`.
+    pub fn hide_token(self) -> &'static str {
+        match self {
+            KicadVersion::V9 => "(hide yes)",
+            _ => "hide",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_major() {
+        assert_eq!(KicadVersion::from_major(7), Some(KicadVersion::V7));
+        assert_eq!(KicadVersion::from_major(9), Some(KicadVersion::V9));
+        assert_eq!(KicadVersion::from_major(6), None);
+        assert_eq!(KicadVersion::default().footprint_version(), 20240108);
+        assert_eq!(KicadVersion::default().symbol_version(), 20231120);
+    }
+}
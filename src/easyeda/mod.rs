@@ -5,15 +5,62 @@
 
 mod api;
 pub mod footprint;
+mod kicad;
+mod pairs;
 mod parser;
+mod source_hash;
 pub mod symbol;
 
 use serde::{Deserialize, Serialize};
 
 pub use api::{ComponentData, EasyEdaClient};
-pub use footprint::{generate_kicad_mod, parse_footprint_shapes};
+pub use footprint::{
+    generate_kicad_mod, package_token, parse_footprint_shapes, FootprintOrigin, RefPlacement,
+};
+pub use kicad::KicadVersion;
+pub use pairs::{find_diff_pairs, pair_adjacent_order};
 pub use parser::parse_symbol_pins;
-pub use symbol::generate_kicad_sym;
+pub use source_hash::{embedded_source_hash, source_hash};
+pub use symbol::{generate_kicad_sym, snapped_pins, SnappedPin, SymbolProperties, SymbolStyle};
+
+/// 3D model reference in a footprint `SVGNODE` shape
+/// (`SVGNODE~{"attrs": {"c_etype": "outline3D", "uuid": ..., "title": ...}}`).
+#[derive(Debug, Deserialize)]
+struct SvgNode {
+    attrs: SvgNodeAttrs,
+}
+
+#[derive(Debug, Deserialize)]
+struct SvgNodeAttrs {
+    #[serde(default)]
+    c_etype: Option<String>,
+    #[serde(default)]
+    uuid: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// A 3D model found in the footprint shapes.
+#[derive(Debug, PartialEq)]
+struct SvgNodeModel {
+    uuid: String,
+    title: Option<String>,
+}
+
+/// Find the 3D outline `SVGNODE` among footprint shapes.
+fn svgnode_model(shapes: &[String]) -> Option<SvgNodeModel> {
+    shapes.iter().find_map(|shape| {
+        let json = shape.strip_prefix("SVGNODE~")?;
+        let attrs = serde_json::from_str::<SvgNode>(json).ok()?.attrs;
+        if attrs.c_etype.as_deref() != Some("outline3D") {
+            return None;
+        }
+        Some(SvgNodeModel {
+            uuid: attrs.uuid.filter(|u| !u.is_empty())?,
+            title: attrs.title.filter(|t| !t.is_empty()),
+        })
+    })
+}
 
 /// A component pin with number and name.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +71,12 @@ pub struct Pin {
     pub name: String,
 }
 
+/// Source hash fields describing how a file was generated: the tool version
+/// and the output settings, ahead of the EasyEDA data itself.
+fn generator_fields(settings: &[String]) -> impl Iterator<Item = &str> {
+    std::iter::once(env!("CARGO_PKG_VERSION")).chain(settings.iter().map(String::as_str))
+}
+
 /// Component metadata from EasyEDA.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ComponentMeta {
@@ -33,12 +86,20 @@ pub struct ComponentMeta {
     pub footprint_name: Option<String>,
     /// 3D model name (if available).
     pub model_3d: Option<String>,
+    /// EasyEDA 3D model UUID, when the footprint references its model
+    /// through an `SVGNODE` shape.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_3d_uuid: Option<String>,
     /// Raw footprint shapes from EasyEDA (for generating .kicad_mod).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub footprint_shapes: Vec<String>,
-    /// Raw symbol shapes from EasyEDA (for generating .kicad_sym).
+    /// Raw symbol shapes from EasyEDA (for generating .kicad_sym); all
+    /// units combined for multi-part symbols.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub symbol_shapes: Vec<String>,
+    /// Symbol shapes per unit, for multi-part symbols only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbol_units: Vec<Vec<String>>,
 }
 
 impl ComponentMeta {
@@ -56,6 +117,17 @@ impl ComponentMeta {
             }
         }
 
+        // Multi-part symbols keep each unit's shapes in a subpart
+        let units: Vec<Vec<String>> = data
+            .subparts
+            .iter()
+            .filter_map(|sub| sub.data_str.as_ref()?.shape.clone())
+            .collect();
+        if units.len() > 1 {
+            meta.symbol_shapes = units.concat();
+            meta.symbol_units = units;
+        }
+
         if let Some(ref pkg) = data.package_detail {
             meta.footprint_name = Some(pkg.title.clone());
 
@@ -76,27 +148,104 @@ impl ComponentMeta {
             }
         }
 
+        // Some footprints only reference their 3D model from an SVGNODE shape
+        if meta.model_3d.is_none() {
+            if let Some(model) = svgnode_model(&meta.footprint_shapes) {
+                meta.model_3d = model.title;
+                meta.model_3d_uuid = Some(model.uuid);
+            }
+        }
+
         meta
     }
 
     /// Generate KiCad .kicad_mod file content from stored footprint shapes.
-    pub fn generate_footprint(&self) -> Option<String> {
+    pub fn generate_footprint(
+        &self,
+        version: KicadVersion,
+        placement: RefPlacement,
+        origin: FootprintOrigin,
+    ) -> Option<String> {
         let name = self.footprint_name.as_ref()?;
         if self.footprint_shapes.is_empty() {
             return None;
         }
 
-        let (pads, lines) = parse_footprint_shapes(&self.footprint_shapes);
+        let (pads, lines) = parse_footprint_shapes(&self.footprint_shapes, false);
         if pads.is_empty() {
             return None;
         }
 
-        generate_kicad_mod(name, &pads, &lines).ok()
+        let settings = [
+            format!("{:?}", version),
+            format!("{:?}", placement),
+            format!("{:?}", origin),
+        ];
+        let source = source_hash(
+            generator_fields(&settings)
+                .chain(std::iter::once(name.as_str()))
+                .chain(self.footprint_shapes.iter().map(String::as_str)),
+        );
+        generate_kicad_mod(name, &pads, &lines, Some(&source), version, placement, origin).ok()
     }
 
     /// Generate KiCad .kicad_sym file content from stored symbol shapes.
-    pub fn generate_symbol(&self, name: &str, pins: &[Pin]) -> Option<String> {
-        generate_kicad_sym(name, pins, &self.symbol_shapes).ok()
+    ///
+    /// `footprint` is the `Library:Name` the symbol's Footprint property points at.
+    /// The footprint filter names the footprint generated alongside: its file
+    /// stem (`<name>.kicad_mod`) and the full EasyEDA name inside the file.
+    pub fn generate_symbol(
+        &self,
+        name: &str,
+        pins: &[Pin],
+        footprint: Option<&str>,
+        style: &SymbolStyle,
+        version: KicadVersion,
+    ) -> Option<String> {
+        let footprint_filters: Vec<&str> = match self.footprint_name.as_deref() {
+            Some(footprint) if footprint != name => vec![name, footprint],
+            Some(footprint) => vec![footprint],
+            None => Vec::new(),
+        };
+        let units = self.symbol_unit_shapes();
+        let settings = [
+            format!("{:?}", version),
+            format!("{:?}", style),
+            name.to_string(),
+            footprint.unwrap_or_default().to_string(),
+        ];
+        let source = source_hash(
+            generator_fields(&settings)
+                .chain(self.footprint_name.as_deref())
+                .chain(self.symbol_shapes.iter().map(String::as_str))
+                .chain(pins.iter().flat_map(|p| [p.number.as_str(), p.name.as_str()])),
+        );
+        let properties = SymbolProperties {
+            footprint_filters: &footprint_filters,
+            footprint,
+            source: Some(&source),
+        };
+        generate_kicad_sym(name, pins, units, &properties, style, version).ok()
+    }
+
+    /// Symbol pins drawn at odd angles, which the generated symbol snaps to
+    /// the nearest quarter turn.
+    pub fn snapped_pins(&self) -> Vec<SnappedPin> {
+        snapped_pins(self.symbol_unit_shapes())
+    }
+
+    /// EasyEDA shapes of each symbol unit (one unit for ordinary symbols).
+    fn symbol_unit_shapes(&self) -> &[Vec<String>] {
+        if self.symbol_units.is_empty() {
+            std::slice::from_ref(&self.symbol_shapes)
+        } else {
+            self.symbol_units.as_slice()
+        }
+    }
+
+    /// Short package label derived from the EasyEDA footprint name (e.g. `WLP-9`).
+    pub fn package_token(&self) -> Option<String> {
+        self.footprint_name.as_deref().map(package_token)
     }
 
     /// Get EasyEDA component URL.
@@ -106,3 +255,98 @@ impl ComponentMeta {
             .map(|uuid| format!("https://easyeda.com/component/{}", uuid))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Component whose footprint has no `3DModel` in `c_para` and references
+    /// its model from an SVGNODE outline instead.
+    const SVGNODE_COMPONENT: &str = r#"{
+        "uuid": "abc", "title": "TEST-PART", "lcsc": {"number": "C1234"},
+        "packageDetail": {"uuid": "def", "title": "SOT-23-3_L2.9-W1.3-P1.90-LS2.4-BR",
+            "dataStr": {
+                "head": {"c_para": {"package": "SOT-23-3_L2.9-W1.3-P1.90-LS2.4-BR"}},
+                "shape": [
+                    "PAD~RECT~4000~3000~6~6~1~~1~0~~0~pad1",
+                    "SVGNODE~{\"gId\":\"g1_outline\",\"nodeName\":\"g\",\"attrs\":{\"uuid\":\"0a1b2c3d4e5f\",\"c_etype\":\"outline3D\",\"title\":\"SOT-23-3_L2.9-W1.3-H1.0\"},\"childNodes\":[]}"
+                ]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_svgnode_3d_model() {
+        let data: ComponentData = serde_json::from_str(SVGNODE_COMPONENT).unwrap();
+        let meta = ComponentMeta::from_component_data(&data);
+        assert_eq!(meta.model_3d_uuid.as_deref(), Some("0a1b2c3d4e5f"));
+        assert_eq!(meta.model_3d.as_deref(), Some("SOT-23-3_L2.9-W1.3-H1.0"));
+
+        // Other SVGNODEs (e.g. silkscreen art) aren't 3D models
+        let shapes = vec![r#"SVGNODE~{"attrs":{"c_etype":"logo","uuid":"x"}}"#.to_string()];
+        assert_eq!(svgnode_model(&shapes), None);
+    }
+
+    #[test]
+    fn test_source_hash_covers_settings() {
+        let data: ComponentData = serde_json::from_str(SVGNODE_COMPONENT).unwrap();
+        let meta = ComponentMeta::from_component_data(&data);
+        let hash = |version, origin| {
+            let footprint = meta
+                .generate_footprint(version, RefPlacement::default(), origin)
+                .unwrap();
+            embedded_source_hash(&footprint).unwrap().to_string()
+        };
+
+        let default = hash(KicadVersion::V8, FootprintOrigin::default());
+        assert_eq!(default, hash(KicadVersion::V8, FootprintOrigin::default()));
+        assert_ne!(default, hash(KicadVersion::V7, FootprintOrigin::default()));
+        assert_ne!(default, hash(KicadVersion::V8, FootprintOrigin::Pin1));
+
+        let pins = [Pin {
+            number: "1".to_string(),
+            name: "A".to_string(),
+        }];
+        let symbol = |footprint, style: &SymbolStyle| {
+            let symbol = meta
+                .generate_symbol("TEST-PART", &pins, footprint, style, KicadVersion::V8)
+                .unwrap();
+            embedded_source_hash(&symbol).unwrap().to_string()
+        };
+        let plain = symbol(None, &SymbolStyle::BOX);
+        assert_ne!(plain, symbol(Some("JLCPCB:TEST-PART"), &SymbolStyle::BOX));
+        assert_ne!(plain, symbol(None, &SymbolStyle::IEC));
+    }
+
+    #[test]
+    fn test_footprint_filter_matches_generated_footprint() {
+        let data: ComponentData = serde_json::from_str(SVGNODE_COMPONENT).unwrap();
+        let meta = ComponentMeta::from_component_data(&data);
+        let footprint = meta
+            .generate_footprint(
+                KicadVersion::default(),
+                RefPlacement::default(),
+                FootprintOrigin::default(),
+            )
+            .unwrap();
+        let pins = [Pin {
+            number: "1".to_string(),
+            name: "A".to_string(),
+        }];
+        let symbol = meta
+            .generate_symbol("TEST-PART", &pins, None, &SymbolStyle::default(), KicadVersion::V8)
+            .unwrap();
+
+        let filters = symbol
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("(property \"ki_fp_filters\" \""))
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let filters: Vec<&str> = filters.split(' ').collect();
+        // The footprint file is `TEST-PART.kicad_mod`; KiCad lists it by stem
+        assert!(filters.contains(&"TEST-PART"), "{:?}", filters);
+        let inner = "SOT-23-3_L2.9-W1.3-P1.90-LS2.4-BR";
+        assert!(footprint.contains(&format!("(footprint \"{}\"", inner)));
+        assert!(filters.contains(&inner), "{:?}", filters);
+    }
+}
@@ -0,0 +1,141 @@
+//! Differential pair detection from pin names.
+//!
+//! Pins like `D+`/`D-` or `TX_P`/`TX_N` belong together; KiCad convention is
+//! to draw them next to each other on the symbol.
+
+/// Supply rail names whose `+`/`-` variants (`V+`/`V-`, `VCC+`) are rails,
+/// not a differential pair.
+const POWER_STEMS: [&str; 12] = [
+    "V", "VS", "VCC", "VDD", "VEE", "VSS", "VBAT", "VBUS", "VPP", "VM", "GND", "PWR",
+];
+
+/// Whether a `+`/`-` pair base names a supply rail, ignoring an analog or
+/// digital prefix and a trailing rail number (`AVDD`, `VCC2`).
+fn is_power_stem(base: &str) -> bool {
+    let stem = base.trim_end_matches(|c: char| c.is_ascii_digit() || c == '_');
+    let stem = stem.to_ascii_uppercase();
+    let unprefixed = stem
+        .strip_prefix(['A', 'D'])
+        .filter(|s| s.starts_with('V'))
+        .unwrap_or(&stem);
+    POWER_STEMS.contains(&stem.as_str()) || POWER_STEMS.contains(&unprefixed)
+}
+
+/// Split a pin name into its pair base and polarity (`true` for positive).
+///
+/// Recognizes a trailing `+`/`-` or `_P`/`_N` suffix. `+`/`-` on a supply
+/// rail name (`V+`, `VCC-`) isn't a pair.
+pub fn diff_pair_base(name: &str) -> Option<(&str, bool)> {
+    let (base, positive) = if let Some(base) = name.strip_suffix('+') {
+        if is_power_stem(base) {
+            return None;
+        }
+        (base, true)
+    } else if let Some(base) = name.strip_suffix('-') {
+        if is_power_stem(base) {
+            return None;
+        }
+        (base, false)
+    } else if let Some(base) = name.strip_suffix("_P").or_else(|| name.strip_suffix("_p")) {
+        (base, true)
+    } else if let Some(base) = name.strip_suffix("_N").or_else(|| name.strip_suffix("_n")) {
+        (base, false)
+    } else {
+        return None;
+    };
+
+    if base.is_empty() {
+        None
+    } else {
+        Some((base, positive))
+    }
+}
+
+/// Find differential pairs among pin names.
+///
+/// Returns `(positive, negative)` index pairs in order of the positive pin.
+/// Repeated names pair up in order, so a connector with two `D+`/`D-` pins
+/// yields two pairs.
+pub fn find_diff_pairs(names: &[&str]) -> Vec<(usize, usize)> {
+    let mut used = vec![false; names.len()];
+    let mut pairs = Vec::new();
+
+    for (i, name) in names.iter().enumerate() {
+        let Some((base, true)) = diff_pair_base(name) else {
+            continue;
+        };
+        let partner = (0..names.len())
+            .find(|&j| !used[j] && diff_pair_base(names[j]) == Some((base, false)));
+        if let Some(j) = partner {
+            used[i] = true;
+            used[j] = true;
+            pairs.push((i, j));
+        }
+    }
+
+    pairs
+}
+
+/// Order pin names so each differential pair is adjacent, positive first.
+///
+/// Returns indices into `names`. A pair takes the position of whichever
+/// member appears first; all other pins keep their relative order.
+pub fn pair_adjacent_order(names: &[&str]) -> Vec<usize> {
+    let mut partner: Vec<Option<usize>> = vec![None; names.len()];
+    for (p, n) in find_diff_pairs(names) {
+        partner[p] = Some(n);
+        partner[n] = Some(p);
+    }
+
+    let mut placed = vec![false; names.len()];
+    let mut order = Vec::with_capacity(names.len());
+    for i in 0..names.len() {
+        if placed[i] {
+            continue;
+        }
+        match partner[i] {
+            Some(j) => {
+                let (p, n) = if diff_pair_base(names[i]).is_some_and(|(_, pos)| pos) {
+                    (i, j)
+                } else {
+                    (j, i)
+                };
+                order.extend([p, n]);
+                placed[p] = true;
+                placed[n] = true;
+            }
+            None => {
+                order.push(i);
+                placed[i] = true;
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usb_pair_adjacent() {
+        let names = ["VBUS", "D-", "GND", "D+", "ID"];
+        assert_eq!(find_diff_pairs(&names), vec![(3, 1)]);
+        assert_eq!(pair_adjacent_order(&names), vec![0, 3, 1, 2, 4]);
+
+        assert_eq!(diff_pair_base("TX_P"), Some(("TX", true)));
+        assert_eq!(diff_pair_base("+"), None);
+        assert_eq!(pair_adjacent_order(&["V+", "GND"]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_supply_rails_are_not_pairs() {
+        assert_eq!(find_diff_pairs(&["V+", "IN-", "IN+", "V-"]), vec![(2, 1)]);
+        for rail in ["V+", "VCC-", "VDD+", "VEE-", "VS+", "AVDD-", "VCC2+"] {
+            assert_eq!(diff_pair_base(rail), None, "{}", rail);
+        }
+        assert_eq!(diff_pair_base("VOUT+"), Some(("VOUT", true)));
+        assert_eq!(diff_pair_base("D-"), Some(("D", false)));
+    }
+}
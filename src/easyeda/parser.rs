@@ -1,5 +1,7 @@
 //! Parser for EasyEDA symbol pin data.
 
+use std::collections::HashSet;
+
 use super::Pin;
 
 /// Parse pins from EasyEDA symbol shape data.
@@ -13,7 +15,10 @@ use super::Pin;
 /// - Segment 0: Settings (spice pin number at index 3)
 /// - Segment 3: Pin name (at index 4)
 /// - Segment 4: Display pin number (at index 4)
-pub fn parse_symbol_pins(shapes: &[String]) -> Vec<Pin> {
+///
+/// Pins are sorted by number unless `preserve_order` is set, which keeps the
+/// order they're listed in (the physical order for many connectors).
+pub fn parse_symbol_pins(shapes: &[String], preserve_order: bool) -> Vec<Pin> {
     let mut pins = Vec::new();
 
     for shape in shapes {
@@ -28,13 +33,19 @@ pub fn parse_symbol_pins(shapes: &[String]) -> Vec<Pin> {
     }
 
     // Sort pins by number (alphanumeric sort for BGA-style pins like A1, B2)
-    pins.sort_by(|a, b| {
-        // Try numeric sort first, fall back to alphanumeric
-        match (a.number.parse::<u32>(), b.number.parse::<u32>()) {
-            (Ok(na), Ok(nb)) => na.cmp(&nb),
-            _ => alphanum_sort(&a.number, &b.number),
-        }
-    });
+    if !preserve_order {
+        pins.sort_by(|a, b| {
+            // Try numeric sort first, fall back to alphanumeric
+            match (a.number.parse::<u32>(), b.number.parse::<u32>()) {
+                (Ok(na), Ok(nb)) => na.cmp(&nb),
+                _ => alphanum_sort(&a.number, &b.number),
+            }
+        });
+    }
+
+    // Multi-part symbols may draw a shared pin (e.g. power) in every unit
+    let mut seen = HashSet::new();
+    pins.retain(|p| seen.insert(p.number.clone()));
 
     pins
 }
@@ -83,7 +94,7 @@ fn alphanum_sort(a: &str, b: &str) -> std::cmp::Ordering {
     let (a_prefix, a_num) = split_alphanum(a);
     let (b_prefix, b_num) = split_alphanum(b);
 
-    match a_prefix.cmp(&b_prefix) {
+    match a_prefix.cmp(b_prefix) {
         std::cmp::Ordering::Equal => a_num.cmp(&b_num),
         other => other,
     }
@@ -117,7 +128,7 @@ mod tests {
             "R~340~270~2~2~120~70~#880000~1~0~none~gge79~0~".to_string(), // Rectangle, should be skipped
         ];
 
-        let pins = parse_symbol_pins(&shapes);
+        let pins = parse_symbol_pins(&shapes, false);
 
         assert_eq!(pins.len(), 2);
         assert_eq!(pins[0].number, "A1");
@@ -125,4 +136,22 @@ mod tests {
         assert_eq!(pins[1].number, "A2");
         assert_eq!(pins[1].name, "VDD");
     }
+
+    #[test]
+    fn test_preserve_pin_order() {
+        // Edge connector listing its odd pins first, then the even ones
+        let shapes: Vec<String> = ["1", "3", "2", "4", "3"]
+            .iter()
+            .map(|n| {
+                format!(
+                    "P~show~0~{n}~300~280~180~gge{n}~0^^300~280^^M 300 280 h 20~#880000\
+                     ^^1~322~283~0~PIN{n}~start~~~#0000FF^^1~315~279~0~{n}~end~~~#0000FF"
+                )
+            })
+            .collect();
+
+        let numbers = |pins: Vec<Pin>| pins.into_iter().map(|p| p.number).collect::<Vec<_>>();
+        assert_eq!(numbers(parse_symbol_pins(&shapes, false)), ["1", "2", "3", "4"]);
+        assert_eq!(numbers(parse_symbol_pins(&shapes, true)), ["1", "3", "2", "4"]);
+    }
 }
@@ -0,0 +1,55 @@
+//! Source hashes embedded in generated KiCad files.
+//!
+//! Each generated `.kicad_mod`/`.kicad_sym` records a hash of the EasyEDA
+//! data it was made from, together with the tool version and the settings
+//! that shape the output. When regenerating, a file whose content differs
+//! from the new output although its source hash is unchanged was most likely
+//! edited by hand.
+
+/// Prefix marking the hash inside generated files.
+const PREFIX: &str = "fnv1a:";
+
+/// Hex digits of the 64-bit hash.
+const HASH_DIGITS: usize = 16;
+
+/// Deterministic hash of EasyEDA input fields (FNV-1a, 64-bit).
+///
+/// Stable across runs and Rust versions, unlike `DefaultHasher`. Fields are
+/// separated so `["ab", "c"]` and `["a", "bc"]` hash differently.
+pub fn source_hash<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for field in fields {
+        for byte in field.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{}{:016x}", PREFIX, hash)
+}
+
+/// The source hash embedded in generated file content, if any.
+pub fn embedded_source_hash(content: &str) -> Option<&str> {
+    let start = content.find(PREFIX)?;
+    let hash = content.get(start..start + PREFIX.len() + HASH_DIGITS)?;
+    hash[PREFIX.len()..]
+        .bytes()
+        .all(|b| b.is_ascii_hexdigit())
+        .then_some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_hash_roundtrip() {
+        let hash = source_hash(["R_0402", "PAD~RECT~4000~3000~6~6~1~~1~0~~0~pad1"]);
+        assert_eq!(hash, source_hash(["R_0402", "PAD~RECT~4000~3000~6~6~1~~1~0~~0~pad1"]));
+        assert_ne!(source_hash(["ab", "c"]), source_hash(["a", "bc"]));
+
+        let content = format!("(footprint \"X\"\n  (descr \"pcb-jlcpcb source {}\")\n)", hash);
+        assert_eq!(embedded_source_hash(&content), Some(hash.as_str()));
+        assert_eq!(embedded_source_hash("(footprint \"X\")"), None);
+        assert_eq!(embedded_source_hash("fnv1a:xyz"), None);
+    }
+}
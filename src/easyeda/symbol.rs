@@ -3,13 +3,72 @@
 use anyhow::Result;
 use std::fmt::Write;
 
-use super::Pin;
+use super::pairs::pair_adjacent_order;
+use super::{KicadVersion, Pin};
 
 /// EasyEDA to KiCad coordinate conversion factor.
 /// EasyEDA uses 10 mil units, KiCad uses mm.
 const EASYEDA_TO_MM: f64 = 0.254;
 
+/// Pin pitch EasyEDA symbols are drawn on, in mm.
+const EASYEDA_PIN_PITCH: f64 = 2.54;
+
+/// How a generated symbol's body and pins are laid out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolStyle {
+    /// Distance between neighbouring pins in mm; EasyEDA pin positions are
+    /// scaled from their 2.54 mm pitch to this
+    pub pin_spacing: f64,
+    /// Gap between the outermost pins and the body outline in mm
+    pub box_margin: f64,
+    /// Fill the body with the background colour
+    pub filled: bool,
+}
+
+impl SymbolStyle {
+    /// Filled rectangle on the 2.54 mm grid (the default).
+    pub const BOX: Self = Self {
+        pin_spacing: 2.54,
+        box_margin: 2.54,
+        filled: true,
+    };
+
+    /// IEC 60617 style: unfilled outline on a 5.08 mm pin grid.
+    pub const IEC: Self = Self {
+        pin_spacing: 5.08,
+        box_margin: 1.27,
+        filled: false,
+    };
+
+    /// Look up a style by name (`box` or `iec`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => Some(Self::BOX),
+            "iec" => Some(Self::IEC),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SymbolStyle {
+    fn default() -> Self {
+        Self::BOX
+    }
+}
+
+/// Property values written into a generated symbol.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SymbolProperties<'a> {
+    /// Footprint names written as the footprint filter
+    pub footprint_filters: &'a [&'a str],
+    /// Footprint the symbol points at (`Library:Name`)
+    pub footprint: Option<&'a str>,
+    /// Hash of the EasyEDA input, kept in a hidden property
+    pub source: Option<&'a str>,
+}
+
 /// Parsed rectangle from EasyEDA symbol.
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct SymbolRect {
     x: f64,
@@ -19,6 +78,7 @@ struct SymbolRect {
 }
 
 /// Parsed pin with position from EasyEDA symbol.
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct SymbolPin {
     number: String,
@@ -112,41 +172,130 @@ fn parse_pin_with_position(shape: &str) -> Option<SymbolPin> {
     })
 }
 
-/// Generate KiCad .kicad_sym file content.
-pub fn generate_kicad_sym(name: &str, pins: &[Pin], shapes: &[String]) -> Result<String> {
-    let mut out = String::new();
+/// Pins and placement for one unit of a symbol.
+struct UnitLayout<'a> {
+    pins: Vec<&'a Pin>,
+    positions: std::collections::HashMap<String, SymbolPin>,
+    /// Stacking order for pins without a parsed position
+    fallback_slot: Vec<usize>,
+    center_x: f64,
+    center_y: f64,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+}
 
-    // Parse shapes for positions
-    let (_rects, symbol_pins) = parse_symbol_shapes(shapes);
+impl<'a> UnitLayout<'a> {
+    fn new(pins: Vec<&'a Pin>, shapes: &[String], style: &SymbolStyle) -> Self {
+        // Parse shapes for positions, scaled to the style's pin spacing
+        let (_rects, mut symbol_pins) = parse_symbol_shapes(shapes);
+        let scale = style.pin_spacing / EASYEDA_PIN_PITCH;
+        for pin in &mut symbol_pins {
+            pin.x *= scale;
+            pin.y *= scale;
+        }
 
-    // Create a map of pin number -> position
-    let pin_positions: std::collections::HashMap<&str, &SymbolPin> = symbol_pins
+        // Fallback stacking order for pins without a parsed position, keeping
+        // differential pairs (D+/D-, TX_P/TX_N) adjacent
+        let names: Vec<&str> = pins.iter().map(|p| p.name.as_str()).collect();
+        let mut fallback_slot = vec![0; pins.len()];
+        for (slot, idx) in pair_adjacent_order(&names).into_iter().enumerate() {
+            fallback_slot[idx] = slot;
+        }
+
+        // Calculate bounding box from pins
+        let (raw_min_x, raw_max_x, raw_min_y, raw_max_y) = calculate_bounds(&symbol_pins);
+
+        // Center offset to move symbol to origin
+        let center_x = (raw_min_x + raw_max_x) / 2.0;
+        let center_y = (raw_min_y + raw_max_y) / 2.0;
+
+        Self {
+            pins,
+            positions: symbol_pins
+                .into_iter()
+                .map(|p| (p.number.clone(), p))
+                .collect(),
+            fallback_slot,
+            center_x,
+            center_y,
+            // Centered bounds
+            min_x: raw_min_x - center_x,
+            max_x: raw_max_x - center_x,
+            min_y: raw_min_y - center_y,
+            max_y: raw_max_y - center_y,
+        }
+    }
+}
+
+/// Split pins into symbol units.
+///
+/// `units` holds the EasyEDA shapes of each unit (one list for ordinary
+/// symbols). A pin belongs to the unit whose shapes draw it; pins no unit
+/// draws go to the first.
+fn unit_layouts<'a>(
+    pins: &'a [Pin],
+    units: &[Vec<String>],
+    style: &SymbolStyle,
+) -> Vec<UnitLayout<'a>> {
+    if units.len() <= 1 {
+        let shapes = units.first().map(Vec::as_slice).unwrap_or_default();
+        return vec![UnitLayout::new(pins.iter().collect(), shapes, style)];
+    }
+
+    let drawn: Vec<Vec<String>> = units
         .iter()
-        .map(|p| (p.number.as_str(), p))
+        .map(|shapes| {
+            let (_, symbol_pins) = parse_symbol_shapes(shapes);
+            symbol_pins.into_iter().map(|p| p.number).collect()
+        })
         .collect();
+    let mut unit_pins: Vec<Vec<&Pin>> = vec![Vec::new(); units.len()];
+    for pin in pins {
+        let unit = drawn.iter().position(|numbers| numbers.contains(&pin.number));
+        unit_pins[unit.unwrap_or(0)].push(pin);
+    }
 
-    // Calculate bounding box from pins
-    let (raw_min_x, raw_max_x, raw_min_y, raw_max_y) = calculate_bounds(&symbol_pins);
+    unit_pins
+        .into_iter()
+        .zip(units)
+        .map(|(pins, shapes)| UnitLayout::new(pins, shapes, style))
+        .collect()
+}
+
+/// Generate KiCad .kicad_sym file content.
+///
+/// `units` holds the EasyEDA symbol shapes per unit; more than one produces
+/// a multi-unit symbol (resistor arrays, dual gates).
+pub fn generate_kicad_sym(
+    name: &str,
+    pins: &[Pin],
+    units: &[Vec<String>],
+    properties: &SymbolProperties,
+    style: &SymbolStyle,
+    version: KicadVersion,
+) -> Result<String> {
+    let mut out = String::new();
 
-    // Center offset to move symbol to origin
-    let center_x = (raw_min_x + raw_max_x) / 2.0;
-    let center_y = (raw_min_y + raw_max_y) / 2.0;
+    let layouts = unit_layouts(pins, units, style);
+    let (min_y, max_y) = (layouts[0].min_y, layouts[0].max_y);
 
-    // Centered bounds
-    let min_x = raw_min_x - center_x;
-    let max_x = raw_max_x - center_x;
-    let min_y = raw_min_y - center_y;
-    let max_y = raw_max_y - center_y;
+    let box_margin = style.box_margin;
 
-    let box_margin = 2.54;
+    let hide = version.hide_token();
 
     writeln!(out, "(kicad_symbol_lib")?;
-    writeln!(out, "  (version 20231120)")?;
+    writeln!(out, "  (version {})", version.symbol_version())?;
     writeln!(out, "  (generator \"pcb-jlcpcb\")")?;
-    writeln!(out, "  (generator_version \"1.0\")")?;
+    if version.has_generator_version() {
+        writeln!(out, "  (generator_version \"1.0\")")?;
+    }
     writeln!(out, "  (symbol \"{name}\"")?;
     writeln!(out, "    (pin_names (offset 1.016))")?;
-    writeln!(out, "    (exclude_from_sim no)")?;
+    if version.has_exclude_from_sim() {
+        writeln!(out, "    (exclude_from_sim no)")?;
+    }
     writeln!(out, "    (in_bom yes)")?;
     writeln!(out, "    (on_board yes)")?;
 
@@ -157,61 +306,140 @@ pub fn generate_kicad_sym(name: &str, pins: &[Pin], shapes: &[String]) -> Result
     writeln!(out, "    (property \"Value\" \"{name}\" (at 0 {} 0)", min_y - box_margin - 1.27)?;
     writeln!(out, "      (effects (font (size 1.27 1.27)))")?;
     writeln!(out, "    )")?;
-    writeln!(out, "    (property \"Footprint\" \"\" (at 0 0 0)")?;
-    writeln!(out, "      (effects (font (size 1.27 1.27)) hide)")?;
+    let footprint = properties.footprint.unwrap_or_default();
+    writeln!(out, "    (property \"Footprint\" \"{footprint}\" (at 0 0 0)")?;
+    writeln!(out, "      (effects (font (size 1.27 1.27)) {hide})")?;
     writeln!(out, "    )")?;
     writeln!(out, "    (property \"Datasheet\" \"\" (at 0 0 0)")?;
-    writeln!(out, "      (effects (font (size 1.27 1.27)) hide)")?;
+    writeln!(out, "      (effects (font (size 1.27 1.27)) {hide})")?;
     writeln!(out, "    )")?;
+    if !properties.footprint_filters.is_empty() {
+        let filters = properties.footprint_filters.join(" ");
+        writeln!(out, "    (property \"ki_fp_filters\" \"{filters}\" (at 0 0 0)")?;
+        writeln!(out, "      (effects (font (size 1.27 1.27)) {hide})")?;
+        writeln!(out, "    )")?;
+    }
+    if let Some(source) = properties.source {
+        writeln!(out, "    (property \"JLCPCB_Source\" \"{source}\" (at 0 0 0)")?;
+        writeln!(out, "      (effects (font (size 1.27 1.27)) {hide})")?;
+        writeln!(out, "    )")?;
+    }
 
-    // Symbol body (rectangle)
-    writeln!(out, "    (symbol \"{name}_0_1\"")?;
+    if let [layout] = layouts.as_slice() {
+        // Shared body (unit 0) plus the pins of the only unit
+        writeln!(out, "    (symbol \"{name}_0_1\"")?;
+        write_body(&mut out, layout, style)?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (symbol \"{name}_1_1\"")?;
+        write_unit_pins(&mut out, layout, style)?;
+        writeln!(out, "    )")?;
+    } else {
+        for (i, layout) in layouts.iter().enumerate() {
+            writeln!(out, "    (symbol \"{name}_{}_1\"", i + 1)?;
+            write_body(&mut out, layout, style)?;
+            write_unit_pins(&mut out, layout, style)?;
+            writeln!(out, "    )")?;
+        }
+    }
+
+    writeln!(out, "  )")?;
+    writeln!(out, ")")?;
+
+    Ok(out)
+}
+
+/// Write a unit's body rectangle.
+fn write_body(out: &mut String, layout: &UnitLayout, style: &SymbolStyle) -> Result<()> {
+    let box_margin = style.box_margin;
     writeln!(
         out,
         "      (rectangle (start {:.4} {:.4}) (end {:.4} {:.4})",
-        min_x - box_margin,
-        max_y + box_margin,
-        max_x + box_margin,
-        min_y - box_margin
+        layout.min_x - box_margin,
+        layout.max_y + box_margin,
+        layout.max_x + box_margin,
+        layout.min_y - box_margin
     )?;
     writeln!(out, "        (stroke (width 0.254) (type default))")?;
-    writeln!(out, "        (fill (type background))")?;
+    let fill = if style.filled { "background" } else { "none" };
+    writeln!(out, "        (fill (type {fill}))")?;
     writeln!(out, "      )")?;
-    writeln!(out, "    )")?;
-
-    // Symbol pins
-    writeln!(out, "    (symbol \"{name}_1_1\"")?;
+    Ok(())
+}
 
-    for pin in pins {
+/// Write a unit's pins around its body.
+fn write_unit_pins(out: &mut String, layout: &UnitLayout, style: &SymbolStyle) -> Result<()> {
+    let box_margin = style.box_margin;
+    let UnitLayout {
+        center_x,
+        center_y,
+        min_x,
+        max_x,
+        min_y,
+        max_y,
+        ..
+    } = *layout;
+
+    for (i, pin) in layout.pins.iter().enumerate() {
         // Try to get position from parsed shapes, or calculate default
-        let (pin_x, pin_y, angle) = if let Some(sp) = pin_positions.get(pin.number.as_str()) {
+        let (pin_x, pin_y, angle) = if let Some(sp) = layout.positions.get(&pin.number) {
             // Apply centering offset to pin position
             let centered_y = sp.y - center_y;
 
+            let rotation = snap_rotation(sp.rotation);
+
             // Determine which side of the box this pin is on based on rotation
-            let (x, y, a) = match sp.rotation as i32 {
-                0 => (max_x + box_margin + 2.54, centered_y, 180.0),   // Right side, points left
+            let (x, y, a) = match rotation {
                 90 => (sp.x - center_x, min_y - box_margin - 2.54, 90.0),   // Bottom, points up
                 180 => (min_x - box_margin - 2.54, centered_y, 0.0),   // Left side, points right
                 270 => (sp.x - center_x, max_y + box_margin + 2.54, 270.0), // Top, points down
-                _ => (max_x + box_margin + 2.54, centered_y, 180.0),
+                _ => (max_x + box_margin + 2.54, centered_y, 180.0),   // Right side, points left
             };
             (x, y, a)
         } else {
             // Default position: stack on the left
-            let idx = pins.iter().position(|p| p.number == pin.number).unwrap_or(0);
-            let y = max_y - (idx as f64 * 2.54);
+            let y = max_y - (layout.fallback_slot[i] as f64 * style.pin_spacing);
             (min_x - box_margin - 2.54, y, 0.0)
         };
 
-        write_pin(&mut out, &pin.number, &pin.name, pin_x, pin_y, angle)?;
+        write_pin(out, &pin.number, &pin.name, pin_x, pin_y, angle)?;
     }
 
-    writeln!(out, "    )")?;
-    writeln!(out, "  )")?;
-    writeln!(out, ")")?;
+    Ok(())
+}
 
-    Ok(out)
+/// A pin EasyEDA draws at an angle that isn't a multiple of 90°.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnappedPin {
+    pub number: String,
+    /// Rotation in the EasyEDA symbol, in degrees
+    pub drawn: f64,
+    /// Rotation [`generate_kicad_sym`] places it at
+    pub placed: i32,
+}
+
+/// Pins that [`generate_kicad_sym`] snaps to the nearest quarter turn.
+pub fn snapped_pins(units: &[Vec<String>]) -> Vec<SnappedPin> {
+    units
+        .iter()
+        .flat_map(|shapes| parse_symbol_shapes(shapes).1)
+        .filter_map(|pin| {
+            let placed = snap_rotation(pin.rotation);
+            (f64::from(placed) != pin.rotation).then_some(SnappedPin {
+                number: pin.number,
+                drawn: pin.rotation,
+                placed,
+            })
+        })
+        .collect()
+}
+
+/// Snap an EasyEDA pin rotation to the nearest of 0/90/180/270 degrees.
+///
+/// Pins at odd angles (e.g. 45°) would otherwise all land on one side of the
+/// body, on top of each other.
+fn snap_rotation(rotation: f64) -> i32 {
+    let quarter_turns = (rotation / 90.0).round() as i32;
+    quarter_turns.rem_euclid(4) * 90
 }
 
 /// Calculate bounding box from pin positions.
@@ -243,15 +471,16 @@ fn calculate_bounds(pins: &[SymbolPin]) -> (f64, f64, f64, f64) {
 /// Write a single pin to the output.
 fn write_pin(out: &mut String, number: &str, name: &str, x: f64, y: f64, angle: f64) -> Result<()> {
     // Determine pin type based on name
-    let pin_type = if name.contains("VCC") || name.contains("VDD") || name.contains("VIN") {
-        "power_in"
-    } else if name.contains("GND") || name.contains("VSS") {
+    let pin_type = if name.contains("VCC")
+        || name.contains("VDD")
+        || name.contains("VIN")
+        || name.contains("GND")
+        || name.contains("VSS")
+    {
         "power_in"
     } else if name.contains("OUT") {
         "output"
-    } else if name.contains("IN") || name.contains("DIN") {
-        "input"
-    } else if name.contains("CLK") || name.contains("BCLK") || name.contains("LRCLK") {
+    } else if name.contains("IN") || name.contains("DIN") || name.contains("CLK") {
         "input"
     } else {
         "bidirectional"
@@ -261,6 +490,7 @@ fn write_pin(out: &mut String, number: &str, name: &str, x: f64, y: f64, angle:
         out,
         "      (pin {pin_type} line (at {x:.4} {y:.4} {angle:.0}) (length 2.54)"
     )?;
+    let name = kicad_pin_name(name);
     writeln!(out, "        (name \"{name}\" (effects (font (size 1.27 1.27))))")?;
     writeln!(out, "        (number \"{number}\" (effects (font (size 1.27 1.27))))")?;
     writeln!(out, "      )")?;
@@ -268,9 +498,50 @@ fn write_pin(out: &mut String, number: &str, name: &str, x: f64, y: f64, angle:
     Ok(())
 }
 
+/// Convert active-low pin name conventions (`~CS`, `!CS`, `RESET#`) to KiCad's
+/// overbar notation (`~{CS}`), which KiCad draws as a bar over the name.
+fn kicad_pin_name(name: &str) -> String {
+    let base = name
+        .strip_prefix(['~', '!'])
+        .or_else(|| name.strip_suffix('#'))
+        .filter(|base| !base.is_empty() && !base.starts_with('{'));
+
+    match base {
+        Some(base) => format!("~{{{}}}", base),
+        None => name.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::easyeda::{parse_symbol_pins, ComponentData, ComponentMeta};
+
+    fn plain_symbol(pins: &[Pin], version: KicadVersion) -> String {
+        generate_kicad_sym(
+            "TEST",
+            pins,
+            &[],
+            &SymbolProperties::default(),
+            &SymbolStyle::default(),
+            version,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_kicad_pin_name_overbar() {
+        assert_eq!(kicad_pin_name("~CS"), "~{CS}");
+        assert_eq!(kicad_pin_name("!WR"), "~{WR}");
+        assert_eq!(kicad_pin_name("RESET#"), "~{RESET}");
+        assert_eq!(kicad_pin_name("~{CS}"), "~{CS}");
+        assert_eq!(kicad_pin_name("VCC"), "VCC");
+        assert_eq!(kicad_pin_name("#"), "#");
+
+        let pins = vec![Pin { number: "1".to_string(), name: "~CS".to_string() }];
+        let result = plain_symbol(&pins, KicadVersion::default());
+        assert!(result.contains("(name \"~{CS}\""));
+    }
 
     #[test]
     fn test_generate_simple_symbol() {
@@ -278,9 +549,168 @@ mod tests {
             Pin { number: "1".to_string(), name: "GND".to_string() },
             Pin { number: "2".to_string(), name: "VCC".to_string() },
         ];
-        let result = generate_kicad_sym("TEST", &pins, &[]).unwrap();
+        let result = plain_symbol(&pins, KicadVersion::default());
         assert!(result.contains("(symbol \"TEST\""));
         assert!(result.contains("GND"));
         assert!(result.contains("VCC"));
+        assert!(result.contains("(property \"Footprint\" \"\""));
+
+        let result = generate_kicad_sym(
+            "TEST",
+            &pins,
+            &[],
+            &SymbolProperties {
+                footprint: Some("JLCPCB:TEST"),
+                ..Default::default()
+            },
+            &SymbolStyle::default(),
+            KicadVersion::default(),
+        )
+        .unwrap();
+        assert!(result.contains("(property \"Footprint\" \"JLCPCB:TEST\""));
+    }
+
+    #[test]
+    fn test_fallback_layout_keeps_diff_pair_adjacent() {
+        let pins = vec![
+            Pin { number: "1".to_string(), name: "D-".to_string() },
+            Pin { number: "2".to_string(), name: "GND".to_string() },
+            Pin { number: "3".to_string(), name: "D+".to_string() },
+        ];
+        let result = plain_symbol(&pins, KicadVersion::default());
+        let pin_at = |name: &str| {
+            let marker = format!("(name \"{name}\"");
+            let end = result.find(&marker).unwrap();
+            let start = result[..end].rfind("(at ").unwrap();
+            result[start..end].split_whitespace().nth(2).unwrap().to_string()
+        };
+        assert_eq!(pin_at("D+"), "5.0800");
+        assert_eq!(pin_at("D-"), "2.5400");
+        assert_eq!(pin_at("GND"), "0.0000");
+    }
+
+    #[test]
+    fn test_symbol_kicad_versions() {
+        let pins = vec![Pin { number: "1".to_string(), name: "GND".to_string() }];
+
+        let v7 = plain_symbol(&pins, KicadVersion::V7);
+        assert!(v7.contains("(version 20220914)"));
+        assert!(!v7.contains("generator_version"));
+        assert!(!v7.contains("exclude_from_sim"));
+
+        let v9 = plain_symbol(&pins, KicadVersion::V9);
+        assert!(v9.contains("(version 20241209)"));
+        assert!(v9.contains("(hide yes)"));
+    }
+
+    /// EasyEDA pin shape for a test fixture.
+    fn pin_shape(number: &str, name: &str, x: i32, y: i32, rotation: i32) -> String {
+        format!(
+            "P~show~0~{number}~{x}~{y}~{rotation}~gge{number}~0^^{x}~{y}^^M {x} {y} h 20~#880000\
+             ^^1~{x}~{y}~0~{name}~start~~~#0000FF^^1~{x}~{y}~0~{number}~end~~~#0000FF\
+             ^^0~{x}~{y}^^0~M {x} {y} L {x} {y}"
+        )
+    }
+
+    #[test]
+    fn test_symbol_style_pin_spacing() {
+        let pins = vec![
+            Pin { number: "1".to_string(), name: "A".to_string() },
+            Pin { number: "2".to_string(), name: "B".to_string() },
+        ];
+        // Drawn 10 EasyEDA units (2.54 mm) apart
+        let shapes = vec![pin_shape("1", "A", 0, 0, 0), pin_shape("2", "B", 0, 10, 0)];
+        let style = SymbolStyle {
+            pin_spacing: 3.81,
+            ..SymbolStyle::BOX
+        };
+        let pin_ys = |sym: &str| -> Vec<f64> {
+            sym.lines()
+                .filter(|l| l.trim_start().starts_with("(pin "))
+                .map(|l| {
+                    let at = &l[l.find("(at ").unwrap() + 4..];
+                    at.split_whitespace().nth(1).unwrap().parse().unwrap()
+                })
+                .collect()
+        };
+        let generate = |shapes: &[Vec<String>], style: &SymbolStyle| {
+            let props = SymbolProperties::default();
+            generate_kicad_sym("T", &pins, shapes, &props, style, KicadVersion::default()).unwrap()
+        };
+
+        let drawn = pin_ys(&generate(&[shapes], &style));
+        assert!(((drawn[0] - drawn[1]).abs() - 3.81).abs() < 1e-3, "{:?}", drawn);
+        let stacked = pin_ys(&generate(&[], &style));
+        assert!(((stacked[0] - stacked[1]).abs() - 3.81).abs() < 1e-3, "{:?}", stacked);
+
+        let iec = generate(&[], &SymbolStyle::IEC);
+        assert!(iec.contains("(fill (type none))"));
+        assert_eq!(SymbolStyle::from_name("iec"), Some(SymbolStyle::IEC));
+        assert_eq!(SymbolStyle::from_name("fancy"), None);
+    }
+
+    #[test]
+    fn test_snap_rotation() {
+        assert_eq!(snap_rotation(0.0), 0);
+        assert_eq!(snap_rotation(45.0), 90);
+        assert_eq!(snap_rotation(200.0), 180);
+        assert_eq!(snap_rotation(-90.0), 270);
+        assert_eq!(snap_rotation(350.0), 0);
+
+        // A 45° pin goes on the bottom edge instead of the right
+        let shapes = vec![pin_shape("1", "IN", 300, 300, 45), pin_shape("2", "OUT", 340, 300, 0)];
+        let pins = parse_symbol_pins(&shapes, false);
+        let sym = generate_kicad_sym(
+            "T",
+            &pins,
+            &[shapes],
+            &SymbolProperties::default(),
+            &SymbolStyle::default(),
+            KicadVersion::default(),
+        )
+        .unwrap();
+        let pin1 = sym.lines().find(|l| l.contains("(pin ") && l.ends_with("90) (length 2.54)"));
+        assert!(pin1.is_some(), "{}", sym);
+
+        let shapes = vec![pin_shape("1", "IN", 300, 300, 45), pin_shape("2", "OUT", 340, 300, 0)];
+        assert_eq!(
+            snapped_pins(&[shapes]),
+            [SnappedPin {
+                number: "1".to_string(),
+                drawn: 45.0,
+                placed: 90
+            }]
+        );
+    }
+
+    #[test]
+    fn test_two_unit_array() {
+        // A dual resistor array: EasyEDA draws each element as a subpart
+        let data: ComponentData = serde_json::from_value(serde_json::json!({
+            "uuid": "abc",
+            "title": "4D02WGJ0103TCE",
+            "dataStr": { "shape": [] },
+            "subparts": [
+                { "dataStr": { "shape": [pin_shape("1", "R1A", 380, 300, 180), pin_shape("4", "R1B", 420, 300, 0)] } },
+                { "dataStr": { "shape": [pin_shape("2", "R2A", 380, 300, 180), pin_shape("3", "R2B", 420, 300, 0)] } },
+            ],
+        }))
+        .unwrap();
+        let meta = ComponentMeta::from_component_data(&data);
+        assert_eq!(meta.symbol_units.len(), 2);
+
+        let pins = parse_symbol_pins(&meta.symbol_shapes, false);
+        assert_eq!(pins.len(), 4);
+
+        let sym = meta
+            .generate_symbol("RN", &pins, None, &SymbolStyle::default(), KicadVersion::default())
+            .unwrap();
+        let unit1 = sym.find("(symbol \"RN_1_1\"").unwrap();
+        let unit2 = sym.find("(symbol \"RN_2_1\"").unwrap();
+        assert!(!sym.contains("RN_0_1"));
+        assert_eq!(sym.matches("(rectangle").count(), 2);
+        let pin2 = sym.find("(number \"2\"").unwrap();
+        let pin4 = sym.find("(number \"4\"").unwrap();
+        assert!(unit1 < pin4 && pin4 < unit2 && unit2 < pin2);
     }
 }
@@ -2,4 +2,7 @@
 
 mod zen;
 
-pub use zen::{sanitize_mpn, ZenGenerator};
+pub use zen::{
+    check_standard_value, check_value_conflict, parse_value, part_dielectric, part_power,
+    part_tolerance, part_voltage, passive_value, sanitize_mpn, ZenGenerator,
+};
@@ -1,10 +1,13 @@
 //! .zen file generation for JLCPCB parts.
 
+use std::path::Path;
+
 use anyhow::{Context, Result};
 use minijinja::Environment;
 use regex::Regex;
 
 use crate::api::{JlcPart, PartType};
+use crate::easyeda::{find_diff_pairs, pair_adjacent_order};
 
 /// Context for rendering the generic passive template.
 #[derive(Debug, serde::Serialize)]
@@ -84,12 +87,16 @@ struct ComponentContext {
     name: String,
     /// Unique struct fields for Pins struct (deduplicated by name)
     struct_fields: Vec<StructField>,
+    /// Differential pairs among the struct fields
+    diff_pairs: Vec<DiffPair>,
+    /// Whether struct fields span more than one pin group (emit group comments)
+    grouped: bool,
     /// All pins with number -> sanitized name mapping
     pins: Vec<PinInfo>,
     datasheet: Option<String>,
     /// Footprint/package name from EasyEDA
     footprint_name: Option<String>,
-    /// Footprint filename (e.g., "AMS1117-3_3.kicad_mod")
+    /// Footprint path relative to the .zen file (e.g., "AMS1117-3_3.kicad_mod")
     footprint_file: Option<String>,
     /// Symbol filename (e.g., "AMS1117-3_3.kicad_sym")
     symbol_file: Option<String>,
@@ -97,6 +104,8 @@ struct ComponentContext {
     model_3d: Option<String>,
     /// EasyEDA component URL
     easyeda_url: Option<String>,
+    /// Placeholder for a part without EasyEDA data (pins need filling in)
+    stub: bool,
 }
 
 /// A pin with its number and sanitized name for struct field.
@@ -108,6 +117,8 @@ struct PinInfo {
     name: String,
     /// Sanitized name for struct field
     sanitized: String,
+    /// Pin function group label (e.g. "Power")
+    group: String,
 }
 
 /// Unique struct field for the Pins struct.
@@ -115,11 +126,99 @@ struct PinInfo {
 struct StructField {
     /// Sanitized name for struct field
     sanitized: String,
+    /// Pin function group label
+    group: String,
+}
+
+/// Pin function groups, in the order they appear in the `Pins` struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PinGroup {
+    Power,
+    Control,
+    Clock,
+    Io,
+}
+
+impl PinGroup {
+    /// Classify a pin by its (original) name.
+    fn classify(name: &str) -> Self {
+        const POWER: [&str; 15] = [
+            "VCC", "VDD", "VSS", "VEE", "GND", "AGND", "DGND", "PGND", "AVDD", "AVCC", "DVDD",
+            "VIN", "VOUT", "VBUS", "VBAT",
+        ];
+        const CONTROL: [&str; 9] =
+            ["EN", "ENABLE", "RST", "RESET", "SHDN", "SD", "BOOT", "STBY", "SLEEP"];
+        // Oscillator pins are matched as prefixes so AUXIN isn't an XIN;
+        // CLK can sit anywhere (BCLK, CLKOUT)
+        const OSCILLATOR: [&str; 4] = ["XTAL", "XIN", "XOUT", "OSC"];
+
+        let upper = name.to_ascii_uppercase();
+        // Active-low control pins: ~RESET, !EN, /SHDN, and NRST when the rest
+        // is a control word (so NC and NSS keep their N)
+        let token = upper
+            .strip_prefix(['~', '!', '/'])
+            .or_else(|| upper.strip_prefix('N').filter(|rest| is_control_word(&CONTROL, rest)))
+            .unwrap_or(&upper);
+
+        if POWER.iter().any(|p| upper.starts_with(p)) || upper == "V+" || upper == "V-" {
+            PinGroup::Power
+        } else if upper.contains("CLK") || OSCILLATOR.iter().any(|c| upper.starts_with(c)) {
+            PinGroup::Clock
+        } else if is_control_word(&CONTROL, token) {
+            PinGroup::Control
+        } else {
+            PinGroup::Io
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PinGroup::Power => "Power",
+            PinGroup::Control => "Control",
+            PinGroup::Clock => "Clock",
+            PinGroup::Io => "I/O",
+        }
+    }
 }
 
+/// Whether a pin name is one of `words`, optionally followed by a digit or
+/// separator (`BOOT0`, `SD_MODE`), so `SD` doesn't match `SDA`.
+fn is_control_word(words: &[&str], name: &str) -> bool {
+    words.iter().any(|w| {
+        name.strip_prefix(w)
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+    })
+}
+
+/// A differential pair of struct fields (e.g. D+/D-).
+#[derive(Debug, serde::Serialize)]
+struct DiffPair {
+    positive: String,
+    negative: String,
+}
+
+/// Built-in templates: (name, filename, source).
+const TEMPLATES: [(&str, &str, &str); 2] = [
+    (
+        "generic",
+        "generic.zen.jinja",
+        include_str!("../../templates/generic.zen.jinja"),
+    ),
+    (
+        "component",
+        "component.zen.jinja",
+        include_str!("../../templates/component.zen.jinja"),
+    ),
+];
+
+/// Default maximum description length in characters, including the `...`.
+pub const DEFAULT_DESCRIPTION_LENGTH: usize = 100;
+
 /// Generator for .zen files from JLCPCB parts.
 pub struct ZenGenerator {
     env: Environment<'static>,
+    /// Descriptions longer than this many characters are truncated
+    description_length: usize,
 }
 
 impl Default for ZenGenerator {
@@ -132,14 +231,48 @@ impl ZenGenerator {
     /// Create a new generator.
     pub fn new() -> Self {
         let mut env = Environment::new();
-        env.add_template("generic", include_str!("../../templates/generic.zen.jinja"))
-            .expect("Failed to add generic template");
-        env.add_template(
-            "component",
-            include_str!("../../templates/component.zen.jinja"),
-        )
-        .expect("Failed to add component template");
-        Self { env }
+        for (name, _, source) in TEMPLATES {
+            env.add_template(name, source)
+                .expect("Failed to add built-in template");
+        }
+        Self {
+            env,
+            description_length: DEFAULT_DESCRIPTION_LENGTH,
+        }
+    }
+
+    /// Create a generator that loads templates from a directory.
+    ///
+    /// Each template (e.g. `component.zen.jinja`) is read from `dir` if it
+    /// exists there; missing files fall back to the built-in version.
+    pub fn with_template_dir(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            anyhow::bail!("Template directory not found: {}", dir.display());
+        }
+
+        let mut env = Environment::new();
+        for (name, filename, source) in TEMPLATES {
+            let path = dir.join(filename);
+            if path.is_file() {
+                let custom = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read template {}", path.display()))?;
+                env.add_template_owned(name, custom)
+                    .with_context(|| format!("Invalid template {}", path.display()))?;
+            } else {
+                env.add_template(name, source)
+                    .expect("Failed to add built-in template");
+            }
+        }
+        Ok(Self {
+            env,
+            description_length: DEFAULT_DESCRIPTION_LENGTH,
+        })
+    }
+
+    /// Truncate component descriptions to `length` characters.
+    pub fn with_description_length(mut self, length: usize) -> Self {
+        self.description_length = length;
+        self
     }
 
     /// Generate a .zen file for a generic passive component.
@@ -191,41 +324,98 @@ impl ZenGenerator {
         footprint_file: &Option<String>,
         symbol_file: &Option<String>,
     ) -> Result<String> {
+        let ctx = self.component_context(part, name, pins, meta, footprint_file, symbol_file);
+        self.render_component(&ctx)
+    }
+
+    /// Generate a placeholder .zen file for a part EasyEDA has no data for.
+    ///
+    /// `pins` are stand-ins; the file carries a TODO asking for the real ones.
+    pub fn generate_stub(
+        &self,
+        part: &JlcPart,
+        name: &str,
+        pins: &[(String, String)],
+    ) -> Result<String> {
+        let ctx = ComponentContext {
+            stub: true,
+            ..self.component_context(part, name, pins, &Default::default(), &None, &None)
+        };
+        self.render_component(&ctx)
+    }
+
+    /// Render the component template.
+    fn render_component(&self, ctx: &ComponentContext) -> Result<String> {
+        let template = self.env.get_template("component")?;
+        template
+            .render(ctx)
+            .context("Failed to render component template")
+    }
+
+    /// Build the component template context.
+    fn component_context(
+        &self,
+        part: &JlcPart,
+        name: &str,
+        pins: &[(String, String)],
+        meta: &crate::easyeda::ComponentMeta,
+        footprint_file: &Option<String>,
+        symbol_file: &Option<String>,
+    ) -> ComponentContext {
         use std::collections::HashSet;
 
         // Build list of all pins with their info
+        let mode = SanitizeMode::for_part(part);
         let pin_infos: Vec<PinInfo> = pins
             .iter()
             .map(|(number, pin_name)| PinInfo {
                 number: number.clone(),
                 name: pin_name.clone(),
-                sanitized: sanitize_pin_name(pin_name),
+                sanitized: sanitize_pin_name(pin_name, mode),
+                group: PinGroup::classify(pin_name).label().to_string(),
             })
             .collect();
 
         // Deduplicate struct fields (multiple pins can have the same name, like VOUT on AMS1117)
         let mut seen: HashSet<String> = HashSet::new();
-        let struct_fields: Vec<StructField> = pin_infos
+        let unique_pins: Vec<&PinInfo> = pin_infos
             .iter()
-            .filter_map(|p| {
-                if seen.insert(p.sanitized.clone()) {
-                    Some(StructField {
-                        sanitized: p.sanitized.clone(),
-                    })
-                } else {
-                    None
-                }
+            .filter(|p| seen.insert(p.sanitized.clone()))
+            .collect();
+
+        // Keep differential pairs next to each other and note them on the struct
+        let names: Vec<&str> = unique_pins.iter().map(|p| p.name.as_str()).collect();
+        let diff_pairs: Vec<DiffPair> = find_diff_pairs(&names)
+            .into_iter()
+            .map(|(p, n)| DiffPair {
+                positive: unique_pins[p].sanitized.clone(),
+                negative: unique_pins[n].sanitized.clone(),
             })
             .collect();
+        // Group by pin function; the sort is stable so pairs stay adjacent
+        let mut order = pair_adjacent_order(&names);
+        order.sort_by_key(|&i| PinGroup::classify(names[i]));
+        let struct_fields: Vec<StructField> = order
+            .into_iter()
+            .map(|i| StructField {
+                sanitized: unique_pins[i].sanitized.clone(),
+                group: unique_pins[i].group.clone(),
+            })
+            .collect();
+        let grouped = struct_fields
+            .windows(2)
+            .any(|w| w[0].group != w[1].group);
 
-        let ctx = ComponentContext {
+        ComponentContext {
             lcsc: part.lcsc.clone(),
             mpn: part.mpn.clone(),
             manufacturer: part.manufacturer.clone(),
-            description: truncate_description(&part.description),
+            description: truncate_description(&part.description, self.description_length),
             basic: part.basic,
             name: name.to_string(),
             struct_fields,
+            diff_pairs,
+            grouped,
             pins: pin_infos,
             datasheet: part.datasheet.clone(),
             footprint_name: meta.footprint_name.clone(),
@@ -233,55 +423,98 @@ impl ZenGenerator {
             symbol_file: symbol_file.clone(),
             model_3d: meta.model_3d.clone(),
             easyeda_url: meta.easyeda_url(),
-        };
-
-        let template = self.env.get_template("component")?;
-        template
-            .render(&ctx)
-            .context("Failed to render component template")
+            stub: false,
+        }
     }
 }
 
-/// Extract the value from a part's description or attributes.
+/// Extract the value from a part's attributes, falling back to its description.
 fn extract_value(part: &JlcPart) -> String {
+    passive_value(part).unwrap_or_else(|| "—".to_string())
+}
+
+/// A part's tolerance (e.g. `±1%`), from its attributes or description.
+pub fn part_tolerance(part: &JlcPart) -> Option<String> {
+    part.attributes
+        .tolerance
+        .clone()
+        .or_else(|| extract_attributes_from_description(&part.description).tolerance)
+}
+
+/// A part's voltage rating (e.g. `50V`), from its attributes or description.
+pub fn part_voltage(part: &JlcPart) -> Option<String> {
+    part.attributes
+        .voltage
+        .clone()
+        .or_else(|| extract_attributes_from_description(&part.description).voltage)
+}
+
+/// A part's power rating (e.g. `0.1W`), from its attributes or description.
+pub fn part_power(part: &JlcPart) -> Option<String> {
+    part.attributes
+        .power
+        .clone()
+        .or_else(|| extract_attributes_from_description(&part.description).power)
+}
+
+/// A capacitor's dielectric (e.g. `X7R`), from its attributes or description.
+pub fn part_dielectric(part: &JlcPart) -> Option<String> {
+    part.attributes
+        .dielectric
+        .clone()
+        .or_else(|| extract_attributes_from_description(&part.description).dielectric)
+}
+
+/// A passive's value (e.g. `10kΩ`, `100nF`), from its attributes or description.
+pub fn passive_value(part: &JlcPart) -> Option<String> {
+    let (attribute, description) = value_sources(part);
+    attribute.or(description)
+}
+
+/// A passive's value as given by its attributes and by its description.
+fn value_sources(part: &JlcPart) -> (Option<String>, Option<String>) {
+    let desc = &part.description;
     match part.part_type() {
-        PartType::Resistor => {
-            if let Some(ref res) = part.attributes.resistance {
-                return res.clone();
-            }
-            // Try to extract from description
-            if let Some(cap) = extract_resistance_from_desc(&part.description) {
-                return cap;
-            }
-            "—".to_string()
-        }
-        PartType::Capacitor => {
-            if let Some(ref cap) = part.attributes.capacitance {
-                return cap.clone();
-            }
-            if let Some(cap) = extract_capacitance_from_desc(&part.description) {
-                return cap;
-            }
-            "—".to_string()
-        }
-        PartType::Inductor => {
-            if let Some(ref ind) = part.attributes.inductance {
-                return ind.clone();
-            }
-            if let Some(ind) = extract_inductance_from_desc(&part.description) {
-                return ind;
-            }
-            "—".to_string()
-        }
-        _ => "—".to_string(),
+        PartType::Resistor => (
+            part.attributes.resistance.clone(),
+            extract_resistance_from_desc(desc),
+        ),
+        PartType::Capacitor => (
+            part.attributes.capacitance.clone(),
+            extract_capacitance_from_desc(desc),
+        ),
+        PartType::Inductor => (
+            part.attributes.inductance.clone(),
+            extract_inductance_from_desc(desc),
+        ),
+        _ => (None, None),
     }
 }
 
+/// Warn when a passive's attributes and description give different values.
+///
+/// The attribute value is the one used, but a mismatch usually means one of
+/// them is wrong in the catalog, so both are shown.
+pub fn check_value_conflict(part: &JlcPart) -> Option<String> {
+    let (Some(attribute), Some(description)) = value_sources(part) else {
+        return None;
+    };
+    let (a, d) = (parse_value(&attribute)?, parse_value(&description)?);
+    if (a - d).abs() <= a.abs().max(d.abs()) * 1e-3 {
+        return None;
+    }
+
+    Some(format!(
+        "Part attributes give value {} but the description says {}; using {}",
+        attribute, description, attribute
+    ))
+}
+
 /// Extract resistance value from description.
 fn extract_resistance_from_desc(desc: &str) -> Option<String> {
     // Match patterns like "10kΩ", "4.7k", "100R", "4R7", "10k"
     let patterns = [
-        r"(\d+(?:\.\d+)?)\s*([kKmM]?)[Ωohm]",
+        r"(\d+(?:\.\d+)?)\s*([kKmM]?)(?:Ω|[oO]hm)",
         r"(\d+(?:\.\d+)?)\s*([kKmMrR])\s*$",
         r"(\d+)[rR](\d+)",
     ];
@@ -290,6 +523,10 @@ fn extract_resistance_from_desc(desc: &str) -> Option<String> {
         if let Some(caps) = Regex::new(pattern).unwrap().captures(desc) {
             let value = caps.get(1).map(|m| m.as_str()).unwrap_or("");
             let unit = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+            // "4R7": the R stands in for the decimal point
+            if unit.chars().all(|c| c.is_ascii_digit()) && !unit.is_empty() {
+                return Some(format!("{}.{}Ω", value, unit));
+            }
             if !value.is_empty() {
                 let unit = unit.to_lowercase().replace('r', "Ω");
                 return Some(format!("{}{}", value, unit));
@@ -335,8 +572,172 @@ fn extract_inductance_from_desc(desc: &str) -> Option<String> {
     None
 }
 
+/// E24 series mantissas (5% tolerance).
+const E24: [f64; 24] = [
+    1.0, 1.1, 1.2, 1.3, 1.5, 1.6, 1.8, 2.0, 2.2, 2.4, 2.7, 3.0, 3.3, 3.6, 3.9, 4.3, 4.7, 5.1, 5.6,
+    6.2, 6.8, 7.5, 8.2, 9.1,
+];
+
+/// E96 series mantissas (1% tolerance).
+const E96: [f64; 96] = [
+    1.00, 1.02, 1.05, 1.07, 1.10, 1.13, 1.15, 1.18, 1.21, 1.24, 1.27, 1.30, 1.33, 1.37, 1.40, 1.43,
+    1.47, 1.50, 1.54, 1.58, 1.62, 1.65, 1.69, 1.74, 1.78, 1.82, 1.87, 1.91, 1.96, 2.00, 2.05, 2.10,
+    2.15, 2.21, 2.26, 2.32, 2.37, 2.43, 2.49, 2.55, 2.61, 2.67, 2.74, 2.80, 2.87, 2.94, 3.01, 3.09,
+    3.16, 3.24, 3.32, 3.40, 3.48, 3.57, 3.65, 3.74, 3.83, 3.92, 4.02, 4.12, 4.22, 4.32, 4.42, 4.53,
+    4.64, 4.75, 4.87, 4.99, 5.11, 5.23, 5.36, 5.49, 5.62, 5.76, 5.90, 6.04, 6.19, 6.34, 6.49, 6.65,
+    6.81, 6.98, 7.15, 7.32, 7.50, 7.68, 7.87, 8.06, 8.25, 8.45, 8.66, 8.87, 9.09, 9.31, 9.53, 9.76,
+];
+
+/// Standard E-series a value belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ESeries {
+    E24,
+    E96,
+}
+
+/// Nearest standard value for a component value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ESeriesMatch {
+    /// Nearest E24/E96 value, in the same unit as the input
+    pub nearest: f64,
+    /// Series the nearest value comes from
+    pub series: ESeries,
+    /// Whether the input is itself a standard value
+    pub exact: bool,
+}
+
+/// Find the nearest E24/E96 value. E24 wins when a value is in both series.
+pub fn eseries(value: f64) -> Option<ESeriesMatch> {
+    if !value.is_finite() || value <= 0.0 {
+        return None;
+    }
+
+    let decade = 10f64.powf(value.log10().floor());
+    let mantissa = value / decade;
+
+    // Include the next decade's 1.0 so values like 9.9 snap upwards
+    let candidates = E24
+        .iter()
+        .map(|&m| (m, ESeries::E24))
+        .chain(E96.iter().map(|&m| (m, ESeries::E96)))
+        .chain(std::iter::once((10.0, ESeries::E24)));
+
+    let mut best: Option<(f64, ESeries, f64)> = None;
+    for (m, series) in candidates {
+        let error = (mantissa - m).abs() / m;
+        // Strictly smaller keeps E24 ahead of an equal E96 entry
+        if best.is_none_or(|(_, _, e)| error < e) {
+            best = Some((m, series, error));
+        }
+    }
+
+    best.map(|(m, series, error)| ESeriesMatch {
+        nearest: m * decade,
+        series,
+        exact: error < 1e-3,
+    })
+}
+
+/// Parse a component value like "4.7k", "4k7", "100nF" or "10kΩ" into base units.
+pub fn parse_value(value: &str) -> Option<f64> {
+    let re = Regex::new(r"^\s*(\d+(?:\.\d+)?)\s*([pnuµμmkKMGRrΩ]?)(\d+)?").unwrap();
+    let caps = re.captures(value)?;
+    let number = caps.get(1)?.as_str();
+    let prefix = caps.get(2).map_or("", |m| m.as_str());
+
+    let mut base: f64 = number.parse().ok()?;
+    // "4k7" style: the prefix letter stands in for the decimal point
+    if let Some(fraction) = caps.get(3) {
+        if prefix.is_empty() || number.contains('.') {
+            return None;
+        }
+        base = format!("{}.{}", number, fraction.as_str()).parse().ok()?;
+    }
+
+    let multiplier = match prefix {
+        "p" => 1e-12,
+        "n" => 1e-9,
+        "u" | "µ" | "μ" => 1e-6,
+        "m" => 1e-3,
+        "k" | "K" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        _ => 1.0,
+    };
+    Some(base * multiplier)
+}
+
+/// Format a value in base units with an SI prefix (e.g. 12400.0, "Ω" -> "12.4kΩ").
+fn format_value(value: f64, unit: &str) -> String {
+    const PREFIXES: [(f64, &str); 8] = [
+        (1e9, "G"),
+        (1e6, "M"),
+        (1e3, "k"),
+        (1.0, ""),
+        (1e-3, "m"),
+        (1e-6, "u"),
+        (1e-9, "n"),
+        (1e-12, "p"),
+    ];
+    let (scale, prefix) = PREFIXES
+        .iter()
+        .find(|(scale, _)| value >= *scale * 0.9995)
+        .unwrap_or(&PREFIXES[PREFIXES.len() - 1]);
+    let scaled = format!("{:.2}", value / scale);
+    let scaled = scaled.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}{}", scaled, prefix, unit)
+}
+
+/// Warn when a passive's value isn't a standard E24/E96 value.
+///
+/// A non-standard value usually means the value was mis-parsed or the part
+/// data is wrong.
+pub fn check_standard_value(part: &JlcPart) -> Option<String> {
+    let unit = match part.part_type() {
+        PartType::Resistor => "Ω",
+        PartType::Capacitor => "F",
+        PartType::Inductor => "H",
+        _ => return None,
+    };
+
+    let value = extract_value(part);
+    let matched = eseries(parse_value(&value)?)?;
+    if matched.exact {
+        return None;
+    }
+
+    Some(format!(
+        "Value {} is not a standard E24/E96 value (nearest {:?}: {})",
+        value,
+        matched.series,
+        format_value(matched.nearest, unit)
+    ))
+}
+
+/// How pin names are turned into Starlark identifiers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SanitizeMode {
+    /// Uppercase names (IC pins like "vcc" become "VCC")
+    #[default]
+    Uppercase,
+    /// Keep the original casing and only replace illegal characters
+    PreserveCase,
+}
+
+impl SanitizeMode {
+    /// Connectors keep their pin names as printed ("Shield", "SCL");
+    /// everything else is uppercased.
+    fn for_part(part: &JlcPart) -> Self {
+        if part.part_type() == PartType::Other && part.is_connector() {
+            SanitizeMode::PreserveCase
+        } else {
+            SanitizeMode::Uppercase
+        }
+    }
+}
+
 /// Sanitize a pin name for use as a Starlark identifier.
-fn sanitize_pin_name(name: &str) -> String {
+fn sanitize_pin_name(name: &str, mode: SanitizeMode) -> String {
     let chars: Vec<char> = name.chars().collect();
     let len = chars.len();
     let mut result = String::with_capacity(len + 8);
@@ -350,7 +751,10 @@ fn sanitize_pin_name(name: &str) -> String {
             '+' | '-' => result.push('_'),
             '~' | '!' => result.push_str("N_"),
             '#' => result.push('H'),
-            c if c.is_alphanumeric() => result.push(c.to_ascii_uppercase()),
+            c if c.is_alphanumeric() => match mode {
+                SanitizeMode::Uppercase => result.push(c.to_ascii_uppercase()),
+                SanitizeMode::PreserveCase => result.push(c),
+            },
             _ => result.push('_'),
         }
     }
@@ -372,13 +776,16 @@ fn sanitize_pin_name(name: &str) -> String {
     }
 }
 
-/// Truncate description to a reasonable length.
-fn truncate_description(desc: &str) -> String {
-    let max_len = 100;
-    if desc.len() <= max_len {
+/// Truncate a description to at most `max_len` characters, ending in `...`.
+///
+/// Counts characters rather than bytes, so descriptions with `Ω`, `µ` or
+/// CJK text are never cut mid-character.
+fn truncate_description(desc: &str, max_len: usize) -> String {
+    if desc.chars().count() <= max_len {
         desc.to_string()
     } else {
-        format!("{}...", &desc[..max_len - 3])
+        let kept: String = desc.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", kept)
     }
 }
 
@@ -405,12 +812,46 @@ mod tests {
 
     #[test]
     fn test_sanitize_pin_name() {
-        assert_eq!(sanitize_pin_name("VCC"), "VCC");
-        assert_eq!(sanitize_pin_name("V+"), "V_POS");
-        assert_eq!(sanitize_pin_name("V-"), "V_NEG");
-        assert_eq!(sanitize_pin_name("~CS"), "N_CS");
-        assert_eq!(sanitize_pin_name("1"), "P1");
-        assert_eq!(sanitize_pin_name("GND"), "GND");
+        let mode = SanitizeMode::Uppercase;
+        assert_eq!(sanitize_pin_name("VCC", mode), "VCC");
+        assert_eq!(sanitize_pin_name("V+", mode), "V_POS");
+        assert_eq!(sanitize_pin_name("V-", mode), "V_NEG");
+        assert_eq!(sanitize_pin_name("~CS", mode), "N_CS");
+        assert_eq!(sanitize_pin_name("1", mode), "P1");
+        assert_eq!(sanitize_pin_name("GND", mode), "GND");
+        assert_eq!(sanitize_pin_name("Shield", mode), "SHIELD");
+    }
+
+    #[test]
+    fn test_sanitize_connector_pin_name() {
+        let mode = SanitizeMode::PreserveCase;
+        assert_eq!(sanitize_pin_name("Shield", mode), "Shield");
+        assert_eq!(sanitize_pin_name("SCL", mode), "SCL");
+        assert_eq!(sanitize_pin_name("12", mode), "P12");
+        assert_eq!(sanitize_pin_name("Pin 3", mode), "Pin_3");
+        assert_eq!(sanitize_pin_name("D+", mode), "D_POS");
+
+        let connector = JlcPart {
+            category: "Connectors".to_string(),
+            subcategory: "Pin Headers".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(SanitizeMode::for_part(&connector), SanitizeMode::PreserveCase);
+        let ic = JlcPart {
+            category: "Embedded Processors & Controllers".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(SanitizeMode::for_part(&ic), SanitizeMode::Uppercase);
+    }
+
+    #[test]
+    fn test_truncate_description_multibyte() {
+        assert_eq!(truncate_description("10kΩ ±1% 0402", 100), "10kΩ ±1% 0402");
+
+        // Byte 97 falls inside `Ω`, which the old byte slicing panicked on
+        let desc = format!("{}Ω±µ 贴片电阻 thick film", "a".repeat(96));
+        assert_eq!(truncate_description(&desc, 100), format!("{}Ω...", "a".repeat(96)));
+        assert_eq!(truncate_description("贴片电阻器", 4), "贴...");
     }
 
     #[test]
@@ -431,4 +872,155 @@ mod tests {
             Some("10uF".to_string())
         );
     }
+
+    #[test]
+    fn test_component_diff_pair_fields() {
+        let part = JlcPart {
+            lcsc: "C2765186".to_string(),
+            mpn: "TYPE-C-31-M-12".to_string(),
+            ..Default::default()
+        };
+        let pins: Vec<(String, String)> = [("1", "D-"), ("2", "GND"), ("3", "D+"), ("4", "VBUS")]
+            .iter()
+            .map(|(n, name)| (n.to_string(), name.to_string()))
+            .collect();
+        let zen = ZenGenerator::new()
+            .generate_component(&part, "USB", &pins, &Default::default(), &None, &None)
+            .unwrap();
+
+        assert!(zen.contains("#   D_POS / D_NEG\nPins = struct("));
+        let pos = zen.find("D_POS = io").unwrap();
+        let neg = zen.find("D_NEG = io").unwrap();
+        let gnd = zen.find("GND = io").unwrap();
+        assert!(gnd < pos && pos < neg);
+        assert!(zen.contains("Pins = struct(\n    # Power\n    GND = io"));
+        assert!(zen.contains("    # I/O\n    D_POS = io"));
+    }
+
+    #[test]
+    fn test_stub_component_has_todo() {
+        let part = JlcPart {
+            lcsc: "C999999".to_string(),
+            mpn: "MYSTERY-1".to_string(),
+            ..Default::default()
+        };
+        let pins = vec![
+            ("1".to_string(), "P1".to_string()),
+            ("2".to_string(), "P2".to_string()),
+        ];
+        let zen = ZenGenerator::new().generate_stub(&part, "MYSTERY-1", &pins).unwrap();
+        assert!(zen.contains("# TODO: EasyEDA has no symbol or footprint for C999999"));
+        assert!(zen.contains("P2 = io(\"P2\", Net)"));
+        assert!(!zen.contains("symbol = "));
+
+        let zen = ZenGenerator::new()
+            .generate_component(&part, "MYSTERY-1", &pins, &Default::default(), &None, &None)
+            .unwrap();
+        assert!(!zen.contains("TODO"));
+    }
+
+    #[test]
+    fn test_pin_group_classify() {
+        assert_eq!(PinGroup::classify("VDD"), PinGroup::Power);
+        assert_eq!(PinGroup::classify("GND"), PinGroup::Power);
+        assert_eq!(PinGroup::classify("V+"), PinGroup::Power);
+        assert_eq!(PinGroup::classify("EN"), PinGroup::Control);
+        assert_eq!(PinGroup::classify("~RESET"), PinGroup::Control);
+        assert_eq!(PinGroup::classify("NRST"), PinGroup::Control);
+        assert_eq!(PinGroup::classify("BCLK"), PinGroup::Clock);
+        assert_eq!(PinGroup::classify("XTAL1"), PinGroup::Clock);
+        assert_eq!(PinGroup::classify("SDA"), PinGroup::Io);
+        assert_eq!(PinGroup::classify("PA0"), PinGroup::Io);
+
+        assert_eq!(PinGroup::classify("NC"), PinGroup::Io);
+        assert_eq!(PinGroup::classify("NSS"), PinGroup::Io);
+        assert_eq!(PinGroup::classify("NRESET"), PinGroup::Control);
+        assert_eq!(PinGroup::classify("/SHDN"), PinGroup::Control);
+        assert_eq!(PinGroup::classify("AUXIN"), PinGroup::Io);
+        assert_eq!(PinGroup::classify("XIN"), PinGroup::Clock);
+        assert_eq!(PinGroup::classify("OSC_IN"), PinGroup::Clock);
+    }
+
+    #[test]
+    fn test_template_dir_override() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("component.zen.jinja"),
+            "# house style for {{ mpn }}\n",
+        )
+        .unwrap();
+
+        let generator = ZenGenerator::with_template_dir(dir.path()).unwrap();
+        let part = JlcPart {
+            mpn: "NE555".to_string(),
+            ..Default::default()
+        };
+        let zen = generator
+            .generate_component(&part, "NE555", &[], &Default::default(), &None, &None)
+            .unwrap();
+        assert_eq!(zen, "# house style for NE555");
+
+        // Templates missing from the directory fall back to the built-in ones
+        assert!(generator.env.get_template("generic").is_ok());
+
+        assert!(ZenGenerator::with_template_dir(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_extract_resistance_skips_power_rating() {
+        assert_eq!(
+            extract_resistance_from_desc("62.5mW Thick Film Resistors 50V ±1% 10kΩ 0402"),
+            Some("10k".to_string())
+        );
+        assert_eq!(extract_resistance_from_desc("Resistor 4R7 0603"), Some("4.7Ω".to_string()));
+    }
+
+    #[test]
+    fn test_value_conflict() {
+        let mut part = JlcPart {
+            category: "Resistors".to_string(),
+            description: "62.5mW Thick Film Resistors 50V ±1% 1kΩ 0402".to_string(),
+            ..Default::default()
+        };
+        part.attributes.resistance = Some("10kΩ".to_string());
+        assert_eq!(extract_value(&part), "10kΩ");
+        let warning = check_value_conflict(&part).unwrap();
+        assert!(warning.contains("10kΩ") && warning.contains("1k"), "{}", warning);
+
+        part.description = "62.5mW Thick Film Resistors 50V ±1% 10KΩ 0402".to_string();
+        assert_eq!(check_value_conflict(&part), None);
+        part.attributes.resistance = None;
+        assert_eq!(check_value_conflict(&part), None);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("10kΩ"), Some(10_000.0));
+        assert_eq!(parse_value("4k7"), Some(4_700.0));
+        assert_eq!(parse_value("4R7"), Some(4.7));
+        assert_eq!(parse_value("1M"), Some(1e6));
+        assert!((parse_value("100nF").unwrap() - 100e-9).abs() < 1e-18);
+        assert_eq!(parse_value("—"), None);
+    }
+
+    #[test]
+    fn test_eseries() {
+        assert_eq!(E96.len(), 96);
+
+        let m = eseries(4_700.0).unwrap();
+        assert!(m.exact);
+        assert_eq!(m.series, ESeries::E24);
+
+        let m = eseries(10_200.0).unwrap();
+        assert!(m.exact);
+        assert_eq!(m.series, ESeries::E96);
+
+        let m = eseries(12_345.0).unwrap();
+        assert!(!m.exact);
+        assert!((m.nearest - 12_400.0).abs() < 1e-6);
+        assert_eq!(format_value(m.nearest, "Ω"), "12.4kΩ");
+
+        assert!(eseries(100e-9).unwrap().exact);
+        assert_eq!(eseries(0.0), None);
+    }
 }
@@ -0,0 +1,230 @@
+//! Shared HTTP setup for the JLCPCB and EasyEDA clients.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use reqwest::blocking::{ClientBuilder, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+/// Environment variable that enables request timing/size logging on stderr.
+pub const DEBUG_ENV: &str = "PCB_JLCPCB_DEBUG";
+
+/// Delay before the first retry; doubled for each further attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between two attempts, however many retries are allowed.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Network settings shared by all clients, set once from the global CLI flags.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpConfig {
+    /// Per-request timeout
+    pub timeout: Duration,
+    /// Extra attempts after a connection error or 5xx response
+    pub retries: u32,
+    /// Search results requested per exact part-number lookup
+    pub parts_per_request: i32,
+    /// Search results requested per page when resolving a BOM line by MPN
+    pub mpn_results: i32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retries: 2,
+            parts_per_request: 2,
+            mpn_results: 20,
+        }
+    }
+}
+
+static CONFIG: OnceLock<HttpConfig> = OnceLock::new();
+
+/// Set the network settings. Only the first call has any effect.
+pub fn configure(config: HttpConfig) {
+    let _ = CONFIG.set(config);
+}
+
+fn config() -> HttpConfig {
+    CONFIG.get().copied().unwrap_or_default()
+}
+
+/// Search results requested per exact part-number lookup.
+pub fn parts_per_request() -> i32 {
+    config().parts_per_request
+}
+
+/// Search results requested per page when resolving a BOM line by MPN.
+pub fn mpn_results() -> i32 {
+    config().mpn_results
+}
+
+/// Whether request debug logging is enabled.
+pub fn debug_enabled() -> bool {
+    std::env::var(DEBUG_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// HTTP client builder with the shared timeout and compressed responses.
+pub fn client_builder() -> ClientBuilder {
+    reqwest::blocking::Client::builder()
+        .timeout(config().timeout)
+        .gzip(true)
+        .deflate(true)
+}
+
+/// Send a request, retrying connection errors and 5xx responses with
+/// exponential backoff.
+///
+/// The last response (even a 5xx) is returned so callers keep their own
+/// status handling; only a final connection error becomes an `Err`.
+pub fn send_with_retry(request: RequestBuilder, label: &str) -> Result<Response> {
+    let retries = config().retries;
+    let mut attempt = 0;
+
+    loop {
+        let pending = match request.try_clone() {
+            Some(pending) if attempt < retries => pending,
+            // Last attempt, or a streaming body that can't be replayed
+            _ => return Ok(request.send()?),
+        };
+
+        let failure = match pending.send() {
+            Ok(response) if !response.status().is_server_error() => return Ok(response),
+            Ok(response) => response.status().to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        let delay = retry_delay(attempt);
+        if debug_enabled() {
+            eprintln!(
+                "[debug] {}: {}, retrying in {} ms",
+                label,
+                failure,
+                delay.as_millis()
+            );
+        }
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Exponential backoff before retry number `attempt + 1`, capped at
+/// [`RETRY_MAX_DELAY`].
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(RETRY_MAX_DELAY, |delay| delay.min(RETRY_MAX_DELAY))
+}
+
+/// Read a JSON response body, logging its size and timing when debugging.
+///
+/// `started` is when the request was sent. The logged size is the decoded
+/// body size; reqwest strips `Content-Encoding` once it decompresses.
+pub fn read_json<T: DeserializeOwned>(response: Response, label: &str, started: Instant) -> Result<T> {
+    let status = response.status();
+    let body = response.bytes()?;
+
+    if debug_enabled() {
+        eprintln!(
+            "[debug] {}: {} ({} bytes, {} ms)",
+            label,
+            status,
+            body.len(),
+            started.elapsed().as_millis()
+        );
+    }
+
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// `{"ok":true}` gzip-compressed.
+    const GZIPPED_BODY: [u8; 31] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x56, 0xca, 0xcf, 0x56,
+        0xb2, 0x2a, 0x29, 0x2a, 0x4d, 0xad, 0x05, 0x00, 0x90, 0x5f, 0xd4, 0xa7, 0x0b, 0x00, 0x00,
+        0x00,
+    ];
+
+    #[test]
+    fn test_requests_and_decodes_gzip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                 Content-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                GZIPPED_BODY.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&GZIPPED_BODY).unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+
+        let client = client_builder().build().unwrap();
+        let started = Instant::now();
+        let response = client.get(format!("http://{}/", addr)).send().unwrap();
+        let value: serde_json::Value = read_json(response, "test", started).unwrap();
+        assert_eq!(value["ok"], true);
+
+        let request = server.join().unwrap();
+        let accept = request
+            .lines()
+            .find(|l| l.starts_with("accept-encoding:"))
+            .expect("missing Accept-Encoding header");
+        assert!(accept.contains("gzip"));
+        assert!(accept.contains("deflate"));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(40), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(u32::MAX), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_retries_server_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let client = client_builder().build().unwrap();
+        let response =
+            send_with_retry(client.get(format!("http://{}/", addr)), "test").unwrap();
+        assert!(response.status().is_success());
+        server.join().unwrap();
+    }
+}
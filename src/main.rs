@@ -10,16 +10,61 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 mod api;
+mod atomic;
 mod commands;
+mod config;
 mod easyeda;
 mod generator;
+mod http;
+mod natural;
 mod pins;
+mod sexpr;
 
 #[derive(Parser)]
 #[command(name = "pcb-jlcpcb")]
 #[command(author, version, about = "JLCPCB parts library integration for pcb")]
 #[command(propagate_version = true)]
 pub struct Cli {
+    /// Config file with defaults for common flags [default: ~/.pcb/jlcpcb/config.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Network request timeout in seconds [default: 30]
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Retries for failed EasyEDA requests (connection errors and 5xx) [default: 2]
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(0..=i64::from(config::MAX_RETRIES))
+    )]
+    retries: Option<u32>,
+
+    /// Search results fetched per exact part-number lookup [default: 2]
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(1..=i64::from(config::MAX_PAGE_SIZE))
+    )]
+    parts_per_request: Option<i32>,
+
+    /// Search results fetched per page when resolving a BOM line by MPN; a
+    /// second page is checked when the first has no basic part [default: 20]
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(1..=i64::from(config::MAX_PAGE_SIZE))
+    )]
+    mpn_results: Option<i32>,
+
+    /// Print JSON output on a single line instead of pretty-printed
+    #[arg(long, global = true)]
+    json_compact: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -36,16 +81,21 @@ enum Commands {
         or manufacturer part number.")]
     Search {
         /// Search query (value, package, category, MPN, etc.)
-        query: String,
+        #[arg(required_unless_present_any = ["compare", "history"])]
+        query: Option<String>,
 
-        /// Output format (human, json)
-        #[arg(short, long, default_value = "human")]
-        format: String,
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
 
         /// Only show JLCPCB basic parts (lower assembly fee)
         #[arg(short, long)]
         basic: bool,
 
+        /// Show all library types, overriding `basic = true` in the config
+        #[arg(long, conflicts_with = "basic")]
+        no_basic: bool,
+
         /// Include preferred/promotional parts (requires --basic)
         #[arg(short, long, requires = "basic")]
         preferred: bool,
@@ -57,6 +107,88 @@ enum Commands {
         /// Page number (1-indexed)
         #[arg(long, default_value = "1")]
         page: i32,
+
+        /// Only show parts at or above this unit price (USD at --price-qty)
+        #[arg(long, value_name = "USD")]
+        min_price: Option<f64>,
+
+        /// Only show parts at or below this unit price (USD at --price-qty)
+        #[arg(long, value_name = "USD")]
+        max_price: Option<f64>,
+
+        /// Drop parts without pricing (listed last by default when filtering)
+        #[arg(long)]
+        require_price: bool,
+
+        /// Only show parts with at least this much stock; stock below twice
+        /// this is highlighted
+        #[arg(long, value_name = "N")]
+        stock_min: Option<i64>,
+
+        /// Order quantity prices are shown and filtered at [default: 100]
+        #[arg(long, value_name = "N")]
+        price_qty: Option<i32>,
+
+        /// Only show parts whose subcategory starts with this (case-insensitive),
+        /// e.g. "Chip Resistor"
+        #[arg(long, value_name = "PREFIX")]
+        subcategory: Option<String>,
+
+        /// Only show parts in this package (case-insensitive), e.g. "0402"
+        #[arg(long, value_name = "PACKAGE")]
+        package: Option<String>,
+
+        /// Only show parts with at most this tolerance, e.g. "1%" (from part
+        /// attributes or the description)
+        #[arg(long, value_name = "PCT", value_parser = commands::search::parse_tolerance)]
+        tolerance: Option<f64>,
+
+        /// Only show capacitors with this dielectric, e.g. "X7R" (C0G and NP0
+        /// are treated as the same)
+        #[arg(long, value_name = "TYPE")]
+        dielectric: Option<String>,
+
+        /// Only show exact matches: the LCSC code itself, or parts whose MPN
+        /// equals the query (case-insensitive)
+        #[arg(long)]
+        exact: bool,
+
+        /// Treat the query as comma-separated alternatives ("10k,4k7") and
+        /// merge the results
+        #[arg(long)]
+        any: bool,
+
+        /// Wide table with manufacturer, category, subcategory, tolerance,
+        /// voltage and dielectric (fetches details for each result)
+        #[arg(long)]
+        wide: bool,
+
+        /// Compare specific parts side by side instead of searching (e.g. C25744,C25804)
+        #[arg(long, value_name = "LCSC,...", value_delimiter = ',', conflicts_with = "query")]
+        compare: Vec<String>,
+
+        /// Save the results as JSON for a later `generate --from-file`
+        #[arg(long, value_name = "FILE", conflicts_with = "compare")]
+        save: Option<PathBuf>,
+
+        /// When fewer than N parts match, retry with the last query term
+        /// dropped (up to 3 times)
+        #[arg(long, value_name = "N", conflicts_with_all = ["any", "exact", "compare"])]
+        min_results: Option<usize>,
+
+        /// List the distinct categories and subcategories among the results
+        /// instead of the parts (use --limit to scan more)
+        #[arg(long, conflicts_with_all = ["compare", "wide", "save"])]
+        category_list: bool,
+
+        /// Copy the LCSC code of result N (1 = first row) to the clipboard;
+        /// prints it instead when no clipboard is available
+        #[arg(long, value_name = "N", conflicts_with_all = ["compare", "category_list"])]
+        copy: Option<usize>,
+
+        /// List recent searches (kept in ~/.pcb/jlcpcb/history.json) instead of searching
+        #[arg(long, conflicts_with_all = ["query", "compare"])]
+        history: bool,
     },
 
     /// Generate .zen component files from JLCPCB parts
@@ -66,9 +198,32 @@ enum Commands {
         Output defaults to components/JLCPCB/<mpn>/.")]
     Generate {
         /// LCSC part number(s) (e.g., C307331)
-        #[arg(required = true)]
+        #[arg(
+            required_unless_present_any = ["from_bom", "from_file"],
+            conflicts_with_all = ["from_bom", "from_file"]
+        )]
         lcsc: Vec<String>,
 
+        /// Generate every part referenced in a BOM file (.json, .csv or .zen, - for stdin)
+        #[arg(long, value_name = "BOM", conflicts_with = "from_file")]
+        from_bom: Option<PathBuf>,
+
+        /// Generate parts from results saved with `search --save`
+        #[arg(long, value_name = "FILE")]
+        from_file: Option<PathBuf>,
+
+        /// With --from-file, the 1-based result positions to generate (e.g. 1,3)
+        #[arg(long, value_name = "N,...", value_delimiter = ',', requires = "from_file")]
+        index: Vec<usize>,
+
+        /// With --from-file, generate every saved result
+        #[arg(long, requires = "from_file", conflicts_with = "index")]
+        all: bool,
+
+        /// With --from-bom, skip passives that use stdlib generics
+        #[arg(long, requires = "from_bom")]
+        skip_generics: bool,
+
         /// Output directory (default: components/JLCPCB/<mpn>/)
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -80,9 +235,105 @@ enum Commands {
         /// Ignore cache, re-fetch pins from EasyEDA
         #[arg(long)]
         refresh: bool,
+
+        /// Generate only from cached part and pin data, without network access
+        #[arg(long, conflicts_with = "refresh")]
+        offline: bool,
+
+        /// For parts EasyEDA has no entry for, write a placeholder .zen with
+        /// stub pins and a TODO instead of failing
+        #[arg(long)]
+        allow_stub: bool,
+
+        /// With --allow-stub, the number of placeholder pins
+        #[arg(long, value_name = "N", default_value = "1", requires = "allow_stub")]
+        stub_pins: usize,
+
+        /// Keep pins in the order EasyEDA lists them instead of sorting by
+        /// number (useful for ribbon and edge connectors)
+        #[arg(long)]
+        preserve_pin_order: bool,
+
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// KiCad major version to target for symbol/footprint files
+        #[arg(long, default_value = "8", value_parser = clap::value_parser!(u8).range(7..=9))]
+        kicad_version: u8,
+
+        /// For passives, skip the part detail request and parse attributes
+        /// from the search description instead
+        #[arg(long)]
+        attributes_from_search: bool,
+
+        /// Directory with custom generic.zen.jinja/component.zen.jinja templates
+        #[arg(long, value_name = "DIR")]
+        template_dir: Option<PathBuf>,
+
+        /// Record generated parts in index.json in the library directory
+        /// (components/JLCPCB/, or --output for batches)
+        #[arg(long)]
+        update_index: bool,
+
+        /// Put the footprint reference on F.Fab only, leaving silkscreen text-free
+        #[arg(long)]
+        fab_only_ref: bool,
+
+        /// Symbol body style: box (filled, 2.54 mm pin grid) or iec (unfilled
+        /// outline, 5.08 mm pin grid)
+        #[arg(long, default_value = "box", value_parser = ["box", "iec"])]
+        symbol_style: String,
+
+        /// Write files directly into --output, without a <mpn>/ subfolder
+        /// per part (single-part output is always flat)
+        #[arg(long, requires = "output")]
+        flat: bool,
+
+        /// Keep pad 1 at the footprint origin for connectors instead of
+        /// centering on the pads, so they line up with a board edge
+        #[arg(long)]
+        no_footprint_recenter_for_connectors: bool,
+
+        /// Also write a README.md with the part's MPN, LCSC code, datasheet and
+        /// EasyEDA links into each component directory (<name>.README.md
+        /// with --flat)
+        #[arg(long)]
+        write_readme: bool,
+
+        /// With --write-readme, replace an existing README
+        #[arg(long, requires = "write_readme")]
+        force_readme: bool,
+
+        /// Write footprints into a JLCPCB.pretty/ KiCad footprint library and
+        /// point the symbol's Footprint property at JLCPCB:<name>
+        #[arg(long)]
+        pretty: bool,
+
+        /// Run this formatter on each generated .zen file, with the file path
+        /// appended (e.g. "pcb fmt"); failures only warn
+        #[arg(long, value_name = "CMD")]
+        format_cmd: Option<String>,
+
+        /// Truncate part descriptions in the .zen file to N characters [default: 100]
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(10..))]
+        desc_length: Option<u64>,
+
+        /// Fail when JLCPCB returns a part code that differs from the requested
+        /// one in case or leading zeros, instead of accepting it
+        #[arg(long)]
+        strict_lcsc: bool,
+
+        /// CSV of `number,name` pairs renaming individual pins after extraction;
+        /// unlisted pins keep their EasyEDA names (single non-passive part only)
+        #[arg(long, value_name = "CSV")]
+        merge_pins: Option<PathBuf>,
     },
 
     /// BOM operations for JLCPCB assembly
+    ///
+    /// .zen BOMs are read with `pcb bom -f json`; set PCB_BIN to use another
+    /// `pcb` binary and PCB_BOM_ARGS to pass it extra arguments.
     Bom {
         #[command(subcommand)]
         command: BomCommands,
@@ -109,6 +360,84 @@ enum UtilCommands {
         #[arg(long)]
         pins: bool,
     },
+
+    /// Copy the part and pin caches to a directory for sharing
+    #[command(long_about = "Copy the part and pin caches to a directory for sharing.\n\n\
+        Writes `parts/` and `pins/` under DIR, e.g. to commit a vetted cache for \
+        CI. Entries already in DIR are only replaced by newer ones.")]
+    ExportCache {
+        /// Directory to export to
+        dir: PathBuf,
+    },
+
+    /// Merge an exported cache directory into the local caches
+    #[command(long_about = "Merge an exported cache directory into the local caches.\n\n\
+        Reads the `parts/` and `pins/` written by `util export-cache`. Local \
+        entries are only replaced by newer ones (pins by extraction time, parts \
+        by file modification time).")]
+    ImportCache {
+        /// Directory written by `util export-cache`
+        dir: PathBuf,
+    },
+
+    /// Sanity-check a .kicad_mod footprint
+    #[command(long_about = "Sanity-check a .kicad_mod footprint.\n\n\
+        Reports the pad count, duplicate pad numbers, zero-size pads, a missing \
+        courtyard and whether the pads are centered on the origin. Exits with an \
+        error when the footprint has no pads or zero-size pads.")]
+    ValidateFootprint {
+        /// Path to the .kicad_mod file
+        file: PathBuf,
+
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
+    },
+
+    /// Generate a component from a saved EasyEDA component JSON
+    #[command(long_about = "Generate a component from a saved EasyEDA component JSON.\n\n\
+        Reads the `result` object of the EasyEDA components API (bare or still \
+        wrapped in the response) and writes the .zen file, symbol and footprint \
+        without any network access. Useful for parts the live API won't serve.")]
+    ImportEasyeda {
+        /// Path to the EasyEDA component JSON
+        file: PathBuf,
+
+        /// LCSC part number (default: taken from the JSON)
+        #[arg(long)]
+        lcsc: Option<String>,
+
+        /// Output directory (default: components/JLCPCB/<mpn>/)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Component name override
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// KiCad major version to target for symbol/footprint files
+        #[arg(long, default_value = "8", value_parser = clap::value_parser!(u8).range(7..=9))]
+        kicad_version: u8,
+
+        /// Directory with custom generic.zen.jinja/component.zen.jinja templates
+        #[arg(long, value_name = "DIR")]
+        template_dir: Option<PathBuf>,
+    },
+
+    /// Diagnose the environment
+    #[command(long_about = "Diagnose the environment.\n\n\
+        Checks that the `pcb` CLI runs (or PCB_BIN), that the JLCPCB search and \
+        EasyEDA APIs answer a lookup, and that the part and pin cache directories \
+        are writable. Exits with an error when any check fails.")]
+    Doctor {
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -121,7 +450,7 @@ enum BomCommands {
         - Extended: part is in the extended library (higher assembly fee)\n  \
         - Missing: part not found in JLCPCB catalog")]
     Check {
-        /// Path to BOM file (.json or .zen)
+        /// Path to BOM file (.json, .csv or .zen), or - for a JSON BOM on stdin
         bom: PathBuf,
 
         /// Quantity of boards to build
@@ -132,13 +461,49 @@ enum BomCommands {
         #[arg(long)]
         include_dnp: bool,
 
-        /// Output format (human, json)
-        #[arg(short, long, default_value = "human")]
-        format: String,
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
 
         /// Bypass the 24-hour part cache
         #[arg(long)]
         refresh: bool,
+
+        /// Check against a stock snapshot CSV (LCSC,Stock[,Price]) instead of the live API
+        #[arg(long, value_name = "CSV")]
+        against: Option<PathBuf>,
+
+        /// Add an LCSC product page link for each resolved part
+        #[arg(long)]
+        show_url: bool,
+
+        /// Use cached parts but never write to the cache (read-only filesystems)
+        #[arg(long)]
+        no_cache_write: bool,
+
+        /// Also write Missing/Limited lines to a CSV for follow-up sourcing
+        #[arg(long, value_name = "CSV")]
+        export_missing: Option<PathBuf>,
+
+        /// Collapse designator lists longer than N into a "first–last (count)" range (0 = never)
+        #[arg(long, value_name = "N", default_value = "3")]
+        collapse_threshold: usize,
+
+        /// Minimum order quantity; smaller requirements are rounded up to it
+        #[arg(long, value_name = "N")]
+        moq: Option<i64>,
+
+        /// Order multiple (e.g. reel size); requirements are rounded up to it
+        #[arg(long, value_name = "N")]
+        multiple: Option<i64>,
+
+        /// Only print the status counts (JSON: a single counts object)
+        #[arg(long)]
+        summary_only: bool,
+
+        /// Suggest a basic part with the same value and package for each Extended line
+        #[arg(long)]
+        recommend_basic: bool,
     },
 
     /// Export BOM in JLCPCB assembly format
@@ -146,7 +511,7 @@ enum BomCommands {
         Generates a CSV file compatible with JLCPCB's SMT assembly service. \
         The CSV includes columns for Comment, Designator, Footprint, and LCSC part number.")]
     Export {
-        /// Path to BOM file (.json or .zen)
+        /// Path to BOM file (.json, .csv or .zen), or - for a JSON BOM on stdin
         bom: PathBuf,
 
         /// Output CSV file path
@@ -157,9 +522,54 @@ enum BomCommands {
         #[arg(long)]
         include_dnp: bool,
 
-        /// Output format (human, json)
-        #[arg(short, long, default_value = "human")]
-        format: String,
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Bypass the 24-hour part cache
+        #[arg(long)]
+        refresh: bool,
+
+        /// CSV of `from,to` pairs renaming designators in the export
+        #[arg(long, value_name = "CSV")]
+        designator_map: Option<PathBuf>,
+
+        /// Comment column template, e.g. "{value} {voltage} {dielectric} {package}".
+        /// Fields: mpn, description, value, package, tolerance, voltage,
+        /// dielectric, manufacturer, lcsc [default: "{mpn} {description}"]
+        #[arg(long, value_name = "TEMPLATE")]
+        comment_format: Option<String>,
+
+        /// Only export parts assembled on this board side (needs a .zen BOM with a layout)
+        #[arg(long, default_value = "both", value_parser = ["top", "bottom", "both"])]
+        side: String,
+
+        /// Add a Quantity column with the number of designators on each line
+        #[arg(long)]
+        with_quantity: bool,
+
+        /// Fail when a BOM footprint's chip size contradicts the LCSC part's
+        /// package (e.g. 0603 on the board, 0402 ordered)
+        #[arg(long)]
+        validate: bool,
+    },
+
+    /// Report the total cost of building a BOM
+    #[command(long_about = "Report the total cost of building a BOM.\n\n\
+        Prints the component cost for all boards, an estimate of the extended part \
+        loading fee ($3 per unique extended part), the total and per-board cost, \
+        and a breakdown by part category. DNP components are skipped.")]
+    Cost {
+        /// Path to BOM file (.json, .csv or .zen), or - for a JSON BOM on stdin
+        bom: PathBuf,
+
+        /// Quantity of boards to build
+        #[arg(short, long, default_value = "100")]
+        quantity: i32,
+
+        /// Output format (human, json) [default: human]
+        #[arg(short, long)]
+        format: Option<String>,
 
         /// Bypass the 24-hour part cache
         #[arg(long)]
@@ -169,21 +579,60 @@ enum BomCommands {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let config = config::Config::load(cli.config.as_deref())?;
+    if let Some(dir) = config.cache_dir.clone() {
+        config::set_cache_dir(dir);
+    }
+    let defaults = http::HttpConfig::default();
+    http::configure(http::HttpConfig {
+        timeout: std::time::Duration::from_secs(config::resolve(
+            cli.timeout,
+            config.timeout,
+            defaults.timeout.as_secs(),
+        )),
+        retries: config::resolve(cli.retries, config.retries, defaults.retries),
+        parts_per_request: config::resolve(
+            cli.parts_per_request,
+            config.parts_per_request,
+            defaults.parts_per_request,
+        ),
+        mpn_results: config::resolve(cli.mpn_results, config.mpn_results, defaults.mpn_results),
+    });
 
     match cli.command {
         Commands::Search {
             query,
             format,
             basic,
+            no_basic,
             preferred,
             limit,
             page,
+            min_price,
+            max_price,
+            require_price,
+            stock_min,
+            price_qty,
+            subcategory,
+            package,
+            tolerance,
+            dielectric,
+            exact,
+            any,
+            wide,
+            compare,
+            save,
+            min_results,
+            category_list,
+            copy,
+            history,
         } => {
-            let output_format = match format.to_lowercase().as_str() {
+            let output_format = match config.format(format).to_lowercase().as_str() {
                 "json" => commands::search::OutputFormat::Json,
                 _ => commands::search::OutputFormat::Human,
             };
 
+            let basic = config::resolve(config::switch(basic, no_basic), config.basic, false);
             let library_type = if basic && preferred {
                 api::LibraryType::BasicAndPreferred
             } else if basic {
@@ -192,18 +641,114 @@ fn main() -> Result<()> {
                 api::LibraryType::All
             };
 
-            commands::search::execute(&query, output_format, library_type, limit, page)
+            if history {
+                return commands::search::execute_history(output_format, cli.json_compact);
+            }
+
+            match query {
+                Some(query) => {
+                    let options = commands::search::SearchOptions {
+                        format: output_format,
+                        library_type,
+                        limit,
+                        page,
+                        filter: commands::search::PartFilter {
+                            min_price,
+                            max_price,
+                            require_price,
+                            stock_min,
+                            price_qty: price_qty.or(config.price_qty),
+                            subcategory,
+                            package,
+                            tolerance,
+                            dielectric,
+                        },
+                        exact,
+                        any,
+                        wide,
+                        json_compact: cli.json_compact,
+                        save,
+                        min_results,
+                        category_list,
+                        copy,
+                    };
+                    commands::search::execute(&query, &options)
+                }
+                None => commands::search::execute_compare(&compare, output_format, cli.json_compact),
+            }
         }
 
         Commands::Generate {
             lcsc,
+            from_bom,
+            from_file,
+            index,
+            all,
+            skip_generics,
             output,
             name,
             refresh,
+            offline,
+            allow_stub,
+            stub_pins,
+            preserve_pin_order,
+            format,
+            kicad_version,
+            attributes_from_search,
+            template_dir,
+            update_index,
+            fab_only_ref,
+            symbol_style,
+            flat,
+            no_footprint_recenter_for_connectors,
+            write_readme,
+            force_readme,
+            pretty,
+            format_cmd,
+            desc_length,
+            strict_lcsc,
+            merge_pins,
         } => {
-            let options = pins::ExtractionOptions { refresh };
+            let options = commands::generate::GenerateOptions {
+                extraction: pins::ExtractionOptions {
+                    refresh,
+                    offline,
+                    stub_pins: allow_stub.then_some(stub_pins),
+                    preserve_order: preserve_pin_order,
+                },
+                kicad_version: easyeda::KicadVersion::from_major(kicad_version)
+                    .unwrap_or_default(),
+                ref_placement: if fab_only_ref {
+                    easyeda::RefPlacement::FabOnly
+                } else {
+                    easyeda::RefPlacement::SilkAndFab
+                },
+                connector_origin_pin1: no_footprint_recenter_for_connectors,
+                symbol_style: easyeda::SymbolStyle::from_name(&symbol_style).unwrap_or_default(),
+                json: config.format(format).eq_ignore_ascii_case("json"),
+                attributes_from_search,
+                template_dir,
+                update_index,
+                flat,
+                write_readme,
+                force_readme,
+                pretty,
+                format_cmd: format_cmd.or(config.format_cmd.clone()),
+                description_length: desc_length.map(|n| n as usize),
+                strict_lcsc,
+                merge_pins: merge_pins
+                    .as_deref()
+                    .map(commands::generate::load_pin_names)
+                    .transpose()?
+                    .unwrap_or_default(),
+                json_compact: cli.json_compact,
+            };
 
-            if lcsc.len() == 1 {
+            if let Some(file) = from_file {
+                commands::generate::execute_from_file(&file, &index, all, output, &options)
+            } else if let Some(bom) = from_bom {
+                commands::generate::execute_from_bom(&bom, output, skip_generics, &options)
+            } else if lcsc.len() == 1 {
                 commands::generate::execute(&lcsc[0], output, name, &options)
             } else {
                 if name.is_some() {
@@ -214,11 +759,81 @@ fn main() -> Result<()> {
         }
 
         Commands::Bom { command } => match command {
-            BomCommands::Check { bom, quantity, include_dnp, format, refresh } => {
-                commands::bom::execute_check(&bom, quantity, include_dnp, format.eq_ignore_ascii_case("json"), refresh)
+            BomCommands::Check {
+                bom,
+                quantity,
+                include_dnp,
+                format,
+                refresh,
+                against,
+                show_url,
+                no_cache_write,
+                export_missing,
+                collapse_threshold,
+                moq,
+                multiple,
+                summary_only,
+                recommend_basic,
+            } => {
+                let options = commands::bom::CheckOptions {
+                    quantity,
+                    include_dnp,
+                    json: config.format(format).eq_ignore_ascii_case("json"),
+                    refresh,
+                    against,
+                    show_url,
+                    no_cache_write,
+                    export_missing,
+                    collapse_threshold,
+                    order: commands::bom::OrderRules { moq, multiple },
+                    summary_only,
+                    json_compact: cli.json_compact,
+                    recommend_basic,
+                };
+                commands::bom::execute_check(&bom, &options)
+            }
+            BomCommands::Export {
+                bom,
+                output,
+                include_dnp,
+                format,
+                refresh,
+                designator_map,
+                comment_format,
+                side,
+                with_quantity,
+                validate,
+            } => {
+                let options = commands::bom::ExportOptions {
+                    include_dnp,
+                    json: config.format(format).eq_ignore_ascii_case("json"),
+                    refresh,
+                    designator_map,
+                    comment_format,
+                    side: match side.as_str() {
+                        "top" => Some(commands::bom::Side::Top),
+                        "bottom" => Some(commands::bom::Side::Bottom),
+                        _ => None,
+                    },
+                    with_quantity,
+                    validate,
+                    json_compact: cli.json_compact,
+                };
+                commands::bom::execute_export(&bom, &output, &options)
             }
-            BomCommands::Export { bom, output, include_dnp, format, refresh } => {
-                commands::bom::execute_export(&bom, &output, include_dnp, format.eq_ignore_ascii_case("json"), refresh)
+            BomCommands::Cost {
+                bom,
+                quantity,
+                format,
+                refresh,
+            } => {
+                let options = commands::bom::CostOptions {
+                    quantity,
+                    json: config.format(format).eq_ignore_ascii_case("json"),
+                    refresh,
+                    json_compact: cli.json_compact,
+                };
+                commands::bom::execute_cost(&bom, &options)
             }
         },
 
@@ -228,6 +843,35 @@ fn main() -> Result<()> {
             UtilCommands::CleanCache { parts, pins } => {
                 commands::util::execute_clean_cache(parts, pins)
             }
+            UtilCommands::ExportCache { dir } => commands::util::execute_export_cache(&dir),
+            UtilCommands::ImportCache { dir } => commands::util::execute_import_cache(&dir),
+            UtilCommands::Doctor { format } => {
+                let json = config.format(format).eq_ignore_ascii_case("json");
+                commands::util::execute_doctor(json, cli.json_compact)
+            }
+            UtilCommands::ValidateFootprint { file, format } => {
+                let json = config.format(format).eq_ignore_ascii_case("json");
+                commands::util::execute_validate_footprint(&file, json, cli.json_compact)
+            }
+            UtilCommands::ImportEasyeda {
+                file,
+                lcsc,
+                output,
+                name,
+                format,
+                kicad_version,
+                template_dir,
+            } => {
+                let options = commands::generate::GenerateOptions {
+                    kicad_version: easyeda::KicadVersion::from_major(kicad_version)
+                        .unwrap_or_default(),
+                    json: config.format(format).eq_ignore_ascii_case("json"),
+                    template_dir,
+                    json_compact: cli.json_compact,
+                    ..Default::default()
+                };
+                commands::util::execute_import_easyeda(&file, lcsc, output, name, &options)
+            }
         },
     }
 }
@@ -0,0 +1,63 @@
+//! Natural ("human") string ordering, so `R2` sorts before `R10`.
+
+use std::cmp::Ordering;
+
+/// Compare strings treating runs of ASCII digits as numbers.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a;
+    let mut b = b;
+
+    loop {
+        match (a.is_empty(), b.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+
+        let (a_chunk, a_rest) = split_chunk(a);
+        let (b_chunk, b_rest) = split_chunk(b);
+        let a_digits = a_chunk.starts_with(|c: char| c.is_ascii_digit());
+        let b_digits = b_chunk.starts_with(|c: char| c.is_ascii_digit());
+
+        let ordering = if a_digits && b_digits {
+            let a_num = a_chunk.trim_start_matches('0');
+            let b_num = b_chunk.trim_start_matches('0');
+            a_num
+                .len()
+                .cmp(&b_num.len())
+                .then_with(|| a_num.cmp(b_num))
+                .then_with(|| a_chunk.len().cmp(&b_chunk.len()))
+        } else {
+            a_chunk.cmp(b_chunk)
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = a_rest;
+        b = b_rest;
+    }
+}
+
+/// Split off the leading run of digits or non-digits.
+fn split_chunk(s: &str) -> (&str, &str) {
+    let digits = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut refs = vec!["R10", "C2", "R2", "R1A", "R1", "U1", "C10"];
+        refs.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(refs, ["C2", "C10", "R1", "R1A", "R2", "R10", "U1"]);
+        assert_eq!(natural_cmp("R1", "R01"), Ordering::Less);
+    }
+}
@@ -10,6 +10,8 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::atomic;
+use crate::config;
 use crate::easyeda::{ComponentMeta, Pin};
 
 /// Cached pin information for a component.
@@ -42,18 +44,16 @@ impl Default for PinCache {
 impl PinCache {
     /// Create a new pin cache manager.
     ///
-    /// Cache location: `~/.pcb/jlcpcb/pins/`
+    /// Cache location: `~/.pcb/jlcpcb/pins/` (or `pins/` under the
+    /// configured cache dir)
     pub fn new() -> Self {
-        let cache_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".pcb")
-            .join("jlcpcb")
-            .join("pins");
+        let cache_dir = config::cache_dir().join("pins");
 
         Self { cache_dir }
     }
 
     /// Create cache with a custom directory (for testing).
+    #[allow(dead_code)]
     pub fn with_dir(cache_dir: PathBuf) -> Self {
         Self { cache_dir }
     }
@@ -104,13 +104,14 @@ impl PinCache {
             .context("Failed to serialize cache data")?;
 
         let path = self.cache_path(lcsc);
-        fs::write(&path, content)
+        atomic::write_file(&path, content)
             .with_context(|| format!("Failed to write cache file: {}", path.display()))?;
 
         Ok(())
     }
 
     /// Remove cached pins for a part.
+    #[allow(dead_code)]
     pub fn remove(&self, lcsc: &str) -> Result<bool> {
         let path = self.cache_path(lcsc);
 
@@ -124,6 +125,7 @@ impl PinCache {
     }
 
     /// Check if a part has cached pins.
+    #[allow(dead_code)]
     pub fn exists(&self, lcsc: &str) -> bool {
         self.cache_path(lcsc).exists()
     }
@@ -197,4 +199,28 @@ mod tests {
         assert!(cache.remove("C123456").unwrap());
         assert!(!cache.exists("C123456"));
     }
+
+    #[test]
+    fn test_save_replaces_partial_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = PinCache::with_dir(temp_dir.path().to_path_buf());
+        let pins = vec![Pin {
+            number: "1".to_string(),
+            name: "VCC".to_string(),
+        }];
+
+        // A run killed mid-write left a truncated file behind
+        fs::write(cache.cache_path("C123456"), r#"{"lcsc": "C12"#).unwrap();
+        assert!(cache.load("C123456").is_err());
+
+        cache.save("C123456", "TEST-PART", &pins, None).unwrap();
+        assert_eq!(cache.load("C123456").unwrap().unwrap().pins.len(), 1);
+
+        // No temp files are left next to the cache entry
+        let names: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["C123456.json"]);
+    }
 }
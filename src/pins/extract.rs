@@ -1,17 +1,37 @@
 //! Pin extraction from EasyEDA library.
 
-use anyhow::{Context, Result};
-use colored::Colorize;
+use anyhow::Result;
 
 use super::cache::PinCache;
 use crate::api::JlcPart;
-use crate::easyeda::{parse_symbol_pins, ComponentMeta, EasyEdaClient, Pin};
+use crate::easyeda::{
+    parse_footprint_shapes, parse_symbol_pins, ComponentMeta, EasyEdaClient, Pin,
+};
+
+/// A source of pins for a component, returning `None` when it has nothing.
+/// The flag keeps pins in listed order instead of sorting them by number.
+type PinSource = fn(&ComponentMeta, bool) -> Option<Vec<Pin>>;
+
+/// Pin source used when EasyEDA has no symbol pins; names are pad numbers.
+pub const FOOTPRINT_PAD_SOURCE: &str = "footprint pads";
+
+/// Pin sources tried in order; the first one that yields pins wins.
+const PIN_SOURCES: [(&str, PinSource); 2] = [
+    ("EasyEDA symbol", pins_from_symbol),
+    (FOOTPRINT_PAD_SOURCE, pins_from_footprint),
+];
 
 /// Options for pin extraction.
 #[derive(Debug, Clone, Default)]
 pub struct ExtractionOptions {
     /// Ignore cache, re-fetch pins from EasyEDA
     pub refresh: bool,
+    /// Only use cached pins; never contact EasyEDA
+    pub offline: bool,
+    /// Placeholder pin count for parts EasyEDA doesn't know (`--allow-stub`)
+    pub stub_pins: Option<usize>,
+    /// Keep pins in EasyEDA's listed order instead of sorting by number
+    pub preserve_order: bool,
 }
 
 /// Result of pin extraction including metadata.
@@ -21,74 +41,240 @@ pub struct ExtractionResult {
     pub pins: Vec<Pin>,
     /// Component metadata (footprint, 3D model, etc.).
     pub meta: ComponentMeta,
+    /// Whether the pins are placeholders for a part missing from EasyEDA.
+    pub stub: bool,
+    /// Which of [`PIN_SOURCES`] the pins came from; `None` for stubs.
+    pub pin_source: Option<&'static str>,
+    /// Problems that didn't stop the extraction, for the caller to report.
+    pub warnings: Vec<String>,
 }
 
 /// Extract pins for a component from EasyEDA library.
 ///
 /// Flow:
 /// 1. Check cache (unless --refresh)
-/// 2. Fetch from EasyEDA API
-/// 3. Cache the result
+/// 2. Fetch from EasyEDA API (an error with --offline); a part EasyEDA
+///    doesn't know gets placeholder pins when `stub_pins` is set
+/// 3. Take pins from the first of [`PIN_SOURCES`] that has any
+/// 4. Cache the result
 pub fn extract_pins(part: &JlcPart, options: &ExtractionOptions) -> Result<ExtractionResult> {
     let cache = PinCache::new();
 
     // Check cache first (unless refresh requested)
     if !options.refresh {
         if let Some(cached) = cache.load(&part.lcsc)? {
-            eprintln!(
-                "  {} Using cached pins for {}",
-                "→".cyan(),
-                part.lcsc.green()
-            );
+            let meta = cached.meta.unwrap_or_default();
+            // The cache holds sorted pins; the listed order comes from the shapes
+            let from_shapes = pins_from_meta(&meta, options.preserve_order);
+            let pin_source = from_shapes.as_ref().map(|(source, _)| *source);
+            let pins = match from_shapes {
+                Some((_, pins)) if options.preserve_order => pins,
+                _ => cached.pins,
+            };
             return Ok(ExtractionResult {
-                pins: cached.pins,
-                meta: cached.meta.unwrap_or_default(),
+                pins,
+                meta,
+                stub: false,
+                pin_source,
+                warnings: Vec::new(),
             });
         }
     }
 
+    if options.offline {
+        anyhow::bail!(
+            "Pins for {} ({}) are not cached\n\n\
+            Run `generate {}` once without --offline to fetch them.",
+            part.lcsc,
+            part.mpn,
+            part.lcsc
+        );
+    }
+
     // Fetch from EasyEDA API
-    let result = extract_via_easyeda(part)?;
+    let Some(meta) = fetch_easyeda_meta(part, &EasyEdaClient::new()?)? else {
+        let Some(count) = options.stub_pins else {
+            anyhow::bail!(
+                "Component {} ({}) not found in EasyEDA\n\n\
+                Pass --allow-stub to generate a placeholder .zen to fill in by hand.",
+                part.lcsc,
+                part.mpn
+            );
+        };
+        return Ok(ExtractionResult {
+            pins: stub_pins(count),
+            meta: ComponentMeta::default(),
+            stub: true,
+            pin_source: None,
+            warnings: Vec::new(),
+        });
+    };
 
-    if result.pins.is_empty() {
+    let Some((source, pins)) = pins_from_meta(&meta, options.preserve_order) else {
         anyhow::bail!(
             "No pin information found for {} ({})\n\n\
-            The component may not have a symbol in the EasyEDA library.",
+            The component may not have a symbol or footprint in the EasyEDA library.",
             part.lcsc,
             part.mpn
         );
-    }
+    };
 
-    eprintln!(
-        "  {} Extracted {} pins from EasyEDA library",
-        "✓".green(),
-        result.pins.len()
-    );
+    let mut result = ExtractionResult {
+        pins,
+        meta,
+        stub: false,
+        pin_source: Some(source),
+        warnings: Vec::new(),
+    };
 
-    // Cache the result
-    if let Err(e) = cache.save(&part.lcsc, &part.mpn, &result.pins, Some(&result.meta)) {
-        eprintln!("  {} Failed to cache pins: {}", "!".yellow(), e);
+    // Cache the result, always in sorted order
+    let sorted = if options.preserve_order {
+        pins_from_meta(&result.meta, false).map_or_else(Vec::new, |(_, pins)| pins)
+    } else {
+        result.pins.clone()
+    };
+    if let Err(e) = cache.save(&part.lcsc, &part.mpn, &sorted, Some(&result.meta)) {
+        result.warnings.push(format!("Failed to cache pins: {:#}", e));
     }
 
     Ok(result)
 }
 
-/// Extract pins from EasyEDA library.
-fn extract_via_easyeda(part: &JlcPart) -> Result<ExtractionResult> {
-    let easyeda = EasyEdaClient::new()?;
+/// Fetch a part's EasyEDA data into the pin cache without printing anything.
+///
+/// Lets a batch warm the cache for many parts in parallel before generating
+/// them one by one. Parts already cached (unless `refresh`) are skipped, as
+/// is everything `--offline`; a part EasyEDA doesn't know is left for
+/// [`extract_pins`] to report. `easyeda` can be shared by the threads.
+pub fn prefetch_pins(
+    part: &JlcPart,
+    options: &ExtractionOptions,
+    easyeda: &EasyEdaClient,
+) -> Result<()> {
+    let cache = PinCache::new();
+    if options.offline || (!options.refresh && cache.load(&part.lcsc)?.is_some()) {
+        return Ok(());
+    }
+
+    let Some(meta) = fetch_easyeda_meta(part, easyeda)? else {
+        return Ok(());
+    };
+    if let Some((_, pins)) = pins_from_meta(&meta, false) {
+        cache.save(&part.lcsc, &part.mpn, &pins, Some(&meta))?;
+    }
+    Ok(())
+}
+
+/// Fetch component metadata (symbol and footprint shapes) from EasyEDA.
+///
+/// Returns `None` when EasyEDA has no entry for the part.
+fn fetch_easyeda_meta(part: &JlcPart, easyeda: &EasyEdaClient) -> Result<Option<ComponentMeta>> {
+    let component = easyeda.get_component(&part.lcsc)?;
+    Ok(component.as_ref().map(ComponentMeta::from_component_data))
+}
+
+/// Numbered placeholder pins (`P1`, `P2`, ...), at least one.
+fn stub_pins(count: usize) -> Vec<Pin> {
+    (1..=count.max(1))
+        .map(|n| Pin {
+            number: n.to_string(),
+            name: format!("P{}", n),
+        })
+        .collect()
+}
+
+/// Pins from the first of [`PIN_SOURCES`] that has any, with the source name.
+pub fn pins_from_meta(
+    meta: &ComponentMeta,
+    preserve_order: bool,
+) -> Option<(&'static str, Vec<Pin>)> {
+    first_pins(meta, &PIN_SOURCES, preserve_order)
+}
+
+/// Try each pin source in order, returning the first non-empty result.
+fn first_pins<'a>(
+    meta: &ComponentMeta,
+    sources: &[(&'a str, PinSource)],
+    preserve_order: bool,
+) -> Option<(&'a str, Vec<Pin>)> {
+    sources.iter().find_map(|(name, source)| {
+        source(meta, preserve_order)
+            .filter(|pins| !pins.is_empty())
+            .map(|pins| (*name, pins))
+    })
+}
+
+/// Pins from the EasyEDA schematic symbol.
+fn pins_from_symbol(meta: &ComponentMeta, preserve_order: bool) -> Option<Vec<Pin>> {
+    Some(parse_symbol_pins(&meta.symbol_shapes, preserve_order))
+}
+
+/// Pins derived from footprint pad numbers, named after the pad.
+fn pins_from_footprint(meta: &ComponentMeta, preserve_order: bool) -> Option<Vec<Pin>> {
+    let (pads, _) = parse_footprint_shapes(&meta.footprint_shapes, preserve_order);
+
+    let mut pins: Vec<Pin> = Vec::new();
+    for pad in pads {
+        if !pins.iter().any(|p| p.number == pad.number) {
+            pins.push(Pin {
+                name: pad.number.clone(),
+                number: pad.number,
+            });
+        }
+    }
+    Some(pins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_pins(_: &ComponentMeta, _: bool) -> Option<Vec<Pin>> {
+        None
+    }
 
-    let component = easyeda
-        .get_component(&part.lcsc)?
-        .context("Component not found in EasyEDA")?;
+    fn empty_pins(_: &ComponentMeta, _: bool) -> Option<Vec<Pin>> {
+        Some(Vec::new())
+    }
 
-    let meta = ComponentMeta::from_component_data(&component);
+    fn one_pin(_: &ComponentMeta, _: bool) -> Option<Vec<Pin>> {
+        Some(vec![Pin {
+            number: "1".to_string(),
+            name: "VCC".to_string(),
+        }])
+    }
 
-    let shapes = component
-        .data_str
-        .and_then(|d| d.shape)
-        .unwrap_or_default();
+    #[test]
+    fn test_first_pins_skips_empty_sources() {
+        let meta = ComponentMeta::default();
+        let sources: [(&str, PinSource); 3] =
+            [("none", no_pins), ("empty", empty_pins), ("one", one_pin)];
 
-    let pins = parse_symbol_pins(&shapes);
+        let (source, pins) = first_pins(&meta, &sources, false).unwrap();
+        assert_eq!(source, "one");
+        assert_eq!(pins[0].name, "VCC");
 
-    Ok(ExtractionResult { pins, meta })
+        assert!(first_pins(&meta, &sources[..2], false).is_none());
+    }
+
+    #[test]
+    fn test_pins_fall_back_to_footprint_pads() {
+        let meta = ComponentMeta {
+            footprint_shapes: vec![
+                "PAD~RECT~4000~3000~6~6~1~~2~0~~0~pad2".to_string(),
+                "PAD~RECT~4010~3000~6~6~1~~1~0~~0~pad1".to_string(),
+                "PAD~RECT~4020~3000~6~6~1~~1~0~~0~pad1b".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let (source, pins) = first_pins(&meta, &PIN_SOURCES, false).unwrap();
+        assert_eq!(source, "footprint pads");
+        let numbers: Vec<&str> = pins.iter().map(|p| p.number.as_str()).collect();
+        assert_eq!(numbers, ["1", "2"]);
+
+        let (_, pins) = first_pins(&meta, &PIN_SOURCES, true).unwrap();
+        let numbers: Vec<&str> = pins.iter().map(|p| p.number.as_str()).collect();
+        assert_eq!(numbers, ["2", "1"]);
+    }
 }
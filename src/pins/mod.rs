@@ -7,4 +7,7 @@
 pub(crate) mod cache;
 mod extract;
 
-pub use extract::{extract_pins, ExtractionOptions};
+pub use extract::{
+    extract_pins, pins_from_meta, prefetch_pins, ExtractionOptions, ExtractionResult,
+    FOOTPRINT_PAD_SOURCE,
+};
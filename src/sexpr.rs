@@ -0,0 +1,166 @@
+//! Minimal S-expression reader for KiCad files.
+//!
+//! Only what's needed to inspect `.kicad_mod`/`.kicad_sym` content: lists,
+//! bare atoms and quoted strings (with `\` escapes).
+
+use anyhow::{bail, Result};
+
+/// A parsed S-expression node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexpr {
+    /// Bare or quoted atom, with quotes removed
+    Atom(String),
+    /// Parenthesized list
+    List(Vec<Sexpr>),
+}
+
+impl Sexpr {
+    /// The atom's text, if this is an atom.
+    pub fn as_atom(&self) -> Option<&str> {
+        match self {
+            Sexpr::Atom(s) => Some(s),
+            Sexpr::List(_) => None,
+        }
+    }
+
+    /// The list's head atom (e.g. `pad` for `(pad "1" smd ...)`).
+    pub fn head(&self) -> Option<&str> {
+        match self {
+            Sexpr::List(items) => items.first()?.as_atom(),
+            Sexpr::Atom(_) => None,
+        }
+    }
+
+    /// Items of a list, or an empty slice for atoms.
+    pub fn items(&self) -> &[Sexpr] {
+        match self {
+            Sexpr::List(items) => items,
+            Sexpr::Atom(_) => &[],
+        }
+    }
+
+    /// First child list with the given head.
+    pub fn child(&self, head: &str) -> Option<&Sexpr> {
+        self.items().iter().find(|c| c.head() == Some(head))
+    }
+
+    /// All child lists with the given head.
+    pub fn children<'a>(&'a self, head: &'a str) -> impl Iterator<Item = &'a Sexpr> + 'a {
+        self.items().iter().filter(move |c| c.head() == Some(head))
+    }
+
+    /// Parse the atoms after the head as numbers (e.g. `(at 1.5 -2 90)`).
+    pub fn numbers(&self) -> Vec<f64> {
+        self.items()
+            .iter()
+            .skip(1)
+            .filter_map(|c| c.as_atom()?.parse().ok())
+            .collect()
+    }
+}
+
+/// Parse a single top-level S-expression.
+pub fn parse(input: &str) -> Result<Sexpr> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let expr = parser.parse_expr()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        bail!("Unexpected content after top-level expression");
+    }
+    Ok(expr)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Sexpr> {
+        match self.chars.get(self.pos) {
+            None => bail!("Unexpected end of input"),
+            Some('(') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        None => bail!("Unclosed '('"),
+                        Some(')') => {
+                            self.pos += 1;
+                            return Ok(Sexpr::List(items));
+                        }
+                        Some(_) => items.push(self.parse_expr()?),
+                    }
+                }
+            }
+            Some(')') => bail!("Unexpected ')' at offset {}", self.pos),
+            Some('"') => {
+                self.pos += 1;
+                let mut s = String::new();
+                loop {
+                    match self.chars.get(self.pos) {
+                        None => bail!("Unterminated string"),
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(Sexpr::Atom(s));
+                        }
+                        Some('\\') => {
+                            if let Some(&c) = self.chars.get(self.pos + 1) {
+                                s.push(c);
+                            }
+                            self.pos += 2;
+                        }
+                        Some(&c) => {
+                            s.push(c);
+                            self.pos += 1;
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|&c| !c.is_whitespace() && c != '(' && c != ')' && c != '"')
+                {
+                    self.pos += 1;
+                }
+                Ok(Sexpr::Atom(self.chars[start..self.pos].iter().collect()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_footprint_fragment() {
+        let expr =
+            parse(r#"(footprint "R_0402" (pad "1" smd rect (at -0.5 0 90) (layers "F.Cu")))"#)
+                .unwrap();
+        assert_eq!(expr.head(), Some("footprint"));
+        let pad = expr.child("pad").unwrap();
+        assert_eq!(pad.items()[1].as_atom(), Some("1"));
+        assert_eq!(pad.child("at").unwrap().numbers(), vec![-0.5, 0.0, 90.0]);
+
+        assert!(parse("(a (b)").is_err());
+        assert_eq!(
+            parse(r#"("a \"b\" (c)")"#).unwrap().items()[0].as_atom(),
+            Some("a \"b\" (c)")
+        );
+    }
+}